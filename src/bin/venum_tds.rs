@@ -0,0 +1,372 @@
+use std::{
+    collections::HashMap,
+    fs::File,
+    io::{self, BufWriter, Write},
+    path::PathBuf,
+    process::ExitCode,
+};
+
+use clap::{Parser, ValueEnum};
+
+use venum_tds::{
+    conf::{json_schema::config_schema_json, jsonconf::ConfigRoot},
+    data_cell_row::DataCellRow,
+    data_cell_table::DataCellTable,
+    errors::{IoErrors, Result, VenumTdsError},
+    io::{
+        csv::{CsvOptions, CsvRowReader, CsvRowWriter},
+        ndjson::NdjsonRowWriter,
+    },
+    render::RowRenderer,
+    transform::data_cell_row::transrich_pass::TransrichPasses,
+    value_fmt::value_to_string,
+};
+
+/// How many rows we collect, before we print them as one table in a dry-run.
+const DRY_RUN_TABLE_ROWS: usize = 20;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum OutputFormat {
+    Csv,
+    Ndjson,
+}
+
+/// Applies a transrichment config (json, yaml or toml) to a csv file and writes the result as csv or ndjson.
+///
+/// Exits with 0 on success, 1 on a fatal error and 2 if some rows failed (only with --errors-to).
+#[derive(Debug, Parser)]
+#[command(name = "venum_tds", version, about)]
+struct Args {
+    /// The input csv file
+    #[arg(required_unless_present = "print_schema")]
+    input: Option<PathBuf>,
+
+    /// The transrichment config (json, or yaml/toml by extension), see JSONCONF.md
+    #[arg(short, long, required_unless_present = "print_schema")]
+    config: Option<PathBuf>,
+
+    /// Metadata for `meta` addItem specs, as key=value. Can be given multiple times.
+    #[arg(short, long = "meta", value_parser = parse_key_val)]
+    meta: Vec<(String, String)>,
+
+    /// Where to write the result to. Defaults to stdout.
+    #[arg(short, long)]
+    output: Option<PathBuf>,
+
+    /// Output format
+    #[arg(short, long, value_enum, default_value_t = OutputFormat::Csv)]
+    format: OutputFormat,
+
+    /// Field delimiter of the input (and of the csv output)
+    #[arg(short, long, default_value_t = ',')]
+    delimiter: char,
+
+    /// The input has no header line
+    #[arg(long)]
+    no_headers: bool,
+
+    /// Only process the first N rows
+    #[arg(short, long)]
+    limit: Option<usize>,
+
+    /// Don't write any output, print the transrichted rows as (text) tables to stdout instead
+    #[arg(long)]
+    dry_run: bool,
+
+    /// Write rows that fail to read or to transrich into this (csv) file and carry on, instead of
+    /// aborting. It has the columns `row` (the number of the row), `error` and `record` (the row,
+    /// as read, in a single column)
+    #[arg(long)]
+    errors_to: Option<PathBuf>,
+
+    /// Print the JSON Schema of the config (e.g. for editors) and exit
+    #[arg(long, exclusive = true)]
+    print_schema: bool,
+}
+
+fn parse_key_val(s: &str) -> std::result::Result<(String, String), String> {
+    s.split_once('=')
+        .map(|(k, v)| (String::from(k), String::from(v)))
+        .ok_or_else(|| format!("invalid key=value: no '=' found in '{}'", s))
+}
+
+/// The original row as a single csv record (with the input delimiter), so that it fits into one
+/// column of the error file, no matter how many fields it has.
+fn row_to_record(row: &DataCellRow, delimiter: u8) -> Result<String> {
+    let csv_err = |e: &dyn std::fmt::Display| {
+        VenumTdsError::Io(IoErrors::Csv {
+            msg: format!("{}", e),
+        })
+    };
+    let mut w = csv::WriterBuilder::new()
+        .delimiter(delimiter)
+        .terminator(csv::Terminator::Any(b'\n'))
+        .from_writer(Vec::new());
+    w.write_record(row.iter_sorted().map(|dc| value_to_string(dc.get_data())))
+        .map_err(|e| csv_err(&e))?;
+    let mut record = w.into_inner().map_err(|e| csv_err(&e))?;
+    record.pop(); // the terminator
+    String::from_utf8(record).map_err(|e| csv_err(&e))
+}
+
+fn io_err<E: std::fmt::Display>(path: &PathBuf, e: E) -> VenumTdsError {
+    VenumTdsError::Io(IoErrors::File {
+        path: path.display().to_string(),
+        msg: format!("{}", e),
+    })
+}
+
+enum RowSink {
+    Csv(CsvRowWriter<Box<dyn Write>>),
+    Ndjson(NdjsonRowWriter<Box<dyn Write>>),
+    DryRun(Vec<DataCellRow>),
+}
+
+impl RowSink {
+    fn write(&mut self, row: DataCellRow) -> Result<()> {
+        match self {
+            RowSink::Csv(w) => w.write(&row),
+            RowSink::Ndjson(w) => w.write(&row),
+            RowSink::DryRun(buf) => {
+                buf.push(row);
+                if buf.len() >= DRY_RUN_TABLE_ROWS {
+                    println!("{}", RowRenderer::default().render_rows(buf));
+                    buf.clear();
+                }
+                Ok(())
+            }
+        }
+    }
+
+    fn finish(&mut self) -> Result<()> {
+        match self {
+            RowSink::Csv(w) => w.flush(),
+            RowSink::Ndjson(w) => w.flush(),
+            RowSink::DryRun(buf) => {
+                if !buf.is_empty() {
+                    println!("{}", RowRenderer::default().render_rows(buf));
+                    buf.clear();
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+fn run(args: &Args) -> Result<usize> {
+    if args.print_schema {
+        println!("{}", config_schema_json()?);
+        return Ok(0);
+    }
+    // (clap makes sure, that we have both, without --print-schema)
+    let (Some(input), Some(config)) = (&args.input, &args.config) else {
+        return Err(VenumTdsError::Generic {
+            msg: String::from("the input and --config are required"),
+        });
+    };
+
+    if !args.delimiter.is_ascii() {
+        return Err(VenumTdsError::Generic {
+            msg: format!("delimiter must be an ascii char, got '{}'", args.delimiter),
+        });
+    }
+    let csv_opts = CsvOptions {
+        delimiter: args.delimiter as u8,
+        has_headers: !args.no_headers,
+    };
+
+    let config = ConfigRoot::from_path(config)?;
+    let meta: HashMap<String, String> = args.meta.iter().cloned().collect();
+    let mut passes = TransrichPasses::try_from((&config, Some(&meta)))?;
+
+    if passes.is_table() && args.errors_to.is_some() {
+        return Err(VenumTdsError::Generic {
+            msg: String::from("--errors-to is not supported with table transformers"),
+        });
+    }
+
+    let reader = CsvRowReader::from_path(input, &csv_opts)?;
+
+    let mut sink = if args.dry_run {
+        RowSink::DryRun(Vec::with_capacity(DRY_RUN_TABLE_ROWS))
+    } else {
+        let out: Box<dyn Write> = match &args.output {
+            Some(path) => Box::new(BufWriter::new(
+                File::create(path).map_err(|e| io_err(path, e))?,
+            )),
+            None => Box::new(BufWriter::new(io::stdout())),
+        };
+        match args.format {
+            OutputFormat::Csv => RowSink::Csv(CsvRowWriter::from_writer(out, &csv_opts)),
+            OutputFormat::Ndjson => RowSink::Ndjson(NdjsonRowWriter::new(out)),
+        }
+    };
+
+    let mut error_sink = match &args.errors_to {
+        Some(path) => {
+            let mut w = CsvRowWriter::from_path(path, &csv_opts)?;
+            w.write_record(["row", "error", "record"])?;
+            Some(w)
+        }
+        None => None,
+    };
+
+    let (mut num_read, mut num_failed) = (0_usize, 0_usize);
+    let rows = reader.take(args.limit.unwrap_or(usize::MAX));
+
+    if passes.is_table() {
+        // Table transformers need all rows at once, so we have to read them all first
+        let table = rows.collect::<Result<DataCellTable>>()?;
+        num_read = table.len();
+        for row in passes.transrich_table(table)? {
+            sink.write(row)?;
+        }
+    } else {
+        for row in rows {
+            num_read += 1;
+            let row = match (row, &mut error_sink) {
+                (Ok(row), _) => row,
+                // (the reader can't give us the record, if it can't read it)
+                (Err(e), Some(w)) => {
+                    num_failed += 1;
+                    w.write_record([num_read.to_string(), format!("{:?}", e), String::new()])?;
+                    continue;
+                }
+                (Err(e), None) => return Err(e),
+            };
+
+            // We only need the original row, if we want to report it
+            let original = error_sink.as_ref().map(|_| row.clone());
+
+            // (exploding passes can turn a row into zero or many rows)
+            match passes.transrich_rows(row) {
+                Ok(rows) => {
+                    for row in rows {
+                        sink.write(row)?;
+                    }
+                }
+                Err(e) => match (&mut error_sink, original) {
+                    (Some(w), Some(orig)) => {
+                        num_failed += 1;
+                        w.write_record([
+                            num_read.to_string(),
+                            format!("{:?}", e),
+                            row_to_record(&orig, csv_opts.delimiter)?,
+                        ])?;
+                    }
+                    _ => {
+                        return Err(VenumTdsError::Generic {
+                            msg: format!("row {}: {:?}", num_read, e),
+                        })
+                    }
+                },
+            }
+        }
+    }
+
+    sink.finish()?;
+    if let Some(w) = &mut error_sink {
+        w.flush()?;
+    }
+
+    for report in passes.validation_reports() {
+        eprint!("{}", report);
+    }
+
+    eprintln!(
+        "rows read: {}, ok: {}, failed: {}",
+        num_read,
+        num_read - num_failed,
+        num_failed
+    );
+    Ok(num_failed)
+}
+
+fn main() -> ExitCode {
+    let args = Args::parse();
+    match run(&args) {
+        Ok(0) => ExitCode::SUCCESS,
+        Ok(_) => ExitCode::from(2),
+        Err(e) => {
+            eprintln!("error: {:?}", e);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::*;
+
+    #[test]
+    fn csv_to_ndjson() {
+        let dir = tempfile::tempdir().unwrap();
+        let input = dir.path().join("in.csv");
+        let config = dir.path().join("config.json");
+        let output = dir.path().join("out.ndjson");
+        fs::write(&input, "name,id,junk\nb,1,x\na,,y\n").unwrap();
+        fs::write(
+            &config,
+            r#"[{ "transformers": [{ "type": "deleteItems", "cfg": [2] }] }]"#,
+        )
+        .unwrap();
+
+        let args = Args::try_parse_from([
+            "venum_tds",
+            input.to_str().unwrap(),
+            "--config",
+            config.to_str().unwrap(),
+            "--format",
+            "ndjson",
+            "--output",
+            output.to_str().unwrap(),
+        ])
+        .unwrap();
+        assert_eq!(0, run(&args).unwrap());
+        assert_eq!(
+            "{\"name\":\"b\",\"id\":\"1\"}\n{\"name\":\"a\",\"id\":null}\n",
+            fs::read_to_string(&output).unwrap()
+        );
+    }
+
+    #[test]
+    fn errors_to() {
+        let dir = tempfile::tempdir().unwrap();
+        let input = dir.path().join("in.csv");
+        let config = dir.path().join("config.json");
+        let errors = dir.path().join("errors.csv");
+        // the third row isn't valid utf-8, so it can't be read
+        fs::write(&input, b"id,name\n1,a-x\n2,b\n\xff,c\n").unwrap();
+        // "b" can't be split
+        fs::write(
+            &config,
+            r#"[{ "transformers": [{ "type": "splitItem", "cfg": { "idx": 1, "spec": { "name": "separatorChar", "char": "-" }, "deleteAfterSplit": true, "targetLeft": { "idx": 2, "targetType": "String" }, "targetRight": { "idx": 3, "targetType": "String" } } }] }]"#,
+        )
+        .unwrap();
+
+        let args = Args::try_parse_from([
+            "venum_tds",
+            input.to_str().unwrap(),
+            "--config",
+            config.to_str().unwrap(),
+            "--output",
+            dir.path().join("out.csv").to_str().unwrap(),
+            "--errors-to",
+            errors.to_str().unwrap(),
+        ])
+        .unwrap();
+        assert_eq!(2, run(&args).unwrap());
+
+        let mut rdr = csv::Reader::from_path(&errors).unwrap();
+        assert_eq!(
+            vec!["row", "error", "record"],
+            rdr.headers().unwrap().iter().collect::<Vec<&str>>()
+        );
+        let records: Vec<csv::StringRecord> = rdr.records().map(|r| r.unwrap()).collect();
+        assert_eq!(2, records.len());
+        assert_eq!(vec!["2", "2,b"], vec![&records[0][0], &records[0][2]]);
+        assert_eq!(vec!["3", ""], vec![&records[1][0], &records[1][2]]);
+    }
+}
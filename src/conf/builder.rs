@@ -0,0 +1,243 @@
+use venum::value_type::ValueType;
+
+use super::jsonconf::{
+    AddItemConfig, ConfigRoot, DedupConfig, DedupStreamConfig, ExplodeItemConfig, GroupByConfig,
+    ItemTargetConfig, LookupEnrichConfig, MapValuesConfig, MeltConfig, ModifyItemConfig,
+    OrderItemsEntry, OrderItemsOptions, PivotConfig, RoundItemConfig, SortConfig, SplitItemConfig,
+    TableTransformerConfig, TransformEnrichPassConfig, TransformerConfig, UnpivotConfig,
+    ValidateItemsConfig, WhenConfig,
+};
+
+use crate::schema::Schema;
+
+impl ItemTargetConfig {
+    pub fn new(idx: usize, target_type: ValueType) -> Self {
+        Self {
+            idx,
+            header: None,
+            target_type,
+        }
+    }
+
+    pub fn with_header<S: Into<String>>(mut self, header: S) -> Self {
+        self.header = Some(header.into());
+        self
+    }
+}
+
+impl ConfigRoot {
+    /// Builds a config in code, instead of writing it by hand, e.g.
+    /// `ConfigRoot::builder().pass(|p| p.delete_items([0, 1]).order_item(3, 0)).build()`.
+    pub fn builder() -> ConfigRootBuilder {
+        ConfigRootBuilder::default()
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct ConfigRootBuilder {
+    passes: Vec<TransformEnrichPassConfig>,
+}
+
+impl ConfigRootBuilder {
+    /// Adds a pass, which is built by `f`.
+    pub fn pass<F>(mut self, f: F) -> Self
+    where
+        F: FnOnce(PassBuilder) -> PassBuilder,
+    {
+        self.passes.push(f(PassBuilder::default()).build());
+        self
+    }
+
+    pub fn build(self) -> ConfigRoot {
+        ConfigRoot(self.passes)
+    }
+}
+
+/// Builds a `TransformEnrichPassConfig`, see `ConfigRootBuilder::pass`. The transformers (and
+/// table transformers) are added in order.
+#[derive(Debug, Default)]
+pub struct PassBuilder {
+    pass: TransformEnrichPassConfig,
+}
+
+impl PassBuilder {
+    pub fn comment<S: Into<String>>(mut self, comment: S) -> Self {
+        self.pass.comment = Some(comment.into());
+        self
+    }
+
+    /// See `TransformEnrichPassConfig::strict`.
+    pub fn strict(mut self, strict: bool) -> Self {
+        self.pass.strict = Some(strict);
+        self
+    }
+
+    pub fn transformer(mut self, transformer: TransformerConfig) -> Self {
+        self.pass.transformers.push(transformer);
+        self
+    }
+
+    pub fn delete_items<I: IntoIterator<Item = usize>>(self, idxs: I) -> Self {
+        self.transformer(TransformerConfig::DeleteItems {
+            cfg: idxs.into_iter().collect(),
+        })
+    }
+
+    pub fn split_item(self, cfg: SplitItemConfig) -> Self {
+        self.transformer(TransformerConfig::SplitItem { cfg })
+    }
+
+    pub fn add_item(self, cfg: AddItemConfig) -> Self {
+        self.transformer(TransformerConfig::AddItem { cfg })
+    }
+
+    pub fn assert_schema(self, cfg: Schema) -> Self {
+        self.transformer(TransformerConfig::AssertSchema { cfg })
+    }
+
+    pub fn validate_items(self, cfg: ValidateItemsConfig) -> Self {
+        self.transformer(TransformerConfig::ValidateItems { cfg })
+    }
+
+    pub fn map_values(self, cfg: MapValuesConfig) -> Self {
+        self.transformer(TransformerConfig::MapValues { cfg })
+    }
+
+    pub fn modify_item(self, cfg: ModifyItemConfig) -> Self {
+        self.transformer(TransformerConfig::ModifyItem { cfg })
+    }
+
+    pub fn round_item(self, cfg: RoundItemConfig) -> Self {
+        self.transformer(TransformerConfig::RoundItem { cfg })
+    }
+
+    pub fn when(self, cfg: WhenConfig) -> Self {
+        self.transformer(TransformerConfig::When { cfg })
+    }
+
+    pub fn explode_item(self, cfg: ExplodeItemConfig) -> Self {
+        self.transformer(TransformerConfig::ExplodeItem { cfg })
+    }
+
+    pub fn unpivot(self, cfg: UnpivotConfig) -> Self {
+        self.transformer(TransformerConfig::Unpivot { cfg })
+    }
+
+    pub fn lookup_enrich(self, cfg: LookupEnrichConfig) -> Self {
+        self.transformer(TransformerConfig::LookupEnrich { cfg })
+    }
+
+    pub fn dedup_stream(self, cfg: DedupStreamConfig) -> Self {
+        self.transformer(TransformerConfig::DedupStream { cfg })
+    }
+
+    /// Re-assigns the idx `from` to `to`, after the transformers (see `orderItems`).
+    pub fn order_item(mut self, from: usize, to: usize) -> Self {
+        self.pass
+            .order_items
+            .get_or_insert_with(Vec::new)
+            .push(OrderItemsEntry { from, to });
+        self
+    }
+
+    pub fn order_items_options(mut self, options: OrderItemsOptions) -> Self {
+        self.pass.order_items_options = Some(options);
+        self
+    }
+
+    pub fn table_transformer(mut self, table_transformer: TableTransformerConfig) -> Self {
+        self.pass
+            .table_transformers
+            .get_or_insert_with(Vec::new)
+            .push(table_transformer);
+        self
+    }
+
+    pub fn melt(self, cfg: MeltConfig) -> Self {
+        self.table_transformer(TableTransformerConfig::Melt { cfg })
+    }
+
+    pub fn pivot(self, cfg: PivotConfig) -> Self {
+        self.table_transformer(TableTransformerConfig::Pivot { cfg })
+    }
+
+    pub fn group_by(self, cfg: GroupByConfig) -> Self {
+        self.table_transformer(TableTransformerConfig::GroupBy { cfg })
+    }
+
+    pub fn sort(self, cfg: SortConfig) -> Self {
+        self.table_transformer(TableTransformerConfig::Sort { cfg })
+    }
+
+    pub fn dedup(self, cfg: DedupConfig) -> Self {
+        self.table_transformer(TableTransformerConfig::Dedup { cfg })
+    }
+
+    pub fn build(self) -> TransformEnrichPassConfig {
+        self.pass
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        conf::jsonconf::SplitterType, data_cell_row::ColumnRef,
+        transform::data_cell_table::sort::SortKey,
+    };
+
+    use super::*;
+
+    #[test]
+    fn build_config() {
+        let data = r#"
+        [
+            {
+                "comment": "pass1",
+                "transformers": [
+                    {
+                        "type": "splitItem",
+                        "cfg": {
+                            "idx": 2,
+                            "spec": { "name": "separatorChar", "char": " " },
+                            "deleteAfterSplit": true,
+                            "targetLeft": { "idx": 10, "header": "amount", "targetType": "Decimal" },
+                            "targetRight": { "idx": 11, "targetType": "String" }
+                        }
+                    },
+                    { "type": "deleteItems", "cfg": [0, 1] }
+                ],
+                "orderItems": [{ "from": 10, "to": 0 }, { "from": 11, "to": 1 }]
+            },
+            {
+                "transformers": [],
+                "tableTransformers": [{ "type": "sort", "cfg": { "keys": [{ "column": "amount" }] } }]
+            }
+        ]
+        "#;
+        let config = ConfigRoot::builder()
+            .pass(|p| {
+                p.comment("pass1")
+                    .split_item(SplitItemConfig {
+                        idx: 2,
+                        spec: SplitterType::SeparatorChar {
+                            char: ' ',
+                            split_none: None,
+                        },
+                        delete_after_split: true,
+                        target_left: ItemTargetConfig::new(10, ValueType::Decimal)
+                            .with_header("amount"),
+                        target_right: ItemTargetConfig::new(11, ValueType::String),
+                    })
+                    .delete_items([0, 1])
+                    .order_item(10, 0)
+                    .order_item(11, 1)
+            })
+            .pass(|p| {
+                p.sort(SortConfig {
+                    keys: vec![SortKey::new(ColumnRef::Name(String::from("amount")))],
+                })
+            })
+            .build();
+        assert_eq!(serde_json::from_str::<ConfigRoot>(data).unwrap(), config);
+    }
+}
@@ -1,27 +1,616 @@
 use std::collections::HashMap;
+use std::path::Path;
 
 use super::jsonconf::ConfigRoot;
-use venum::value::Value;
+use venum::{value::Value, value_type::ValueType};
 
 use crate::{
-    conf::jsonconf::{AddItemType, SplitterType, TransformEnrichPassConfig, TransformerConfig},
+    conf::jsonconf::{
+        AddItemType, ColumnRulesConfig, ConditionConfig, ItemTargetConfig, MapValuesConfig,
+        ModifierConfig, OrderItemsEntry, OrderItemsOptions, SplitterType, TableTransformerConfig,
+        TransformEnrichPassConfig, TransformerConfig,
+    },
+    constraints::{ColumnRules, RowValidator, Rule},
     data_cell::DataCell,
-    errors::{Result, VenumTdsError},
+    data_cell_row::DataCellRow,
+    errors::{IoErrors, Result, VenumTdsError},
     transform::{
         data_cell::splitting::SplitDataCellUsingValueSplit,
         data_cell_row::{
+            condition::RowCondition,
+            transrich_explode::{ExplodeItem, TransrichExplode, UnpivotItems},
             transrich_inplace::*,
             transrich_inplace_stateful::*,
             transrich_pass::{TransrichPass, TransrichPasses},
         },
-        value::spliting::{ValueStringRegexPairSplit, ValueStringSeparatorCharSplit},
+        data_cell_table::{
+            dedup::{Dedup, DedupStream, DiscardSink, DiscardingDedup},
+            group_by::{Aggregation, GroupBy},
+            join::{Join, LookupEnrich},
+            reshape::{Melt, Pivot},
+            sort::Sort,
+            transrich_table::TransrichTable,
+        },
+        value::{
+            modifying::StringModifier,
+            spliting::{
+                ValueStringRegexPairSplit, ValueStringSeparatorCharSplit,
+                ValueStringSeparatorCharTokens,
+            },
+        },
     },
+    value_fmt::value_to_string,
+};
+
+#[cfg(feature = "csv")]
+use crate::{
+    io::csv::{CsvOptions, CsvRowReader},
+    transform::data_cell_table::dedup::CsvFileSink,
 };
 
 const SPLIT_NONE_DEFAULT: bool = true;
 
+impl TryFrom<&ColumnRulesConfig> for ColumnRules {
+    type Error = VenumTdsError;
+
+    fn try_from(crc: &ColumnRulesConfig) -> Result<Self> {
+        let rules = crc
+            .rules
+            .iter()
+            .map(|rc| rc.to_rule(crc.idx, crc.dtype.as_ref()))
+            .collect::<Result<Vec<Rule>>>()?;
+        Ok(ColumnRules {
+            idx: crc.idx,
+            rules,
+        })
+    }
+}
+
+fn table_file_err<E: std::fmt::Display>(path: &str, e: E) -> VenumTdsError {
+    VenumTdsError::Io(IoErrors::File {
+        path: String::from(path),
+        msg: format!("{}", e),
+    })
+}
+
+#[cfg(not(feature = "csv"))]
+fn csv_feature_err(path: &str) -> VenumTdsError {
+    table_file_err(path, "reading or writing .csv files needs the csv feature")
+}
+
+/// Reads a lookup table from a file. A `.json` file must hold a single object, a `.csv` file two
+/// columns (key, value) and a header row.
+fn load_lookup_table(path: &str) -> Result<HashMap<String, String>> {
+    match Path::new(path).extension().and_then(|e| e.to_str()) {
+        Some("json") => {
+            let content = std::fs::read_to_string(path).map_err(|e| table_file_err(path, e))?;
+            let raw: HashMap<String, serde_json::Value> =
+                serde_json::from_str(&content).map_err(|e| table_file_err(path, e))?;
+            Ok(raw
+                .into_iter()
+                .map(|(k, v)| match v {
+                    serde_json::Value::String(s) => (k, s),
+                    other => (k, other.to_string()),
+                })
+                .collect())
+        }
+        #[cfg(feature = "csv")]
+        Some("csv") => {
+            let mut rdr = csv::ReaderBuilder::new()
+                .has_headers(true)
+                .from_path(path)
+                .map_err(|e| table_file_err(path, e))?;
+            let mut table: HashMap<String, String> = HashMap::new();
+            for record in rdr.records() {
+                let record = record.map_err(|e| table_file_err(path, e))?;
+                match (record.get(0), record.get(1)) {
+                    (Some(k), Some(v)) => {
+                        table.insert(String::from(k), String::from(v));
+                    }
+                    _ => {
+                        return Err(table_file_err(
+                            path,
+                            format!("expected two columns, got {}", record.len()),
+                        ))
+                    }
+                }
+            }
+            Ok(table)
+        }
+        #[cfg(not(feature = "csv"))]
+        Some("csv") => Err(csv_feature_err(path)),
+        _ => Err(table_file_err(path, "unsupported lookup table format")),
+    }
+}
+
+/// Reads the reference rows for a `lookupEnrich` from a file. Only `.csv` files (with a header
+/// row) are supported, for now.
+fn load_reference_rows(path: &str) -> Result<Vec<DataCellRow>> {
+    match Path::new(path).extension().and_then(|e| e.to_str()) {
+        #[cfg(feature = "csv")]
+        Some("csv") => CsvRowReader::from_path(path, &CsvOptions::default())?.collect(),
+        #[cfg(not(feature = "csv"))]
+        Some("csv") => Err(csv_feature_err(path)),
+        _ => Err(table_file_err(path, "unsupported reference file format")),
+    }
+}
+
+/// Where discarded rows (i.e. duplicates) go, by the extension of the file. The file is only
+/// written once there are discarded rows.
+fn discard_sink(path: &str) -> Result<Box<dyn DiscardSink>> {
+    match Path::new(path).extension().and_then(|e| e.to_str()) {
+        #[cfg(feature = "csv")]
+        Some("csv") => Ok(Box::new(CsvFileSink::new(path, CsvOptions::default()))),
+        #[cfg(not(feature = "csv"))]
+        Some("csv") => Err(csv_feature_err(path)),
+        _ => Err(table_file_err(
+            path,
+            "unsupported file format for discarded rows",
+        )),
+    }
+}
+
+impl TryFrom<&MapValuesConfig> for MapItemValue {
+    type Error = VenumTdsError;
+
+    fn try_from(mvc: &MapValuesConfig) -> Result<Self> {
+        let raw_table: HashMap<String, String> = match (&mvc.table, &mvc.table_file) {
+            (Some(table), None) => table.clone().into_iter().collect(),
+            (None, Some(path)) => load_lookup_table(path)?,
+            _ => {
+                return Err(VenumTdsError::Generic {
+                    msg: format!(
+                        "mapValues on idx={} needs either a table or a tableFile",
+                        mvc.idx
+                    ),
+                })
+            }
+        };
+
+        let value_type = match &mvc.target {
+            Some(t) => t.target_type.clone(),
+            None => mvc.value_type.clone().unwrap_or(ValueType::String),
+        };
+
+        let mut table: HashMap<String, Value> = HashMap::with_capacity(raw_table.len());
+        for (k, v) in &raw_table {
+            // the lookup uses the stringified value, so e.g. "1.50" must become "1.5" for floats
+            let key = match &mvc.key_type {
+                Some(kt) => value_to_string(&Value::from_str_and_type(k, kt)?),
+                None => k.clone(),
+            };
+            table.insert(key, Value::from_str_and_type(v, &value_type)?);
+        }
+
+        Ok(MapItemValue {
+            idx: mvc.idx,
+            target_idx: mvc.target.as_ref().map(|t| t.idx),
+            target_header: mvc.target.as_ref().and_then(|t| t.header.clone()),
+            key_type: mvc.key_type.clone(),
+            default: mvc
+                .default
+                .as_deref()
+                .map(|d| Value::from_str_and_type(d, &value_type))
+                .transpose()?,
+            value_type,
+            table,
+            strict: mvc.strict.unwrap_or(false),
+        })
+    }
+}
+
+impl TryFrom<&ModifierConfig> for StringModifier {
+    type Error = VenumTdsError;
+
+    fn try_from(mc: &ModifierConfig) -> Result<Self> {
+        Ok(match mc {
+            ModifierConfig::Trim { side, chars } => StringModifier::Trim {
+                side: side.unwrap_or_default(),
+                chars: chars.as_ref().map(|cs| cs.chars().collect()),
+            },
+            ModifierConfig::Case { to } => StringModifier::Case(*to),
+            ModifierConfig::Replace { from, to } => StringModifier::Replace {
+                from: from.clone(),
+                to: to.clone(),
+            },
+            ModifierConfig::ReplacePattern {
+                pattern,
+                replacement,
+            } => StringModifier::replace_pattern(pattern, replacement)?,
+            ModifierConfig::Pad { side, width, fill } => StringModifier::Pad {
+                side: side.unwrap_or_default(),
+                width: *width,
+                fill: fill.unwrap_or(' '),
+            },
+            ModifierConfig::Substring { start, len } => StringModifier::Substring {
+                start: *start,
+                len: *len,
+            },
+            ModifierConfig::Truncate { max_len } => StringModifier::Substring {
+                start: 0,
+                len: Some(*max_len),
+            },
+            ModifierConfig::Normalize { form } => StringModifier::Normalize(*form),
+            ModifierConfig::CollapseWhitespace => StringModifier::CollapseWhitespace,
+        })
+    }
+}
+
+impl TryFrom<&ConditionConfig> for RowCondition {
+    type Error = VenumTdsError;
+
+    fn try_from(cc: &ConditionConfig) -> Result<Self> {
+        let convert_all = |conditions: &[ConditionConfig]| {
+            conditions
+                .iter()
+                .map(RowCondition::try_from)
+                .collect::<Result<Vec<RowCondition>>>()
+        };
+        Ok(match cc {
+            ConditionConfig::Equals { idx, value } => RowCondition::Equals {
+                idx: *idx,
+                value: value.clone(),
+            },
+            ConditionConfig::NotEquals { idx, value } => {
+                RowCondition::Not(Box::new(RowCondition::Equals {
+                    idx: *idx,
+                    value: value.clone(),
+                }))
+            }
+            ConditionConfig::OneOf { idx, values } => RowCondition::OneOf {
+                idx: *idx,
+                values: values.clone(),
+            },
+            ConditionConfig::Matches { idx, pattern } => RowCondition::matches(*idx, pattern)?,
+            ConditionConfig::IsNone { idx } => RowCondition::IsNone { idx: *idx },
+            ConditionConfig::IsSome { idx } => {
+                RowCondition::Not(Box::new(RowCondition::IsNone { idx: *idx }))
+            }
+            ConditionConfig::All { conditions } => RowCondition::All(convert_all(conditions)?),
+            ConditionConfig::Any { conditions } => RowCondition::Any(convert_all(conditions)?),
+            ConditionConfig::Not { condition } => {
+                RowCondition::Not(Box::new(RowCondition::try_from(condition.as_ref())?))
+            }
+        })
+    }
+}
+
 // Beware, there is a whole lotta cloning going on here!
 
+/// Builds the transricher(s) of a single transformer config and adds them to the given lists.
+fn push_transrichers(
+    tc: &TransformerConfig,
+    enrich_map: Option<&HashMap<String, String>>,
+    transrichers: &mut Vec<Box<dyn TransrichInplace + Send + Sync>>,
+    transrichers_stateful: &mut Vec<Box<dyn TransrichInplaceStateful + Send>>,
+    transrichers_exploding: &mut Vec<Box<dyn TransrichExplode + Send + Sync>>,
+) -> Result<()> {
+    match tc {
+        TransformerConfig::When { cfg } => {
+            let mut then: Vec<Box<dyn TransrichInplace + Send + Sync>> = Vec::new();
+            let mut otherwise: Vec<Box<dyn TransrichInplace + Send + Sync>> = Vec::new();
+            let mut stateful: Vec<Box<dyn TransrichInplaceStateful + Send>> = Vec::new();
+            let mut exploding: Vec<Box<dyn TransrichExplode + Send + Sync>> = Vec::new();
+            for tc in &cfg.then {
+                push_transrichers(tc, enrich_map, &mut then, &mut stateful, &mut exploding)?;
+            }
+            for tc in &cfg.otherwise {
+                push_transrichers(
+                    tc,
+                    enrich_map,
+                    &mut otherwise,
+                    &mut stateful,
+                    &mut exploding,
+                )?;
+            }
+            if !stateful.is_empty() || !exploding.is_empty() {
+                return Err(VenumTdsError::Generic {
+                    msg: String::from(
+                        "stateful and exploding transformers are not supported inside of when",
+                    ),
+                });
+            }
+            transrichers.push(Box::new(When {
+                condition: RowCondition::try_from(&cfg.condition)?,
+                then,
+                otherwise,
+            }));
+        }
+        TransformerConfig::ExplodeItem { cfg } => {
+            transrichers_exploding.push(Box::new(ExplodeItem {
+                idx: cfg.idx,
+                splitter: ValueStringSeparatorCharTokens {
+                    sep_char: cfg.sep_char,
+                },
+                target_type: cfg.target_type.clone().unwrap_or(ValueType::String),
+                keep_empty: cfg.keep_empty.unwrap_or(false),
+            }));
+        }
+        TransformerConfig::LookupEnrich { cfg } => {
+            let join = Join {
+                kind: cfg.kind.unwrap_or_default(),
+                left_keys: cfg.keys.clone(),
+                right_keys: cfg.reference_keys.clone(),
+                right_columns: cfg.columns.clone(),
+                left_suffix: cfg.left_suffix.clone().unwrap_or_default(),
+                right_suffix: cfg
+                    .right_suffix
+                    .clone()
+                    .unwrap_or_else(|| Join::default().right_suffix),
+                right_idx_offset: cfg.idx_offset,
+            };
+            transrichers_exploding.push(Box::new(
+                LookupEnrich::new(join, load_reference_rows(&cfg.file)?)?.with_file(&cfg.file),
+            ));
+        }
+        TransformerConfig::DedupStream { cfg } => {
+            let mut dedup = DedupStream::new(cfg.keys.clone(), cfg.max_keys);
+            if let Some(path) = &cfg.discarded_to {
+                dedup = dedup.with_discarded(discard_sink(path)?);
+            }
+            transrichers_exploding.push(Box::new(dedup));
+        }
+        TransformerConfig::Unpivot { cfg } => {
+            transrichers_exploding.push(Box::new(UnpivotItems {
+                idxs: cfg.idxs.clone(),
+                key_idx: cfg.key.idx,
+                key_header: cfg.key.header.clone(),
+                key_type: cfg.key.target_type.clone(),
+                value_idx: cfg.value.idx,
+                value_header: cfg.value.header.clone(),
+                value_type: cfg.value.target_type.clone(),
+            }));
+        }
+        TransformerConfig::AssertSchema { cfg } => {
+            transrichers.push(Box::new(AssertSchema::new(cfg.clone())?));
+        }
+        TransformerConfig::ValidateItems { cfg } => {
+            let columns = cfg
+                .columns
+                .iter()
+                .map(ColumnRules::try_from)
+                .collect::<Result<Vec<ColumnRules>>>()?;
+            transrichers_stateful.push(Box::new(ValidateItems {
+                validator: RowValidator::new(columns, cfg.unique.clone()),
+                fail_row: cfg.fail_row.unwrap_or(false),
+            }));
+        }
+        TransformerConfig::RoundItem { cfg } => {
+            transrichers.push(Box::new(RoundItems {
+                idxs: cfg.idxs.clone(),
+                scale: cfg.scale,
+                mode: cfg.mode.unwrap_or_default(),
+            }));
+        }
+        TransformerConfig::ModifyItem { cfg } => {
+            transrichers.push(Box::new(ModifyItems {
+                idxs: cfg.idxs.clone(),
+                modifiers: cfg
+                    .modifiers
+                    .iter()
+                    .map(StringModifier::try_from)
+                    .collect::<Result<Vec<StringModifier>>>()?,
+            }));
+        }
+        TransformerConfig::MapValues { cfg } => {
+            transrichers.push(Box::new(MapItemValue::try_from(cfg)?));
+        }
+        TransformerConfig::DeleteItems { cfg } => {
+            for i in cfg {
+                transrichers.push(Box::new(DeleteItemAtIdx(*i)));
+            }
+        }
+        TransformerConfig::SplitItem { cfg } => {
+            let target_left = DataCell::new_without_data(
+                cfg.target_left.target_type.clone(),
+                cfg.target_left
+                    .header
+                    .clone()
+                    .unwrap_or_else(|| cfg.target_left.idx.to_string()),
+                cfg.target_left.idx,
+            );
+            let target_right = DataCell::new_without_data(
+                cfg.target_right.target_type.clone(),
+                cfg.target_right
+                    .header
+                    .clone()
+                    .unwrap_or_else(|| cfg.target_right.idx.to_string()),
+                cfg.target_right.idx,
+            );
+
+            match &cfg.spec {
+                SplitterType::SeparatorChar {
+                    char: ch,
+                    split_none,
+                } => {
+                    transrichers.push(Box::new(SplitItemAtIdx {
+                        delete_source_item: cfg.delete_after_split,
+                        idx: cfg.idx,
+                        splitter: SplitDataCellUsingValueSplit {
+                            splitter: ValueStringSeparatorCharSplit {
+                                sep_char: *ch,
+                                split_none: split_none.unwrap_or(SPLIT_NONE_DEFAULT),
+                            },
+                            target_left,
+                            target_right,
+                        },
+                    }));
+                }
+                SplitterType::Pattern {
+                    pattern,
+                    split_none,
+                } => {
+                    transrichers.push(Box::new(SplitItemAtIdx {
+                        delete_source_item: cfg.delete_after_split,
+                        idx: cfg.idx,
+                        splitter: SplitDataCellUsingValueSplit {
+                            splitter: ValueStringRegexPairSplit::new(
+                                pattern,
+                                split_none.unwrap_or(SPLIT_NONE_DEFAULT),
+                            )?,
+                            target_left,
+                            target_right,
+                        },
+                    }));
+                }
+            }
+        }
+        TransformerConfig::AddItem { cfg } => {
+            match &cfg.spec {
+                AddItemType::Meta { key } => match enrich_map {
+                    None => {
+                        return Err(VenumTdsError::Generic { msg: String::from("No metadata / enrichment map available, but at least needed for one transrichment") });
+                    }
+                    Some(em) => {
+                        let str_val = em.get(key).ok_or_else(|| VenumTdsError::Generic {
+                            msg: format!("No value for key={} in metadata / enrichment map", key),
+                        })?;
+
+                        // for now, I assume we have control over what is in the enrichment map, and we don't need
+                        // to tunnel through chrono patterns and null-values-mappings and such.
+                        let val = Value::from_str_and_type(str_val, &cfg.target.target_type)?;
+
+                        transrichers.push(Box::new(AddItemStatic(
+                            DataCell::new_with_type_info(
+                                cfg.target.target_type.clone(),
+                                cfg.target
+                                    .header
+                                    .clone()
+                                    .unwrap_or_else(|| cfg.target.idx.to_string()),
+                                cfg.target.idx,
+                                val,
+                            )?,
+                            cfg.on_conflict,
+                        )));
+                    }
+                },
+                AddItemType::Static { value } => {
+                    // CAUTION: this only supports the standard conversion! (Meaning, non-standard date/time formats are not supported here)
+                    transrichers.push(Box::new(AddItemStatic(
+                        DataCell::new_with_type_info(
+                            cfg.target.target_type.clone(),
+                            cfg.target
+                                .header
+                                .clone()
+                                .unwrap_or_else(|| cfg.target.idx.to_string()),
+                            cfg.target.idx,
+                            Value::from_str_and_type(value, &cfg.target.target_type)?,
+                        )?,
+                        cfg.on_conflict,
+                    )));
+                }
+                AddItemType::Runtime {
+                    rt_value,
+                    as_singleton,
+                } => {
+                    if as_singleton.unwrap_or(false) {
+                        transrichers.push(Box::new(
+                            AddItemRuntimeSingleton::new(
+                                cfg.target.header.clone(),
+                                cfg.target.idx,
+                                rt_value.clone(),
+                            )?
+                            .with_on_conflict(cfg.on_conflict),
+                        ));
+                    } else {
+                        transrichers.push(Box::new(AddItemRuntime {
+                            header: cfg.target.header.clone(),
+                            idx: cfg.target.idx,
+                            rtv: rt_value.clone(),
+                            on_conflict: cfg.on_conflict,
+                        }));
+                    }
+                }
+                AddItemType::CopyConvertAs {
+                    from_idx,
+                    number_format,
+                } => {
+                    transrichers.push(Box::new(AddItemCopyConvertAs {
+                        src_idx: *from_idx,
+                        target_header: cfg.target.header.clone(),
+                        target_idx: cfg.target.idx,
+                        target_data_type: cfg.target.target_type.clone(),
+                        on_conflict: cfg.on_conflict,
+                        number_format: number_format.clone(),
+                    }));
+                }
+                AddItemType::RuntimeStateful { rt_value } => match rt_value {
+                    RuntimeValueStateful::RowEnumeration => {
+                        transrichers_stateful.push(Box::new(
+                            AddItemRuntimeStatefulRowEnum::new(
+                                cfg.target.header.clone(),
+                                cfg.target.idx,
+                            )
+                            .with_on_conflict(cfg.on_conflict),
+                        ));
+                    }
+                },
+            }
+        }
+    }
+    Ok(())
+}
+
+impl From<&ItemTargetConfig> for DataCell {
+    fn from(itc: &ItemTargetConfig) -> Self {
+        DataCell::new_without_data(
+            itc.target_type.clone(),
+            itc.header.clone().unwrap_or_else(|| itc.idx.to_string()),
+            itc.idx,
+        )
+    }
+}
+
+fn table_transricher(
+    ttc: &TableTransformerConfig,
+) -> Result<Box<dyn TransrichTable + Send + Sync>> {
+    let transricher: Box<dyn TransrichTable + Send + Sync> = match ttc {
+        TableTransformerConfig::Melt { cfg } => Box::new(Melt {
+            id_idxs: cfg.id_idxs.clone(),
+            value_idxs: cfg.value_idxs.clone(),
+            key_target: DataCell::from(&cfg.key),
+            value_target: DataCell::from(&cfg.value),
+        }),
+        TableTransformerConfig::Pivot { cfg } => Box::new(Pivot {
+            id_idxs: cfg.id_idxs.clone(),
+            key_idx: cfg.key_idx,
+            value_idx: cfg.value_idx,
+            aggregation: cfg.aggregation.unwrap_or_default(),
+            columns: cfg.columns.clone(),
+            header_prefix: cfg.header_prefix.clone().unwrap_or_default(),
+            start_idx: cfg.start_idx,
+        }),
+        TableTransformerConfig::GroupBy { cfg } => Box::new(GroupBy {
+            keys: cfg.keys.clone(),
+            aggregations: cfg
+                .aggregations
+                .iter()
+                .map(|ac| Aggregation {
+                    column: ac.column.clone(),
+                    function: ac.function,
+                    separator: ac.separator.clone(),
+                    target_idx: ac.idx,
+                    target_header: ac.header.clone(),
+                })
+                .collect(),
+        }),
+        TableTransformerConfig::Sort { cfg } => Box::new(Sort {
+            keys: cfg.keys.clone(),
+        }),
+        TableTransformerConfig::Dedup { cfg } => {
+            let dedup = Dedup {
+                keys: cfg.keys.clone(),
+                keep: cfg.keep.clone().unwrap_or_default(),
+            };
+            match &cfg.discarded_to {
+                Some(path) => Box::new(DiscardingDedup::new(dedup, discard_sink(path)?)),
+                None => Box::new(dedup),
+            }
+        }
+    };
+    Ok(transricher)
+}
+
 impl TryFrom<(&TransformEnrichPassConfig, Option<&HashMap<String, String>>)> for TransrichPass {
     type Error = VenumTdsError;
 
@@ -33,168 +622,65 @@ impl TryFrom<(&TransformEnrichPassConfig, Option<&HashMap<String, String>>)> for
         let mut transrichers: Vec<Box<dyn TransrichInplace + Send + Sync>> =
             Vec::with_capacity(tepc.transformers.len());
         let mut transrichers_stateful: Vec<Box<dyn TransrichInplaceStateful + Send>> = Vec::new();
+        let mut transrichers_exploding: Vec<Box<dyn TransrichExplode + Send + Sync>> = Vec::new();
 
         for tc in &tepc.transformers {
-            match tc {
-                TransformerConfig::DeleteItems { cfg } => {
-                    for i in cfg {
-                        transrichers.push(Box::new(DeleteItemAtIdx(*i)));
-                    }
-                }
-                TransformerConfig::SplitItem { cfg } => {
-                    let target_left = DataCell::new_without_data(
-                        cfg.target_left.target_type.clone(),
-                        cfg.target_left
-                            .header
-                            .clone()
-                            .unwrap_or_else(|| cfg.target_left.idx.to_string()),
-                        cfg.target_left.idx,
-                    );
-                    let target_right = DataCell::new_without_data(
-                        cfg.target_right.target_type.clone(),
-                        cfg.target_right
-                            .header
-                            .clone()
-                            .unwrap_or_else(|| cfg.target_right.idx.to_string()),
-                        cfg.target_right.idx,
-                    );
-
-                    match &cfg.spec {
-                        SplitterType::SeparatorChar {
-                            char: ch,
-                            split_none,
-                        } => {
-                            transrichers.push(Box::new(SplitItemAtIdx {
-                                delete_source_item: cfg.delete_after_split,
-                                idx: cfg.idx,
-                                splitter: SplitDataCellUsingValueSplit {
-                                    splitter: ValueStringSeparatorCharSplit {
-                                        sep_char: *ch,
-                                        split_none: split_none.unwrap_or(SPLIT_NONE_DEFAULT),
-                                    },
-                                    target_left,
-                                    target_right,
-                                },
-                            }));
-                        }
-                        SplitterType::Pattern {
-                            pattern,
-                            split_none,
-                        } => {
-                            transrichers.push(Box::new(SplitItemAtIdx {
-                                delete_source_item: cfg.delete_after_split,
-                                idx: cfg.idx,
-                                splitter: SplitDataCellUsingValueSplit {
-                                    splitter: ValueStringRegexPairSplit::new(
-                                        pattern,
-                                        split_none.unwrap_or(SPLIT_NONE_DEFAULT),
-                                    )?,
-                                    target_left,
-                                    target_right,
-                                },
-                            }));
-                        }
-                    }
-                }
-                TransformerConfig::AddItem { cfg } => {
-                    match &cfg.spec {
-                        AddItemType::Meta { key } => match enrich_map {
-                            None => {
-                                return Err(VenumTdsError::Generic { msg: String::from("No metadata / enrichment map available, but at least needed for one transrichment") });
-                            }
-                            Some(em) => {
-                                let str_val =
-                                    em.get(key).ok_or_else(|| VenumTdsError::Generic {
-                                        msg: format!(
-                                            "No value for key={} in metadata / enrichment map",
-                                            key
-                                        ),
-                                    })?;
-
-                                // for now, I assume we have control over what is in the enrichment map, and we don't need
-                                // to tunnel through chrono patterns and null-values-mappings and such.
-                                let val =
-                                    Value::from_str_and_type(str_val, &cfg.target.target_type)?;
-
-                                transrichers.push(Box::new(AddItemStatic(
-                                    DataCell::new_with_type_info(
-                                        cfg.target.target_type.clone(),
-                                        cfg.target
-                                            .header
-                                            .clone()
-                                            .unwrap_or_else(|| cfg.target.idx.to_string()),
-                                        cfg.target.idx,
-                                        val,
-                                    )?,
-                                )));
-                            }
-                        },
-                        AddItemType::Static { value } => {
-                            // CAUTION: this only supports the standard conversion! (Meaning, non-standard date/time formats are not supported here)
-                            transrichers.push(Box::new(AddItemStatic(
-                                DataCell::new_with_type_info(
-                                    cfg.target.target_type.clone(),
-                                    cfg.target
-                                        .header
-                                        .clone()
-                                        .unwrap_or_else(|| cfg.target.idx.to_string()),
-                                    cfg.target.idx,
-                                    Value::from_str_and_type(value, &cfg.target.target_type)?,
-                                )?,
-                            )));
-                        }
-                        AddItemType::Runtime {
-                            rt_value,
-                            as_singleton,
-                        } => {
-                            if as_singleton.unwrap_or(false) {
-                                transrichers.push(Box::new(AddItemRuntimeSingleton::new(
-                                    cfg.target.header.clone(),
-                                    cfg.target.idx,
-                                    rt_value.clone(),
-                                )?));
-                            } else {
-                                transrichers.push(Box::new(AddItemRuntime {
-                                    header: cfg.target.header.clone(),
-                                    idx: cfg.target.idx,
-                                    rtv: rt_value.clone(),
-                                }));
-                            }
-                        }
-                        AddItemType::RuntimeStateful { rt_value } => match rt_value {
-                            RuntimeValueStateful::RowEnumeration => {
-                                transrichers_stateful.push(Box::new(
-                                    AddItemRuntimeStatefulRowEnum::new(
-                                        cfg.target.header.clone(),
-                                        cfg.target.idx,
-                                    ),
-                                ));
-                            }
-                        },
-                    }
-                }
-            };
+            push_transrichers(
+                tc,
+                enrich_map,
+                &mut transrichers,
+                &mut transrichers_stateful,
+                &mut transrichers_exploding,
+            )?;
         }
 
         let mut ordering_opt: Option<Vec<Box<dyn TransrichInplace + Send + Sync>>> = None;
-        if let Some(order_items) = &tepc.order_items {
+        if tepc.order_items.is_some() || tepc.order_items_options.is_some() {
+            let order_items: &[OrderItemsEntry] = tepc.order_items.as_deref().unwrap_or(&[]);
+            let default_opts = OrderItemsOptions::default();
+            let opts = tepc.order_items_options.as_ref().unwrap_or(&default_opts);
+
             let mut ordering: Vec<Box<dyn TransrichInplace + Send + Sync>> =
-                Vec::with_capacity(order_items.len());
+                Vec::with_capacity(order_items.len() + 3);
 
             for o in order_items {
-                ordering.push(Box::new(MutateItemIdx {
-                    from: o.from,
-                    to: o.to,
-                }));
+                match opts.mode.unwrap_or_default() {
+                    OrderMode::Move => ordering.push(Box::new(MutateItemIdx {
+                        from: o.from,
+                        to: o.to,
+                    })),
+                    OrderMode::Swap => ordering.push(Box::new(SwapItemIdx {
+                        from: o.from,
+                        to: o.to,
+                    })),
+                }
+            }
+            if opts.compact.unwrap_or(false) {
+                ordering.push(Box::new(CompactItemIdx));
             }
+            let validation = opts.validation.unwrap_or_default();
+            if validation != OrderValidation::None {
+                ordering.push(Box::new(ValidateItemIdx(validation)));
+            }
+            // after ordering, iteration order should match column order
+            ordering.push(Box::new(SortItemsByIdx));
+
             ordering_opt = Some(ordering);
         }
 
-        Ok(TransrichPass::new(
-            transrichers,
-            transrichers_stateful,
-            ordering_opt,
-        ))
+        let transrichers_table: Vec<Box<dyn TransrichTable + Send + Sync>> = tepc
+            .table_transformers
+            .iter()
+            .flatten()
+            .map(table_transricher)
+            .collect::<Result<_>>()?;
+
+        Ok(
+            TransrichPass::new(transrichers, transrichers_stateful, ordering_opt)
+                .with_exploding(transrichers_exploding)
+                .with_table(transrichers_table)
+                .with_strict(tepc.strict.unwrap_or(false)),
+        )
     }
 }
 
@@ -239,14 +725,27 @@ mod tests {
 
     use crate::{
         conf::jsonconf::{
-            AddItemConfig, AddItemType, ItemTargetConfig, OrderItemsEntry, SplitItemConfig,
-            SplitterType, TransformEnrichPassConfig, TransformerConfig,
+            AddItemConfig, AddItemType, AggregationConfig, ColumnRulesConfig, ConditionConfig,
+            ConfigRoot, GroupByConfig, ItemTargetConfig, MapValuesConfig, MeltConfig,
+            ModifierConfig, ModifyItemConfig, OrderItemsEntry, OrderItemsOptions, RoundItemConfig,
+            RuleConfig, SplitItemConfig, SplitterType, TableTransformerConfig,
+            TransformEnrichPassConfig, TransformerConfig, UnpivotConfig, ValidateItemsConfig,
+            WhenConfig,
         },
         data_cell::DataCell,
+        data_cell_row::{ColumnRef, DataCellRow},
+        data_cell_table::DataCellTable,
         transform::{
             data_cell::splitting::*,
-            data_cell_row::{transrich_inplace::*, transrich_pass::TransrichPass},
-            value::spliting::*,
+            data_cell_row::{
+                transrich_inplace::*,
+                transrich_pass::{TransrichPass, TransrichPasses},
+            },
+            data_cell_table::group_by::AggregateFunction,
+            value::{
+                numbers::{NumberFormat, RoundingMode},
+                spliting::*,
+            },
         },
     };
 
@@ -304,15 +803,18 @@ mod tests {
                         Value::String(String::from("Europe")),
                     )
                     .unwrap(),
+                    None,
                 )),
                 Box::new(AddItemStatic(
                     DataCell::new(String::from("Magic Number"), 23, Value::Float32(1.123)).unwrap(),
+                    None,
                 )),
                 // // We can't rely test this, because of the dynamic nature...
                 // Box::new(AddItemRuntime {
                 //     header: Some(String::from("Runtime DateTime 1")),
                 //     idx: 24,
                 //     rtv: RuntimeValue::CurrentDateTimeUtcAsFixedOffset,
+                //     on_conflict: None,
                 // }),
                 // Box::new(AddItemRuntimeSingleton::new(
                 //     Some(String::from("Runtime DateTime 2")),
@@ -321,6 +823,7 @@ mod tests {
                 // )),
                 Box::new(AddItemStatic(
                     DataCell::new(String::from("Account Id"), 26, Value::Int32(1000)).unwrap(),
+                    None,
                 )),
             ],
             Vec::new(),
@@ -334,6 +837,7 @@ mod tests {
                 Box::new(MutateItemIdx { from: 24, to: 6 }),
                 Box::new(MutateItemIdx { from: 25, to: 7 }),
                 Box::new(MutateItemIdx { from: 26, to: 8 }),
+                Box::new(SortItemsByIdx),
             ]),
         );
 
@@ -391,6 +895,7 @@ mod tests {
                             header: Some(String::from("Region")),
                             target_type: ValueType::String,
                         },
+                        on_conflict: None,
                     },
                 },
                 TransformerConfig::AddItem {
@@ -403,6 +908,7 @@ mod tests {
                             header: Some(String::from("Magic Number")),
                             target_type: ValueType::Float32,
                         },
+                        on_conflict: None,
                     },
                 },
                 // // We can't rely test this, because of the dynamic nature...
@@ -417,6 +923,7 @@ mod tests {
                 //             header: Some(String::from("Runtime DateTime 1")),
                 //             target_type: ValueType::DateTime,
                 //         },
+                //         on_conflict: None,
                 //     },
                 // },
                 // TransformerConfig::AddItem {
@@ -430,6 +937,7 @@ mod tests {
                 //             header: Some(String::from("Runtime DateTime 2")),
                 //             target_type: ValueType::DateTime,
                 //         },
+                //         on_conflict: None,
                 //     },
                 // },
                 TransformerConfig::AddItem {
@@ -442,6 +950,7 @@ mod tests {
                             header: Some(String::from("Account Id")),
                             target_type: ValueType::Int32,
                         },
+                        on_conflict: None,
                     },
                 },
             ],
@@ -456,6 +965,7 @@ mod tests {
                 OrderItemsEntry { from: 25, to: 7 },
                 OrderItemsEntry { from: 26, to: 8 },
             ]),
+            ..Default::default()
         };
 
         let mut metadata: HashMap<String, String> = HashMap::with_capacity(1);
@@ -463,6 +973,581 @@ mod tests {
 
         let test_pass = TransrichPass::try_from((&dsl_fmt, Some(&metadata))).unwrap();
 
-        assert_eq!(format!("{:?}", exp), format!("{:?}", test_pass));
+        assert_eq!(exp.describe(), test_pass.describe());
+
+        // the config is built from the transrichers, so the pass built in code has the same one
+        let written = exp.to_config().unwrap();
+        assert_eq!(Some(&written), test_pass.to_config().as_ref());
+        assert_eq!(
+            exp.describe(),
+            TransrichPass::try_from(&written).unwrap().describe()
+        );
+        assert_eq!(
+            Some(true),
+            test_pass.with_strict(true).to_config().unwrap().strict
+        );
+
+        let config = ConfigRoot(vec![dsl_fmt]);
+        let passes = TransrichPasses::try_from((&config, Some(&metadata))).unwrap();
+        assert_eq!(Some(ConfigRoot(vec![written])), passes.to_config());
+    }
+
+    #[test]
+    fn order_items_options() {
+        let dsl_fmt = TransformEnrichPassConfig {
+            order_items: Some(vec![
+                OrderItemsEntry { from: 0, to: 1 },
+                OrderItemsEntry { from: 2, to: 0 },
+            ]),
+            order_items_options: Some(OrderItemsOptions {
+                mode: Some(OrderMode::Swap),
+                validation: Some(OrderValidation::Permutation),
+                compact: None,
+            }),
+            ..Default::default()
+        };
+        let mut pass = TransrichPass::try_from(&dsl_fmt).unwrap();
+
+        // the ordering transrichers give the options back, including the defaults
+        let written = pass.to_config().unwrap();
+        assert_eq!(dsl_fmt.order_items, written.order_items);
+        assert_eq!(
+            Some(OrderItemsOptions {
+                compact: Some(false),
+                ..dsl_fmt.order_items_options.clone().unwrap()
+            }),
+            written.order_items_options
+        );
+
+        let mut data = DataCellRow::new();
+        data.push(DataCell::new(String::from("a"), 0, Value::Int32(0)).unwrap());
+        data.push(DataCell::new(String::from("b"), 1, Value::Int32(1)).unwrap());
+        data.push(DataCell::new(String::from("c"), 2, Value::Int32(2)).unwrap());
+
+        pass.transrich(&mut data).unwrap();
+        // 0 <-> 1, then 2 <-> 0, and physically sorted afterwards
+        let names: Vec<&str> = (&data).into_iter().map(|dc| dc.get_name()).collect();
+        assert_eq!(vec!["c", "a", "b"], names);
+
+        // the same with "move" leaves us with idx 1 twice
+        let dsl_fmt = TransformEnrichPassConfig {
+            order_items_options: Some(OrderItemsOptions {
+                mode: Some(OrderMode::Move),
+                validation: Some(OrderValidation::Unique),
+                compact: None,
+            }),
+            ..dsl_fmt
+        };
+        let mut pass = TransrichPass::try_from(&dsl_fmt).unwrap();
+        assert!(pass.transrich(&mut data).is_err());
+    }
+
+    #[test]
+    fn strict_pass() {
+        let dsl_fmt = |strict: Option<bool>| TransformEnrichPassConfig {
+            strict,
+            transformers: vec![TransformerConfig::AddItem {
+                cfg: AddItemConfig {
+                    spec: AddItemType::Static {
+                        value: String::from("Europe"),
+                    },
+                    target: ItemTargetConfig {
+                        idx: 0,
+                        header: Some(String::from("region")),
+                        target_type: ValueType::String,
+                    },
+                    on_conflict: None,
+                },
+            }],
+            ..Default::default()
+        };
+        let row = || {
+            let mut data = DataCellRow::new();
+            data.push(DataCell::new(String::from("a"), 0, Value::Int32(1)).unwrap());
+            data
+        };
+
+        // idx 0 is in use, so we just get a duplicate
+        let mut data = row();
+        let mut pass = TransrichPass::try_from(&dsl_fmt(None)).unwrap();
+        pass.transrich(&mut data).unwrap();
+        assert_eq!(2, data.len());
+
+        let mut data = row();
+        let mut pass = TransrichPass::try_from(&dsl_fmt(Some(true))).unwrap();
+        assert!(pass.transrich(&mut data).is_err());
+        assert!(!data.is_strict());
+        assert!(pass.describe().strict);
+    }
+
+    #[test]
+    fn validate_items() {
+        let dsl_fmt = TransformEnrichPassConfig {
+            transformers: vec![TransformerConfig::ValidateItems {
+                cfg: ValidateItemsConfig {
+                    columns: vec![ColumnRulesConfig {
+                        idx: 0,
+                        dtype: Some(ValueType::Int32),
+                        rules: vec![RuleConfig::Range {
+                            min: Some(String::from("0")),
+                            max: Some(String::from("10")),
+                        }],
+                    }],
+                    unique: Vec::new(),
+                    fail_row: None,
+                },
+            }],
+            ..Default::default()
+        };
+        let mut pass = TransrichPass::try_from(&dsl_fmt).unwrap();
+
+        for i in [5, 11] {
+            let mut data = DataCellRow::new();
+            data.push(DataCell::new(String::from("a"), 0, Value::Int32(i)).unwrap());
+            pass.transrich(&mut data).unwrap();
+        }
+        let reports = pass.validation_reports();
+        assert_eq!(1, reports.len());
+        assert_eq!(vec![2], reports[0].failed_rows());
+
+        // range bounds need a type
+        let dsl_fmt = TransformEnrichPassConfig {
+            transformers: vec![TransformerConfig::ValidateItems {
+                cfg: ValidateItemsConfig {
+                    columns: vec![ColumnRulesConfig {
+                        idx: 0,
+                        dtype: None,
+                        rules: vec![RuleConfig::Range {
+                            min: Some(String::from("0")),
+                            max: None,
+                        }],
+                    }],
+                    unique: Vec::new(),
+                    fail_row: None,
+                },
+            }],
+            ..dsl_fmt
+        };
+        assert!(TransrichPass::try_from(&dsl_fmt).is_err());
+    }
+
+    #[test]
+    fn map_values_from_file() {
+        let file = tempfile::Builder::new().suffix(".json").tempfile().unwrap();
+        std::fs::write(file.path(), r#"{ "1.50": "low", "3": "high" }"#).unwrap();
+
+        let mvc = MapValuesConfig {
+            idx: 0,
+            key_type: Some(ValueType::Float64),
+            value_type: None,
+            target: None,
+            table: None,
+            table_file: Some(file.path().display().to_string()),
+            default: None,
+            strict: Some(true),
+        };
+        let m = MapItemValue::try_from(&mvc).unwrap();
+
+        let mut data = DataCellRow::new();
+        data.push(DataCell::new(String::from("a"), 0, Value::Float64(1.5)).unwrap());
+        data.push(DataCell::new(String::from("b"), 1, Value::Int32(3)).unwrap());
+        data.push(DataCell::new(String::from("c"), 2, Value::String(String::from("3.0"))).unwrap());
+        m.transrich(&mut data).unwrap();
+        assert_eq!(
+            &Value::String(String::from("low")),
+            data.get_by_idx(0).unwrap().get_data()
+        );
+        // cells of other types are converted to the key type before the lookup
+        for idx in [1, 2] {
+            let m = MapItemValue {
+                idx,
+                ..MapItemValue::try_from(&mvc).unwrap()
+            };
+            m.transrich(&mut data).unwrap();
+            assert_eq!(
+                &Value::String(String::from("high")),
+                data.get_by_idx(idx).unwrap().get_data()
+            );
+        }
+
+        // either a table or a file
+        let mvc = MapValuesConfig {
+            table_file: None,
+            ..mvc
+        };
+        assert!(MapItemValue::try_from(&mvc).is_err());
+    }
+
+    #[test]
+    fn modify_item() {
+        let dsl_fmt = TransformEnrichPassConfig {
+            transformers: vec![TransformerConfig::ModifyItem {
+                cfg: ModifyItemConfig {
+                    idxs: vec![0],
+                    modifiers: vec![
+                        ModifierConfig::Trim {
+                            side: None,
+                            chars: None,
+                        },
+                        ModifierConfig::Truncate { max_len: 3 },
+                        ModifierConfig::Pad {
+                            side: None,
+                            width: 5,
+                            fill: Some('0'),
+                        },
+                    ],
+                },
+            }],
+            ..Default::default()
+        };
+        let mut pass = TransrichPass::try_from(&dsl_fmt).unwrap();
+
+        let mut data = DataCellRow::new();
+        data.push(
+            DataCell::new(String::from("a"), 0, Value::String(String::from("  1234 "))).unwrap(),
+        );
+        pass.transrich(&mut data).unwrap();
+        assert_eq!(
+            &Value::String(String::from("00123")),
+            data.get_by_idx(0).unwrap().get_data()
+        );
+    }
+
+    #[test]
+    fn copy_convert_as_and_round_item() {
+        let dsl_fmt = TransformEnrichPassConfig {
+            transformers: vec![
+                TransformerConfig::AddItem {
+                    cfg: AddItemConfig {
+                        spec: AddItemType::CopyConvertAs {
+                            from_idx: 0,
+                            number_format: Some(NumberFormat {
+                                decimal_separator: ',',
+                                percent: true,
+                                ..NumberFormat::default()
+                            }),
+                        },
+                        target: ItemTargetConfig {
+                            idx: 1,
+                            header: None,
+                            target_type: ValueType::Float64,
+                        },
+                        on_conflict: None,
+                    },
+                },
+                TransformerConfig::RoundItem {
+                    cfg: RoundItemConfig {
+                        idxs: vec![1],
+                        scale: 2,
+                        mode: Some(RoundingMode::HalfEven),
+                    },
+                },
+            ],
+            ..Default::default()
+        };
+        let mut pass = TransrichPass::try_from(&dsl_fmt).unwrap();
+
+        let mut data = DataCellRow::new();
+        data.push(
+            DataCell::new(
+                String::from("rate"),
+                0,
+                Value::String(String::from("12,5 %")),
+            )
+            .unwrap(),
+        );
+        pass.transrich(&mut data).unwrap();
+        assert_eq!(
+            &Value::Float64(0.12),
+            data.get_by_idx(1).unwrap().get_data()
+        );
+    }
+
+    #[test]
+    fn when() {
+        let dsl_fmt = TransformEnrichPassConfig {
+            transformers: vec![TransformerConfig::When {
+                cfg: WhenConfig {
+                    condition: ConditionConfig::Equals {
+                        idx: 0,
+                        value: String::from("D"),
+                    },
+                    then: vec![TransformerConfig::DeleteItems { cfg: vec![0] }],
+                    otherwise: Vec::new(),
+                },
+            }],
+            ..Default::default()
+        };
+        let mut pass = TransrichPass::try_from(&dsl_fmt).unwrap();
+
+        for (rec_type, exp_len) in [("D", 1), ("H", 2)] {
+            let mut data = DataCellRow::new();
+            data.push(
+                DataCell::new(
+                    String::from("type"),
+                    0,
+                    Value::String(String::from(rec_type)),
+                )
+                .unwrap(),
+            );
+            data.push(DataCell::new(String::from("data"), 1, Value::Int32(1)).unwrap());
+            pass.transrich(&mut data).unwrap();
+            assert_eq!(exp_len, data.len());
+        }
+
+        // stateful transformers can't be conditional
+        let dsl_fmt = TransformEnrichPassConfig {
+            transformers: vec![TransformerConfig::When {
+                cfg: WhenConfig {
+                    condition: ConditionConfig::IsNone { idx: 0 },
+                    then: vec![TransformerConfig::ValidateItems {
+                        cfg: ValidateItemsConfig {
+                            columns: Vec::new(),
+                            unique: vec![vec![0]],
+                            fail_row: None,
+                        },
+                    }],
+                    otherwise: Vec::new(),
+                },
+            }],
+            ..dsl_fmt
+        };
+        assert!(TransrichPass::try_from(&dsl_fmt).is_err());
+    }
+
+    #[test]
+    fn unpivot() {
+        let dsl_fmt = TransformEnrichPassConfig {
+            transformers: vec![TransformerConfig::Unpivot {
+                cfg: UnpivotConfig {
+                    idxs: vec![1, 2],
+                    key: ItemTargetConfig {
+                        idx: 1,
+                        header: Some(String::from("month")),
+                        target_type: ValueType::String,
+                    },
+                    value: ItemTargetConfig {
+                        idx: 2,
+                        header: Some(String::from("value")),
+                        target_type: ValueType::Int32,
+                    },
+                },
+            }],
+            ..Default::default()
+        };
+        let mut pass = TransrichPass::try_from(&dsl_fmt).unwrap();
+        assert!(pass.is_exploding());
+
+        let mut data = DataCellRow::new();
+        data.push(DataCell::new(String::from("id"), 0, Value::Int32(7)).unwrap());
+        data.push(DataCell::new(String::from("jan"), 1, Value::Int32(10)).unwrap());
+        data.push(DataCell::new(String::from("feb"), 2, Value::Int32(20)).unwrap());
+
+        let rows = pass.transrich_rows(data).unwrap();
+        assert_eq!(
+            vec![
+                vec![
+                    Value::Int32(7),
+                    Value::String(String::from("jan")),
+                    Value::Int32(10)
+                ],
+                vec![
+                    Value::Int32(7),
+                    Value::String(String::from("feb")),
+                    Value::Int32(20)
+                ],
+            ],
+            rows.into_iter()
+                .map(|r| r.into_values())
+                .collect::<Vec<Vec<Value>>>()
+        );
+    }
+
+    #[test]
+    fn melt() {
+        let dsl_fmt = TransformEnrichPassConfig {
+            transformers: vec![TransformerConfig::DeleteItems { cfg: vec![3] }],
+            table_transformers: Some(vec![TableTransformerConfig::Melt {
+                cfg: MeltConfig {
+                    id_idxs: vec![0],
+                    value_idxs: None,
+                    key: ItemTargetConfig {
+                        idx: 1,
+                        header: None,
+                        target_type: ValueType::UInt8,
+                    },
+                    value: ItemTargetConfig {
+                        idx: 2,
+                        header: Some(String::from("amount")),
+                        target_type: ValueType::Int64,
+                    },
+                },
+            }]),
+            ..Default::default()
+        };
+        let mut pass = TransrichPass::try_from(&dsl_fmt).unwrap();
+        assert!(pass.is_table());
+
+        let mut data = DataCellRow::new();
+        data.push(DataCell::new(String::from("id"), 0, Value::Int32(7)).unwrap());
+        data.push(DataCell::new(String::from("1"), 1, Value::String(String::from("10"))).unwrap());
+        data.push(DataCell::new(String::from("2"), 2, Value::String(String::from("20"))).unwrap());
+        data.push(DataCell::new(String::from("junk"), 3, Value::Bool(true)).unwrap());
+
+        let table = pass
+            .transrich_table(DataCellTable::from(vec![data]))
+            .unwrap();
+        assert_eq!(2, table.len());
+        assert_eq!(vec!["id", "1", "amount"], table.get(1).unwrap().headers());
+        assert_eq!(
+            vec![Value::Int32(7), Value::UInt8(2), Value::Int64(20)],
+            table.get(1).unwrap().clone().into_values()
+        );
+    }
+
+    #[test]
+    fn group_by() {
+        let dsl_fmt = TransformEnrichPassConfig {
+            table_transformers: Some(vec![TableTransformerConfig::GroupBy {
+                cfg: GroupByConfig {
+                    keys: vec![ColumnRef::Name(String::from("id"))],
+                    aggregations: vec![AggregationConfig {
+                        column: Some(ColumnRef::Idx(1)),
+                        function: AggregateFunction::Max,
+                        separator: None,
+                        idx: 1,
+                        header: Some(String::from("max")),
+                    }],
+                },
+            }]),
+            ..Default::default()
+        };
+        let mut pass = TransrichPass::try_from(&dsl_fmt).unwrap();
+
+        let mut table = DataCellTable::new();
+        for (id, val) in [(1, 3), (2, 1), (1, 5)] {
+            let mut data = DataCellRow::new();
+            data.push(DataCell::new(String::from("id"), 0, Value::Int32(id)).unwrap());
+            data.push(DataCell::new(String::from("val"), 1, Value::Int32(val)).unwrap());
+            table.push(data);
+        }
+        let table = pass.transrich_table(table).unwrap();
+        assert_eq!(
+            vec![
+                vec![Value::Int32(1), Value::Int32(5)],
+                vec![Value::Int32(2), Value::Int32(1)]
+            ],
+            table
+                .into_iter()
+                .map(|r| r.into_values())
+                .collect::<Vec<Vec<Value>>>()
+        );
+    }
+
+    #[test]
+    fn dedup_stream() {
+        use crate::conf::jsonconf::DedupStreamConfig;
+
+        let dsl_fmt = |discarded_to: Option<&str>| TransformEnrichPassConfig {
+            transformers: vec![TransformerConfig::DedupStream {
+                cfg: DedupStreamConfig {
+                    keys: Some(vec![ColumnRef::Name(String::from("booking_id"))]),
+                    max_keys: None,
+                    discarded_to: discarded_to.map(String::from),
+                },
+            }],
+            ..Default::default()
+        };
+        let mut pass = TransrichPass::try_from(&dsl_fmt(None)).unwrap();
+
+        let mut data = DataCellRow::new();
+        data.push(DataCell::new(String::from("booking_id"), 0, Value::Int32(7)).unwrap());
+        data.push(DataCell::new(String::from("amount"), 1, Value::Int32(10)).unwrap());
+        assert_eq!(1, pass.transrich_rows(data.clone()).unwrap().len());
+        assert!(pass.transrich_rows(data).unwrap().is_empty());
+
+        assert!(TransrichPass::try_from(&dsl_fmt(Some("duplicates.xlsx"))).is_err());
+    }
+
+    #[cfg(feature = "csv")]
+    #[test]
+    fn dedup_stream_discarded_to() {
+        use crate::conf::jsonconf::DedupStreamConfig;
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("duplicates.csv");
+        let dsl_fmt = TransformEnrichPassConfig {
+            transformers: vec![TransformerConfig::DedupStream {
+                cfg: DedupStreamConfig {
+                    keys: None,
+                    max_keys: None,
+                    discarded_to: Some(path.display().to_string()),
+                },
+            }],
+            ..Default::default()
+        };
+        let mut pass = TransrichPass::try_from(&dsl_fmt).unwrap();
+        // building the pass doesn't touch the file
+        assert!(!path.exists());
+        assert!(pass.describe().to_string().contains(&format!(
+            "discarded_to: Some({:?})",
+            path.display().to_string()
+        )));
+
+        let mut data = DataCellRow::new();
+        data.push(DataCell::new(String::from("booking_id"), 0, Value::Int32(7)).unwrap());
+        assert_eq!(1, pass.transrich_rows(data.clone()).unwrap().len());
+        assert!(!path.exists());
+        assert!(pass.transrich_rows(data).unwrap().is_empty());
+        assert_eq!("booking_id\n7\n", std::fs::read_to_string(&path).unwrap());
+    }
+
+    #[cfg(feature = "csv")]
+    #[test]
+    fn lookup_enrich() {
+        use crate::{
+            conf::jsonconf::LookupEnrichConfig, transform::data_cell_table::join::JoinKind,
+        };
+
+        let file = tempfile::Builder::new().suffix(".csv").tempfile().unwrap();
+        std::fs::write(file.path(), "id,owner\nA,Alice\nB,Bob\n").unwrap();
+
+        let dsl_fmt = TransformEnrichPassConfig {
+            transformers: vec![TransformerConfig::LookupEnrich {
+                cfg: LookupEnrichConfig {
+                    file: file.path().display().to_string(),
+                    keys: vec![ColumnRef::Idx(0)],
+                    reference_keys: vec![ColumnRef::Name(String::from("id"))],
+                    kind: Some(JoinKind::Inner),
+                    columns: None,
+                    left_suffix: None,
+                    right_suffix: None,
+                    idx_offset: None,
+                },
+            }],
+            ..Default::default()
+        };
+        let mut pass = TransrichPass::try_from(&dsl_fmt).unwrap();
+
+        let mut data = DataCellRow::new();
+        data.push(
+            DataCell::new(String::from("account"), 0, Value::String(String::from("B"))).unwrap(),
+        );
+        let rows = pass.transrich_rows(data.clone()).unwrap();
+        assert_eq!(
+            vec![vec![
+                Value::String(String::from("B")),
+                Value::String(String::from("Bob"))
+            ]],
+            rows.into_iter()
+                .map(|r| r.into_values())
+                .collect::<Vec<Vec<Value>>>()
+        );
+
+        // inner joins drop rows without a match
+        data.get_by_idx_mut(0)
+            .unwrap()
+            .set_data(Value::String(String::from("C")));
+        assert!(pass.transrich_rows(data).unwrap().is_empty());
     }
 }
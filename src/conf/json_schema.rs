@@ -0,0 +1,153 @@
+use schemars::{
+    gen::{SchemaGenerator, SchemaSettings},
+    schema::{InstanceType, Metadata, RootSchema, Schema, SchemaObject},
+    visit::{self, Visitor},
+    JsonSchema,
+};
+
+use super::jsonconf::ConfigRoot;
+
+use crate::errors::{Result, VenumTdsError};
+
+/// The data types, as they are written in a config. (See "Data Types" in JSONCONF.md.) The
+/// `value_types` test makes sure none is missing.
+const VALUE_TYPES: [&str; 19] = [
+    "Bool",
+    "Char",
+    "String",
+    "Int8",
+    "Int16",
+    "Int32",
+    "Int64",
+    "Int128",
+    "UInt8",
+    "UInt16",
+    "UInt32",
+    "UInt64",
+    "UInt128",
+    "Float32",
+    "Float64",
+    "Decimal",
+    "NaiveDate",
+    "NaiveDateTime",
+    "DateTime",
+];
+
+/// The schema of venum's `ValueType`, which doesn't implement `JsonSchema` itself. Used via
+/// `#[schemars(with = "...")]` on the fields of that type.
+pub(crate) struct ValueTypeSchema;
+
+impl JsonSchema for ValueTypeSchema {
+    fn schema_name() -> String {
+        String::from("ValueType")
+    }
+
+    fn json_schema(_gen: &mut SchemaGenerator) -> Schema {
+        SchemaObject {
+            metadata: Some(Box::new(Metadata {
+                description: Some(String::from(
+                    "A data type, see \"Data Types\" in JSONCONF.md.",
+                )),
+                ..Default::default()
+            })),
+            instance_type: Some(InstanceType::String.into()),
+            enum_values: Some(VALUE_TYPES.iter().map(|t| (*t).into()).collect()),
+            ..Default::default()
+        }
+        .into()
+    }
+}
+
+/// Closes all object schemas with properties, i.e. makes unknown fields errors. Deserializing a
+/// config ignores them, but in an editor, they are most likely typos.
+#[derive(Debug, Clone)]
+struct DenyUnknownFields;
+
+impl Visitor for DenyUnknownFields {
+    fn visit_schema_object(&mut self, schema: &mut SchemaObject) {
+        visit::visit_schema_object(self, schema);
+        if let Some(object) = &mut schema.object {
+            if !object.properties.is_empty() && object.additional_properties.is_none() {
+                object.additional_properties = Some(Box::new(Schema::Bool(false)));
+            }
+        }
+    }
+}
+
+/// The JSON Schema (draft 7) of a `ConfigRoot`, with the doc comments of the config types as
+/// descriptions. Editors can use it to autocomplete and validate configs. Unlike deserializing,
+/// it doesn't allow unknown fields, so e.g. `"deleteAfterSpilt"` is caught.
+pub fn config_schema() -> RootSchema {
+    SchemaSettings::draft07()
+        .with_visitor(DenyUnknownFields)
+        .into_generator()
+        .into_root_schema_for::<ConfigRoot>()
+}
+
+/// The JSON Schema of a `ConfigRoot` as (pretty printed) json, see `config_schema`.
+pub fn config_schema_json() -> Result<String> {
+    serde_json::to_string_pretty(&config_schema()).map_err(|e| VenumTdsError::Generic {
+        msg: format!("can't serialize the config schema: {}", e),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::Value as JsonValue;
+    use venum::value_type::ValueType;
+
+    use super::*;
+
+    fn definition<'a>(schema: &'a JsonValue, name: &str) -> &'a JsonValue {
+        &schema["definitions"][name]
+    }
+
+    #[test]
+    fn schema_of_config_root() {
+        let schema: JsonValue = serde_json::from_str(&config_schema_json().unwrap()).unwrap();
+        assert_eq!("array", schema["type"]);
+
+        let split_item = definition(&schema, "SplitItemConfig");
+        assert!(split_item["properties"]["deleteAfterSplit"].is_object());
+        assert!(split_item["required"]
+            .as_array()
+            .unwrap()
+            .contains(&JsonValue::from("deleteAfterSplit")));
+        assert_eq!(JsonValue::Bool(false), split_item["additionalProperties"]);
+
+        // descriptions come from the doc comments
+        assert_eq!(
+            "How a `splitItem` splits the value in two.",
+            definition(&schema, "SplitterType")["description"]
+        );
+        assert_eq!(
+            "#/definitions/ValueType",
+            definition(&schema, "ItemTargetConfig")["properties"]["targetType"]["$ref"]
+        );
+        assert!(definition(&schema, "RuntimeValue").is_object());
+        assert!(definition(&schema, "AddItemType")["oneOf"].is_array());
+    }
+
+    #[test]
+    fn value_types() {
+        for name in VALUE_TYPES {
+            assert!(
+                serde_json::from_value::<ValueType>(JsonValue::from(name)).is_ok(),
+                "{}",
+                name
+            );
+        }
+
+        // serde lists all the variants it knows when it doesn't know one, so a new `ValueType` in
+        // venum (or a renamed one) fails here, until it's added above
+        let msg = serde_json::from_value::<ValueType>(JsonValue::from("NoSuchType"))
+            .unwrap_err()
+            .to_string();
+        let (_, expected) = msg.split_once("expected one of ").expect(&msg);
+        let mut expected: Vec<&str> = expected.split(", ").map(|v| v.trim_matches('`')).collect();
+        expected.sort_unstable();
+        let mut value_types = VALUE_TYPES.to_vec();
+        value_types.sort_unstable();
+        assert_eq!(expected, value_types);
+    }
+}
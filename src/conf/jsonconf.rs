@@ -1,34 +1,67 @@
-use serde::Deserialize;
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
 use venum::value_type::ValueType;
 
-use crate::transform::data_cell_row::{
-    transrich_inplace::RuntimeValue, transrich_inplace_stateful::RuntimeValueStateful,
+pub use crate::constraints::RuleConfig;
+use crate::data_cell_row::{ColumnRef, OnConflict};
+use crate::schema::Schema;
+use crate::transform::{
+    data_cell_row::{
+        transrich_inplace::{OrderMode, OrderValidation, RuntimeValue},
+        transrich_inplace_stateful::RuntimeValueStateful,
+    },
+    data_cell_table::{
+        dedup::DedupKeep, group_by::AggregateFunction, join::JoinKind, reshape::PivotAggregation,
+        sort::SortKey,
+    },
+    value::{
+        modifying::{CaseMode, PadSide, TrimSide, UnicodeForm},
+        numbers::{NumberFormat, RoundingMode},
+    },
 };
 
-#[derive(Debug, Deserialize, PartialEq, Eq)]
+/// How a `splitItem` splits the value in two.
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq)]
+#[cfg_attr(feature = "jsonschema", derive(schemars::JsonSchema))]
 #[serde(tag = "name", rename_all = "camelCase")]
 pub enum SplitterType {
+    /// Splits at the first occurrence of the char.
     SeparatorChar {
         char: char,
-        split_none: Option<bool>, // We default to true!
+        /// Whether a `None` is split into two `None`s. We default to true!
+        #[serde(skip_serializing_if = "Option::is_none")]
+        split_none: Option<bool>,
     },
+    /// Splits into the two capture groups of the (rust-style) regex.
     Pattern {
         pattern: String,
-        split_none: Option<bool>, // We default to true!
+        /// Whether a `None` is split into two `None`s. We default to true!
+        #[serde(skip_serializing_if = "Option::is_none")]
+        split_none: Option<bool>,
     },
 }
 
-#[derive(Debug, Default, Deserialize, PartialEq, Eq)]
+/// A (new) column.
+#[derive(Debug, Clone, Default, Deserialize, Serialize, PartialEq, Eq)]
+#[cfg_attr(feature = "jsonschema", derive(schemars::JsonSchema))]
 #[serde(rename_all = "camelCase")]
 pub struct ItemTargetConfig {
     pub idx: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub header: Option<String>,
+    #[cfg_attr(
+        feature = "jsonschema",
+        schemars(with = "crate::conf::json_schema::ValueTypeSchema")
+    )]
     pub target_type: ValueType,
 }
 
-#[derive(Debug, Deserialize, PartialEq, Eq)]
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq)]
+#[cfg_attr(feature = "jsonschema", derive(schemars::JsonSchema))]
 #[serde(rename_all = "camelCase")]
 pub struct SplitItemConfig {
+    /// The column to split.
     pub idx: usize,
     pub spec: SplitterType,
     pub delete_after_split: bool,
@@ -37,81 +70,516 @@ pub struct SplitItemConfig {
     // TODO: configurable split_none option
 }
 
-#[derive(Debug, Deserialize, PartialEq, Eq)]
+/// Where the value of an `addItem` comes from.
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq)]
+#[cfg_attr(feature = "jsonschema", derive(schemars::JsonSchema))]
 #[serde(tag = "name", rename_all = "camelCase")]
 pub enum AddItemType {
-    Meta {
-        key: String,
-    },
-    Static {
-        value: String,
-    },
+    /// The value of the key in the metadata given at runtime.
+    Meta { key: String },
+    /// A fixed (stringified) value.
+    Static { value: String },
     #[serde(rename_all = "camelCase")]
     Runtime {
         rt_value: RuntimeValue,
+        /// Whether the value is created once, instead of for every row.
+        #[serde(skip_serializing_if = "Option::is_none")]
         as_singleton: Option<bool>,
     },
     #[serde(rename_all = "camelCase")]
-    RuntimeStateful {
-        rt_value: RuntimeValueStateful,
+    RuntimeStateful { rt_value: RuntimeValueStateful },
+    /// The value of another column, converted to the target type.
+    #[serde(rename_all = "camelCase")]
+    CopyConvertAs {
+        from_idx: usize,
+        /// We default to the standard conversion
+        #[serde(skip_serializing_if = "Option::is_none")]
+        number_format: Option<NumberFormat>,
     },
 }
 
-#[derive(Debug, Deserialize, PartialEq, Eq)]
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq)]
+#[cfg_attr(feature = "jsonschema", derive(schemars::JsonSchema))]
 #[serde(rename_all = "camelCase")]
 pub struct AddItemConfig {
     pub spec: AddItemType,
     pub target: ItemTargetConfig,
+    /// We default to just adding (unless the row is strict)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub on_conflict: Option<OnConflict>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq)]
+#[cfg_attr(feature = "jsonschema", derive(schemars::JsonSchema))]
+#[serde(rename_all = "camelCase")]
+pub struct ColumnRulesConfig {
+    pub idx: usize,
+    /// Only needed for "range" and "oneOf"
+    #[serde(rename = "type", skip_serializing_if = "Option::is_none")]
+    #[cfg_attr(
+        feature = "jsonschema",
+        schemars(with = "Option<crate::conf::json_schema::ValueTypeSchema>")
+    )]
+    pub dtype: Option<ValueType>,
+    pub rules: Vec<RuleConfig>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq)]
+#[cfg_attr(feature = "jsonschema", derive(schemars::JsonSchema))]
+#[serde(rename_all = "camelCase")]
+pub struct ValidateItemsConfig {
+    #[serde(default)]
+    pub columns: Vec<ColumnRulesConfig>,
+    /// Keys (arrays of column indices), that must be unique across all rows.
+    #[serde(default)]
+    pub unique: Vec<Vec<usize>>,
+    /// Whether a row that breaks a rule fails. We default to false
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fail_row: Option<bool>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq)]
+#[cfg_attr(feature = "jsonschema", derive(schemars::JsonSchema))]
+#[serde(rename_all = "camelCase")]
+pub struct MapValuesConfig {
+    pub idx: usize,
+    /// If given, the keys are normalized through this type
+    #[cfg_attr(
+        feature = "jsonschema",
+        schemars(with = "Option<crate::conf::json_schema::ValueTypeSchema>")
+    )]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub key_type: Option<ValueType>,
+    /// We default to String. Ignored, if there is a target.
+    #[cfg_attr(
+        feature = "jsonschema",
+        schemars(with = "Option<crate::conf::json_schema::ValueTypeSchema>")
+    )]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub value_type: Option<ValueType>,
+    /// We default to mapping in place
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub target: Option<ItemTargetConfig>,
+    /// (Sorted, so that a written config doesn't change from run to run.)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub table: Option<BTreeMap<String, String>>,
+    /// .json or .csv
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub table_file: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub default: Option<String>,
+    /// Whether a key that is not in the table (without a default) fails the row. We default to
+    /// false
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub strict: Option<bool>,
+}
+
+/// An edit of a string value.
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq)]
+#[cfg_attr(feature = "jsonschema", derive(schemars::JsonSchema))]
+#[serde(tag = "name", rename_all = "camelCase")]
+pub enum ModifierConfig {
+    Trim {
+        /// We default to "both"
+        #[serde(skip_serializing_if = "Option::is_none")]
+        side: Option<TrimSide>,
+        /// We default to whitespace
+        #[serde(skip_serializing_if = "Option::is_none")]
+        chars: Option<String>,
+    },
+    Case {
+        to: CaseMode,
+    },
+    /// Replaces all occurrences, literally.
+    Replace {
+        from: String,
+        to: String,
+    },
+    /// Replaces all matches. The replacement may reference capture groups, as `$1` or `${name}`.
+    ReplacePattern {
+        pattern: String,
+        replacement: String,
+    },
+    Pad {
+        /// We default to "left"
+        #[serde(skip_serializing_if = "Option::is_none")]
+        side: Option<PadSide>,
+        width: usize,
+        /// We default to ' '
+        #[serde(skip_serializing_if = "Option::is_none")]
+        fill: Option<char>,
+    },
+    Substring {
+        start: usize,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        len: Option<usize>,
+    },
+    #[serde(rename_all = "camelCase")]
+    Truncate {
+        max_len: usize,
+    },
+    Normalize {
+        form: UnicodeForm,
+    },
+    /// Replaces every run of whitespace with a single space, and trims both ends.
+    CollapseWhitespace,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq)]
+#[cfg_attr(feature = "jsonschema", derive(schemars::JsonSchema))]
+#[serde(rename_all = "camelCase")]
+pub struct ModifyItemConfig {
+    pub idxs: Vec<usize>,
+    pub modifiers: Vec<ModifierConfig>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq)]
+#[cfg_attr(feature = "jsonschema", derive(schemars::JsonSchema))]
+#[serde(rename_all = "camelCase")]
+pub struct RoundItemConfig {
+    pub idxs: Vec<usize>,
+    /// The number of decimal places.
+    pub scale: u32,
+    /// We default to "halfUp"
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub mode: Option<RoundingMode>,
+}
+
+/// A condition on the (stringified) values of a row. A missing column counts as `None`.
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq)]
+#[cfg_attr(feature = "jsonschema", derive(schemars::JsonSchema))]
+#[serde(tag = "name", rename_all = "camelCase")]
+pub enum ConditionConfig {
+    Equals { idx: usize, value: String },
+    NotEquals { idx: usize, value: String },
+    OneOf { idx: usize, values: Vec<String> },
+    Matches { idx: usize, pattern: String },
+    IsNone { idx: usize },
+    IsSome { idx: usize },
+    All { conditions: Vec<ConditionConfig> },
+    Any { conditions: Vec<ConditionConfig> },
+    Not { condition: Box<ConditionConfig> },
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq)]
+#[cfg_attr(feature = "jsonschema", derive(schemars::JsonSchema))]
+#[serde(rename_all = "camelCase")]
+pub struct ExplodeItemConfig {
+    pub idx: usize,
+    pub sep_char: char,
+    /// We default to String
+    #[cfg_attr(
+        feature = "jsonschema",
+        schemars(with = "Option<crate::conf::json_schema::ValueTypeSchema>")
+    )]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub target_type: Option<ValueType>,
+    /// Whether a row without any tokens is kept once, with a `None` value. We default to false
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub keep_empty: Option<bool>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq)]
+#[cfg_attr(feature = "jsonschema", derive(schemars::JsonSchema))]
+#[serde(rename_all = "camelCase")]
+pub struct UnpivotConfig {
+    pub idxs: Vec<usize>,
+    /// Gets the headers of the unpivoted columns.
+    pub key: ItemTargetConfig,
+    /// Gets the values of the unpivoted columns.
+    pub value: ItemTargetConfig,
 }
 
-#[derive(Debug, Deserialize, PartialEq, Eq)]
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq)]
+#[cfg_attr(feature = "jsonschema", derive(schemars::JsonSchema))]
+#[serde(rename_all = "camelCase")]
+pub struct LookupEnrichConfig {
+    /// .csv
+    pub file: String,
+    pub keys: Vec<ColumnRef>,
+    pub reference_keys: Vec<ColumnRef>,
+    /// We default to "left"
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub kind: Option<JoinKind>,
+    /// We default to all but the reference keys
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub columns: Option<Vec<ColumnRef>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub left_suffix: Option<String>,
+    /// We default to "_right"
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub right_suffix: Option<String>,
+    /// We default to appending after the highest idx
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub idx_offset: Option<usize>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq)]
+#[cfg_attr(feature = "jsonschema", derive(schemars::JsonSchema))]
+#[serde(rename_all = "camelCase")]
+pub struct DedupStreamConfig {
+    /// We default to the whole row
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub keys: Option<Vec<ColumnRef>>,
+    /// We default to no limit
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_keys: Option<usize>,
+    /// .csv
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub discarded_to: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq)]
+#[cfg_attr(feature = "jsonschema", derive(schemars::JsonSchema))]
+#[serde(rename_all = "camelCase")]
+pub struct WhenConfig {
+    pub condition: ConditionConfig,
+    pub then: Vec<TransformerConfig>,
+    #[serde(rename = "else", default)]
+    pub otherwise: Vec<TransformerConfig>,
+}
+
+/// A transformer, working row by row. See JSONCONF.md.
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq)]
+#[cfg_attr(feature = "jsonschema", derive(schemars::JsonSchema))]
 #[serde(tag = "type", rename_all = "camelCase")]
 pub enum TransformerConfig {
+    /// Deletes the columns with these indices.
     DeleteItems { cfg: Vec<usize> },
+    /// Splits a column into two (new) columns.
     SplitItem { cfg: SplitItemConfig },
+    /// Adds a column.
     AddItem { cfg: AddItemConfig },
+    /// Fails the row, if it doesn't match the schema.
+    AssertSchema { cfg: Schema },
+    /// Checks values against rules and reports the violations.
+    ValidateItems { cfg: ValidateItemsConfig },
+    /// Maps the value of a column through a lookup table.
+    MapValues { cfg: MapValuesConfig },
+    /// Edits string values in place.
+    ModifyItem { cfg: ModifyItemConfig },
+    /// Rounds `Decimal` and float values in place.
+    RoundItem { cfg: RoundItemConfig },
+    /// Runs other transformers, if a condition on the row holds.
+    When { cfg: WhenConfig },
+    /// Splits the value of a column and emits one row per resulting value.
+    ExplodeItem { cfg: ExplodeItemConfig },
+    /// Turns columns into rows.
+    Unpivot { cfg: UnpivotConfig },
+    /// Joins every row with reference rows from a file.
+    LookupEnrich { cfg: LookupEnrichConfig },
+    /// Drops rows with the same key as an earlier row.
+    DedupStream { cfg: DedupStreamConfig },
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq)]
+#[cfg_attr(feature = "jsonschema", derive(schemars::JsonSchema))]
+#[serde(rename_all = "camelCase")]
+pub struct MeltConfig {
+    pub id_idxs: Vec<usize>,
+    /// We default to all other columns
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub value_idxs: Option<Vec<usize>>,
+    pub key: ItemTargetConfig,
+    pub value: ItemTargetConfig,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq)]
+#[cfg_attr(feature = "jsonschema", derive(schemars::JsonSchema))]
+#[serde(rename_all = "camelCase")]
+pub struct PivotConfig {
+    pub id_idxs: Vec<usize>,
+    pub key_idx: usize,
+    pub value_idx: usize,
+    /// We default to "error"
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub aggregation: Option<PivotAggregation>,
+    /// We default to all distinct keys
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub columns: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub header_prefix: Option<String>,
+    /// We default to the one after the highest id idx
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub start_idx: Option<usize>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq)]
+#[cfg_attr(feature = "jsonschema", derive(schemars::JsonSchema))]
+#[serde(rename_all = "camelCase")]
+pub struct AggregationConfig {
+    /// Only count works without
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub column: Option<ColumnRef>,
+    pub function: AggregateFunction,
+    /// Only for stringAgg, we default to ","
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub separator: Option<String>,
+    pub idx: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub header: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq)]
+#[cfg_attr(feature = "jsonschema", derive(schemars::JsonSchema))]
+#[serde(rename_all = "camelCase")]
+pub struct GroupByConfig {
+    pub keys: Vec<ColumnRef>,
+    pub aggregations: Vec<AggregationConfig>,
 }
 
-#[derive(Debug, Deserialize, PartialEq, Eq)]
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq)]
+#[cfg_attr(feature = "jsonschema", derive(schemars::JsonSchema))]
+#[serde(rename_all = "camelCase")]
+pub struct SortConfig {
+    pub keys: Vec<SortKey>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq)]
+#[cfg_attr(feature = "jsonschema", derive(schemars::JsonSchema))]
+#[serde(rename_all = "camelCase")]
+pub struct DedupConfig {
+    /// We default to the whole row
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub keys: Option<Vec<ColumnRef>>,
+    /// We default to "first"
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub keep: Option<DedupKeep>,
+    /// .csv
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub discarded_to: Option<String>,
+}
+
+/// A table transformer, working on all rows at once. See JSONCONF.md.
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq)]
+#[cfg_attr(feature = "jsonschema", derive(schemars::JsonSchema))]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum TableTransformerConfig {
+    /// Turns value columns into rows.
+    Melt { cfg: MeltConfig },
+    /// Turns the distinct values of a key column into columns.
+    Pivot { cfg: PivotConfig },
+    /// Groups the rows by key columns and aggregates the other columns.
+    GroupBy { cfg: GroupByConfig },
+    /// Sorts the rows by key columns.
+    Sort { cfg: SortConfig },
+    /// Removes duplicate rows.
+    Dedup { cfg: DedupConfig },
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq)]
+#[cfg_attr(feature = "jsonschema", derive(schemars::JsonSchema))]
 pub struct OrderItemsEntry {
     pub from: usize,
     pub to: usize,
 }
 
-#[derive(Debug, Deserialize, PartialEq, Eq)]
+#[derive(Debug, Clone, Default, Deserialize, Serialize, PartialEq, Eq)]
+#[cfg_attr(feature = "jsonschema", derive(schemars::JsonSchema))]
+#[serde(rename_all = "camelCase")]
+pub struct OrderItemsOptions {
+    /// We default to "move"
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub mode: Option<OrderMode>,
+    /// We default to "none"
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub validation: Option<OrderValidation>,
+    /// Whether the indices are re-assigned to 0..n, keeping their order. We default to false
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub compact: Option<bool>,
+}
+
+/// A transrichment pass.
+#[derive(Debug, Clone, Default, Deserialize, Serialize, PartialEq, Eq)]
+#[cfg_attr(feature = "jsonschema", derive(schemars::JsonSchema))]
 #[serde(rename_all = "camelCase")]
 pub struct TransformEnrichPassConfig {
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub comment: Option<String>,
+    /// Whether rows are in strict mode in this pass, i.e. adding a column whose index or header is
+    /// already in use fails (unless `onConflict` says otherwise). We default to false
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub strict: Option<bool>,
     pub transformers: Vec<TransformerConfig>,
+    /// Re-assigns column indices, after the transformers.
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub order_items: Option<Vec<OrderItemsEntry>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub order_items_options: Option<OrderItemsOptions>,
+    /// Run after every row went through the rest of the pass.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub table_transformers: Option<Vec<TableTransformerConfig>>,
 }
 
-#[derive(Debug, Deserialize, PartialEq, Eq)]
+/// A transrichment configuration, i.e. the passes, in order. See JSONCONF.md.
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq)]
+#[cfg_attr(feature = "jsonschema", derive(schemars::JsonSchema))]
 #[serde(rename_all = "camelCase")]
 pub struct ConfigRoot(pub Vec<TransformEnrichPassConfig>);
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::{conf::load::ConfigFormat, schema::SchemaColumn};
 
-    // #[test]
-    // fn add_item_copy_convert_as() {
-    //     let data = r#"
-    //     {
-    //         "type": "addItem",
-    //         "cfg": {
-    //             "spec": { "name": "copyConvertAs", "fromIdx": 1, "comment": "transaction_date" },
-    //             "target": { "idx": 27, "header": "_transaction_date_start", "targetType": "DateTime" }
-    //         }
-    //     }
-    //     "#;
-    //     assert_eq!(
-    //         TransformerConfig::DeleteItems {
-    //             cfg: vec![0_usize, 1_usize]
-    //         },
-    //         serde_json::from_str(data).expect("could not deserialize ")
-    //     )
-    // }
+    #[test]
+    fn add_item_copy_convert_as() {
+        let data = r#"
+        {
+            "type": "addItem",
+            "cfg": {
+                "spec": {
+                    "name": "copyConvertAs",
+                    "fromIdx": 1,
+                    "numberFormat": { "decimalSeparator": ",", "groupingSeparator": ".", "percent": true }
+                },
+                "target": { "idx": 27, "header": "rate", "targetType": "Decimal" }
+            }
+        }
+        "#;
+        assert_eq!(
+            TransformerConfig::AddItem {
+                cfg: AddItemConfig {
+                    spec: AddItemType::CopyConvertAs {
+                        from_idx: 1,
+                        number_format: Some(NumberFormat {
+                            decimal_separator: ',',
+                            grouping_separator: Some('.'),
+                            percent: true,
+                            ..NumberFormat::default()
+                        })
+                    },
+                    target: ItemTargetConfig {
+                        header: Some(String::from("rate")),
+                        idx: 27_usize,
+                        target_type: ValueType::Decimal
+                    },
+                    on_conflict: None
+                }
+            },
+            serde_json::from_str(data).expect("could not deserialize ")
+        )
+    }
+
+    #[test]
+    fn transformer_config_round_item() {
+        let data = r#"
+        {
+            "type": "roundItem",
+            "cfg": { "idxs": [3], "scale": 2, "mode": "halfEven" }
+        }
+        "#;
+        assert_eq!(
+            TransformerConfig::RoundItem {
+                cfg: RoundItemConfig {
+                    idxs: vec![3],
+                    scale: 2,
+                    mode: Some(RoundingMode::HalfEven)
+                }
+            },
+            serde_json::from_str(data).expect("could not deserialize ")
+        )
+    }
 
     #[test]
     fn transformer_config_delete_items() {
@@ -257,7 +725,127 @@ mod tests {
                         header: Some(String::from("Region")),
                         idx: 12_usize,
                         target_type: ValueType::String
+                    },
+                    on_conflict: None
+                }
+            },
+            serde_json::from_str(data).expect("could not deserialize ")
+        )
+    }
+
+    #[test]
+    fn transformer_config_add_item_on_conflict() {
+        let data = r#"
+        {
+            "type": "addItem",
+            "cfg": {
+                "spec": {
+                    "name": "static",
+                    "value": "Europe"
+                },
+                "target": {
+                    "idx": 12,
+                    "targetType": "String"
+                },
+                "onConflict": "replace"
+            }
+        }
+        "#;
+        match serde_json::from_str(data).expect("could not deserialize ") {
+            TransformerConfig::AddItem { cfg } => {
+                assert_eq!(Some(OnConflict::Replace), cfg.on_conflict)
+            }
+            other => panic!("unexpected config: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn transformer_config_assert_schema() {
+        let data = r#"
+        {
+            "type": "assertSchema",
+            "cfg": {
+                "columns": [
+                    { "idx": 0, "name": "amount", "type": "Float32", "nullable": false }
+                ]
+            }
+        }
+        "#;
+        assert_eq!(
+            TransformerConfig::AssertSchema {
+                cfg: Schema::new(vec![SchemaColumn::new(
+                    0,
+                    Some(String::from("amount")),
+                    ValueType::Float32,
+                    false
+                )])
+            },
+            serde_json::from_str(data).expect("could not deserialize ")
+        )
+    }
+
+    #[test]
+    fn transformer_config_validate_items() {
+        let data = r#"
+        {
+            "type": "validateItems",
+            "cfg": {
+                "columns": [
+                    {
+                        "idx": 1,
+                        "type": "Int32",
+                        "rules": [
+                            { "name": "notNull" },
+                            { "name": "range", "min": "0" },
+                            { "name": "oneOf", "values": ["1", "2"] }
+                        ]
+                    },
+                    {
+                        "idx": 2,
+                        "rules": [
+                            { "name": "length", "max": 3 },
+                            { "name": "pattern", "pattern": "^[A-Z]+$" }
+                        ]
                     }
+                ],
+                "unique": [[0], [1, 2]]
+            }
+        }
+        "#;
+        assert_eq!(
+            TransformerConfig::ValidateItems {
+                cfg: ValidateItemsConfig {
+                    columns: vec![
+                        ColumnRulesConfig {
+                            idx: 1,
+                            dtype: Some(ValueType::Int32),
+                            rules: vec![
+                                RuleConfig::NotNull,
+                                RuleConfig::Range {
+                                    min: Some(String::from("0")),
+                                    max: None
+                                },
+                                RuleConfig::OneOf {
+                                    values: vec![String::from("1"), String::from("2")]
+                                },
+                            ]
+                        },
+                        ColumnRulesConfig {
+                            idx: 2,
+                            dtype: None,
+                            rules: vec![
+                                RuleConfig::Length {
+                                    min: None,
+                                    max: Some(3)
+                                },
+                                RuleConfig::Pattern {
+                                    pattern: String::from("^[A-Z]+$")
+                                },
+                            ]
+                        },
+                    ],
+                    unique: vec![vec![0], vec![1, 2]],
+                    fail_row: None,
                 }
             },
             serde_json::from_str(data).expect("could not deserialize ")
@@ -292,6 +880,21 @@ mod tests {
         )
     }
 
+    #[test]
+    pub fn order_items_options() {
+        let data = r#"
+        { "mode": "swap", "validation": "permutation", "compact": true }
+        "#;
+        assert_eq!(
+            OrderItemsOptions {
+                mode: Some(OrderMode::Swap),
+                validation: Some(OrderValidation::Permutation),
+                compact: Some(true),
+            },
+            serde_json::from_str::<OrderItemsOptions>(data).expect("could not deserialize ")
+        )
+    }
+
     #[test]
     fn transform_enrich_pass_config() {
         let data = r#"
@@ -371,6 +974,7 @@ mod tests {
                         to: 2_usize
                     }
                 ]),
+                ..Default::default()
             },
             serde_json::from_str(data).expect("could not deserialize ")
         )
@@ -476,6 +1080,7 @@ mod tests {
                             to: 2_usize
                         }
                     ]),
+                    ..Default::default()
                 },
                 TransformEnrichPassConfig {
                     comment: Some(String::from("pass2")),
@@ -488,16 +1093,559 @@ mod tests {
                                 header: Some(String::from("Region")),
                                 idx: 12_usize,
                                 target_type: ValueType::String
-                            }
+                            },
+                            on_conflict: None
                         }
                     }],
                     order_items: Some(vec![OrderItemsEntry {
                         from: 12_usize,
                         to: 3_usize
                     }]),
+                    ..Default::default()
                 },
             ]),
             serde_json::from_str(data).expect("could not deserialize ")
         )
     }
+
+    #[test]
+    fn transformer_config_map_values() {
+        let data = r#"
+        {
+            "type": "mapValues",
+            "cfg": {
+                "idx": 3,
+                "target": { "idx": 4, "header": "country", "targetType": "String" },
+                "table": { "DE": "Germany", "CH": "Switzerland" },
+                "default": "unknown"
+            }
+        }
+        "#;
+        let config: TransformerConfig = serde_json::from_str(data).expect("could not deserialize ");
+        assert_eq!(
+            TransformerConfig::MapValues {
+                cfg: MapValuesConfig {
+                    idx: 3,
+                    key_type: None,
+                    value_type: None,
+                    target: Some(ItemTargetConfig {
+                        idx: 4,
+                        header: Some(String::from("country")),
+                        target_type: ValueType::String
+                    }),
+                    table: Some(BTreeMap::from([
+                        (String::from("CH"), String::from("Switzerland")),
+                        (String::from("DE"), String::from("Germany")),
+                    ])),
+                    table_file: None,
+                    default: Some(String::from("unknown")),
+                    strict: None
+                }
+            },
+            config
+        );
+        // the table is written in key order
+        assert!(serde_json::to_string(&config)
+            .unwrap()
+            .contains(r#""table":{"CH":"Switzerland","DE":"Germany"}"#));
+    }
+
+    #[test]
+    fn transformer_config_modify_item() {
+        let data = r#"
+        {
+            "type": "modifyItem",
+            "cfg": {
+                "idxs": [0, 2],
+                "modifiers": [
+                    { "name": "trim", "chars": " _" },
+                    { "name": "case", "to": "title" },
+                    { "name": "pad", "width": 8, "fill": "0" },
+                    { "name": "truncate", "maxLen": 4 },
+                    { "name": "normalize", "form": "nfkc" },
+                    { "name": "collapseWhitespace" }
+                ]
+            }
+        }
+        "#;
+        assert_eq!(
+            TransformerConfig::ModifyItem {
+                cfg: ModifyItemConfig {
+                    idxs: vec![0, 2],
+                    modifiers: vec![
+                        ModifierConfig::Trim {
+                            side: None,
+                            chars: Some(String::from(" _"))
+                        },
+                        ModifierConfig::Case {
+                            to: CaseMode::Title
+                        },
+                        ModifierConfig::Pad {
+                            side: None,
+                            width: 8,
+                            fill: Some('0')
+                        },
+                        ModifierConfig::Truncate { max_len: 4 },
+                        ModifierConfig::Normalize {
+                            form: UnicodeForm::Nfkc
+                        },
+                        ModifierConfig::CollapseWhitespace,
+                    ]
+                }
+            },
+            serde_json::from_str(data).expect("could not deserialize ")
+        )
+    }
+
+    #[test]
+    fn transformer_config_when() {
+        let data = r#"
+        {
+            "type": "when",
+            "cfg": {
+                "condition": {
+                    "name": "any",
+                    "conditions": [
+                        { "name": "equals", "idx": 4, "value": "EUR" },
+                        { "name": "not", "condition": { "name": "isNone", "idx": 7 } }
+                    ]
+                },
+                "then": [ { "type": "deleteItems", "cfg": [1] } ],
+                "else": [ { "type": "deleteItems", "cfg": [2] } ]
+            }
+        }
+        "#;
+        assert_eq!(
+            TransformerConfig::When {
+                cfg: WhenConfig {
+                    condition: ConditionConfig::Any {
+                        conditions: vec![
+                            ConditionConfig::Equals {
+                                idx: 4,
+                                value: String::from("EUR")
+                            },
+                            ConditionConfig::Not {
+                                condition: Box::new(ConditionConfig::IsNone { idx: 7 })
+                            }
+                        ]
+                    },
+                    then: vec![TransformerConfig::DeleteItems { cfg: vec![1] }],
+                    otherwise: vec![TransformerConfig::DeleteItems { cfg: vec![2] }]
+                }
+            },
+            serde_json::from_str(data).expect("could not deserialize ")
+        )
+    }
+
+    #[test]
+    fn transformer_config_explode_item_and_unpivot() {
+        let data = r#"
+        [
+            { "type": "explodeItem", "cfg": { "idx": 3, "sepChar": ";" } },
+            {
+                "type": "unpivot",
+                "cfg": {
+                    "idxs": [5, 6],
+                    "key": { "idx": 5, "header": "month", "targetType": "String" },
+                    "value": { "idx": 6, "targetType": "Decimal" }
+                }
+            }
+        ]
+        "#;
+        assert_eq!(
+            vec![
+                TransformerConfig::ExplodeItem {
+                    cfg: ExplodeItemConfig {
+                        idx: 3,
+                        sep_char: ';',
+                        target_type: None,
+                        keep_empty: None
+                    }
+                },
+                TransformerConfig::Unpivot {
+                    cfg: UnpivotConfig {
+                        idxs: vec![5, 6],
+                        key: ItemTargetConfig {
+                            idx: 5,
+                            header: Some(String::from("month")),
+                            target_type: ValueType::String
+                        },
+                        value: ItemTargetConfig {
+                            idx: 6,
+                            header: None,
+                            target_type: ValueType::Decimal
+                        }
+                    }
+                }
+            ],
+            serde_json::from_str::<Vec<TransformerConfig>>(data).expect("could not deserialize ")
+        )
+    }
+
+    #[test]
+    fn table_transformers() {
+        let data = r#"
+        {
+            "transformers": [],
+            "tableTransformers": [{
+                "type": "pivot",
+                "cfg": { "idIdxs": [0], "keyIdx": 1, "valueIdx": 2, "aggregation": "sum", "headerPrefix": "m_" }
+            }, {
+                "type": "melt",
+                "cfg": {
+                    "idIdxs": [0],
+                    "key": { "idx": 1, "header": "month", "targetType": "String" },
+                    "value": { "idx": 2, "header": "amount", "targetType": "Decimal" }
+                }
+            }]
+        }
+        "#;
+        assert_eq!(
+            TransformEnrichPassConfig {
+                table_transformers: Some(vec![
+                    TableTransformerConfig::Pivot {
+                        cfg: PivotConfig {
+                            id_idxs: vec![0],
+                            key_idx: 1,
+                            value_idx: 2,
+                            aggregation: Some(PivotAggregation::Sum),
+                            columns: None,
+                            header_prefix: Some(String::from("m_")),
+                            start_idx: None,
+                        }
+                    },
+                    TableTransformerConfig::Melt {
+                        cfg: MeltConfig {
+                            id_idxs: vec![0],
+                            value_idxs: None,
+                            key: ItemTargetConfig {
+                                idx: 1,
+                                header: Some(String::from("month")),
+                                target_type: ValueType::String,
+                            },
+                            value: ItemTargetConfig {
+                                idx: 2,
+                                header: Some(String::from("amount")),
+                                target_type: ValueType::Decimal,
+                            },
+                        }
+                    },
+                ]),
+                ..Default::default()
+            },
+            serde_json::from_str::<TransformEnrichPassConfig>(data)
+                .expect("could not deserialize ")
+        )
+    }
+
+    #[test]
+    fn group_by() {
+        let data = r#"
+        {
+            "type": "groupBy",
+            "cfg": {
+                "keys": [0, "month"],
+                "aggregations": [
+                    { "function": "count", "idx": 2, "header": "bookings" },
+                    { "column": "amount", "function": "sum", "idx": 3 },
+                    { "column": 4, "function": "stringAgg", "separator": "|", "idx": 4 }
+                ]
+            }
+        }
+        "#;
+        assert_eq!(
+            TableTransformerConfig::GroupBy {
+                cfg: GroupByConfig {
+                    keys: vec![ColumnRef::Idx(0), ColumnRef::Name(String::from("month"))],
+                    aggregations: vec![
+                        AggregationConfig {
+                            column: None,
+                            function: AggregateFunction::Count,
+                            separator: None,
+                            idx: 2,
+                            header: Some(String::from("bookings")),
+                        },
+                        AggregationConfig {
+                            column: Some(ColumnRef::Name(String::from("amount"))),
+                            function: AggregateFunction::Sum,
+                            separator: None,
+                            idx: 3,
+                            header: None,
+                        },
+                        AggregationConfig {
+                            column: Some(ColumnRef::Idx(4)),
+                            function: AggregateFunction::StringAgg,
+                            separator: Some(String::from("|")),
+                            idx: 4,
+                            header: None,
+                        },
+                    ],
+                }
+            },
+            serde_json::from_str::<TableTransformerConfig>(data).expect("could not deserialize ")
+        )
+    }
+
+    #[test]
+    fn sort() {
+        use crate::transform::{data_cell_table::sort::NullOrder, value::ordering::Collation};
+
+        let data = r#"
+        {
+            "type": "sort",
+            "cfg": {
+                "keys": [
+                    { "column": "name", "collation": { "natural": true } },
+                    { "column": 2, "descending": true, "nulls": "first" }
+                ]
+            }
+        }
+        "#;
+        assert_eq!(
+            TableTransformerConfig::Sort {
+                cfg: SortConfig {
+                    keys: vec![
+                        SortKey {
+                            column: ColumnRef::Name(String::from("name")),
+                            descending: false,
+                            nulls: NullOrder::Last,
+                            collation: Collation {
+                                case_insensitive: false,
+                                natural: true,
+                            },
+                        },
+                        SortKey {
+                            column: ColumnRef::Idx(2),
+                            descending: true,
+                            nulls: NullOrder::First,
+                            collation: Collation::default(),
+                        },
+                    ],
+                }
+            },
+            serde_json::from_str::<TableTransformerConfig>(data).expect("could not deserialize ")
+        )
+    }
+
+    #[test]
+    fn dedup() {
+        let data = r#"
+        {
+            "type": "dedup",
+            "cfg": {
+                "keys": ["booking_id"],
+                "keep": { "max": "version" },
+                "discardedTo": "duplicates.csv"
+            }
+        }
+        "#;
+        assert_eq!(
+            TableTransformerConfig::Dedup {
+                cfg: DedupConfig {
+                    keys: Some(vec![ColumnRef::Name(String::from("booking_id"))]),
+                    keep: Some(DedupKeep::Max(ColumnRef::Name(String::from("version")))),
+                    discarded_to: Some(String::from("duplicates.csv")),
+                }
+            },
+            serde_json::from_str::<TableTransformerConfig>(data).expect("could not deserialize ")
+        );
+
+        let data = r#"{ "type": "dedup", "cfg": { "keep": "last" } }"#;
+        assert_eq!(
+            TableTransformerConfig::Dedup {
+                cfg: DedupConfig {
+                    keys: None,
+                    keep: Some(DedupKeep::Last),
+                    discarded_to: None,
+                }
+            },
+            serde_json::from_str::<TableTransformerConfig>(data).expect("could not deserialize ")
+        );
+
+        let data = r#"{ "type": "dedupStream", "cfg": { "keys": [0, 3], "maxKeys": 1000000 } }"#;
+        assert_eq!(
+            TransformerConfig::DedupStream {
+                cfg: DedupStreamConfig {
+                    keys: Some(vec![ColumnRef::Idx(0), ColumnRef::Idx(3)]),
+                    max_keys: Some(1000000),
+                    discarded_to: None,
+                }
+            },
+            serde_json::from_str::<TransformerConfig>(data).expect("could not deserialize ")
+        );
+    }
+
+    #[test]
+    fn lookup_enrich() {
+        let data = r#"
+        {
+            "type": "lookupEnrich",
+            "cfg": {
+                "file": "accounts.csv",
+                "keys": [2],
+                "referenceKeys": ["account_id"],
+                "columns": ["owner", 3],
+                "rightSuffix": "_acc"
+            }
+        }
+        "#;
+        assert_eq!(
+            TransformerConfig::LookupEnrich {
+                cfg: LookupEnrichConfig {
+                    file: String::from("accounts.csv"),
+                    keys: vec![ColumnRef::Idx(2)],
+                    reference_keys: vec![ColumnRef::Name(String::from("account_id"))],
+                    kind: None,
+                    columns: Some(vec![
+                        ColumnRef::Name(String::from("owner")),
+                        ColumnRef::Idx(3)
+                    ]),
+                    left_suffix: None,
+                    right_suffix: Some(String::from("_acc")),
+                    idx_offset: None,
+                }
+            },
+            serde_json::from_str::<TransformerConfig>(data).expect("could not deserialize ")
+        )
+    }
+
+    #[test]
+    fn round_trip() {
+        // every transformer and table transformer, with (almost) every option set
+        let data = r#"
+        [
+            {
+                "comment": "pass1",
+                "transformers": [
+                    { "type": "deleteItems", "cfg": [0, 1] },
+                    {
+                        "type": "splitItem",
+                        "cfg": {
+                            "idx": 2,
+                            "spec": { "name": "pattern", "pattern": "(\\d+) (.+)", "splitNone": false },
+                            "deleteAfterSplit": true,
+                            "targetLeft": { "idx": 10, "header": "amount", "targetType": "Decimal" },
+                            "targetRight": { "idx": 11, "targetType": "String" }
+                        }
+                    },
+                    {
+                        "type": "addItem",
+                        "cfg": {
+                            "spec": { "name": "runtime", "rtValue": "CurrentDateTimeUtcAsFixedOffset", "asSingleton": true },
+                            "target": { "idx": 12, "targetType": "DateTime" },
+                            "onConflict": "replace"
+                        }
+                    },
+                    {
+                        "type": "addItem",
+                        "cfg": {
+                            "spec": { "name": "runtimeStateful", "rtValue": "RowEnumeration" },
+                            "target": { "idx": 13, "targetType": "UInt128" }
+                        }
+                    },
+                    {
+                        "type": "assertSchema",
+                        "cfg": { "columns": [{ "idx": 0, "name": "id", "type": "Int32", "nullable": false }], "allowExtra": true }
+                    },
+                    {
+                        "type": "validateItems",
+                        "cfg": {
+                            "columns": [{ "idx": 2, "type": "Decimal", "rules": [{ "name": "notNull" }, { "name": "range", "min": "0" }] }],
+                            "unique": [[0]],
+                            "failRow": true
+                        }
+                    },
+                    {
+                        "type": "mapValues",
+                        "cfg": { "idx": 3, "keyType": "Int32", "table": { "1": "one" }, "default": "many", "strict": false }
+                    },
+                    {
+                        "type": "modifyItem",
+                        "cfg": {
+                            "idxs": [3],
+                            "modifiers": [
+                                { "name": "trim", "side": "left", "chars": "_" },
+                                { "name": "case", "to": "upper" },
+                                { "name": "pad", "width": 8, "fill": "0" },
+                                { "name": "normalize", "form": "nfkc" },
+                                { "name": "collapseWhitespace" }
+                            ]
+                        }
+                    },
+                    { "type": "roundItem", "cfg": { "idxs": [10], "scale": 2, "mode": "halfEven" } },
+                    {
+                        "type": "when",
+                        "cfg": {
+                            "condition": { "name": "not", "condition": { "name": "isNone", "idx": 7 } },
+                            "then": [{ "type": "deleteItems", "cfg": [7] }]
+                        }
+                    },
+                    { "type": "explodeItem", "cfg": { "idx": 4, "sepChar": ";", "targetType": "Int8", "keepEmpty": true } },
+                    {
+                        "type": "unpivot",
+                        "cfg": {
+                            "idxs": [5, 6],
+                            "key": { "idx": 5, "header": "month", "targetType": "String" },
+                            "value": { "idx": 6, "targetType": "Float64" }
+                        }
+                    },
+                    {
+                        "type": "lookupEnrich",
+                        "cfg": { "file": "accounts.csv", "keys": [2], "referenceKeys": ["id"], "kind": "semi", "idxOffset": 100 }
+                    },
+                    { "type": "dedupStream", "cfg": { "keys": ["id"], "maxKeys": 10 } }
+                ],
+                "orderItems": [{ "from": 10, "to": 0 }],
+                "orderItemsOptions": { "mode": "swap", "validation": "unique", "compact": true }
+            },
+            {
+                "transformers": [],
+                "tableTransformers": [
+                    {
+                        "type": "melt",
+                        "cfg": {
+                            "idIdxs": [0],
+                            "key": { "idx": 1, "targetType": "String" },
+                            "value": { "idx": 2, "targetType": "Decimal" }
+                        }
+                    },
+                    { "type": "pivot", "cfg": { "idIdxs": [0], "keyIdx": 1, "valueIdx": 2, "aggregation": "sum", "columns": ["jan"] } },
+                    {
+                        "type": "groupBy",
+                        "cfg": { "keys": [0], "aggregations": [{ "function": "stringAgg", "column": "tag", "separator": "|", "idx": 1 }] }
+                    },
+                    {
+                        "type": "sort",
+                        "cfg": { "keys": [{ "column": 1, "descending": true, "nulls": "first", "collation": { "natural": true } }] }
+                    },
+                    { "type": "dedup", "cfg": { "keep": { "max": "version" }, "discardedTo": "dups.csv" } }
+                ]
+            }
+        ]
+        "#;
+        let config: ConfigRoot = serde_json::from_str(data).expect("could not deserialize ");
+        let formats = [
+            ConfigFormat::Json,
+            #[cfg(feature = "yaml")]
+            ConfigFormat::Yaml,
+            #[cfg(feature = "toml")]
+            ConfigFormat::Toml,
+        ];
+        for format in formats {
+            let written = config
+                .to_string_with_format(format)
+                .expect("could not serialize ");
+            assert_eq!(
+                config,
+                ConfigRoot::from_str_with_format(&written, format)
+                    .expect("could not deserialize again "),
+                "{}",
+                written
+            );
+        }
+
+        // options that are not set are left out, not written as null
+        let written = config.to_string_with_format(ConfigFormat::Json).unwrap();
+        assert!(!written.contains(": null"), "{}", written);
+    }
 }
@@ -0,0 +1,235 @@
+use std::fs;
+use std::path::Path;
+
+use super::jsonconf::ConfigRoot;
+#[cfg(feature = "toml")]
+use super::jsonconf::TransformEnrichPassConfig;
+
+use crate::errors::{IoErrors, Result, VenumTdsError};
+
+fn config_err<E: std::fmt::Display>(format: ConfigFormat, e: E) -> VenumTdsError {
+    VenumTdsError::Generic {
+        msg: format!("invalid {} config: {}", format.name(), e),
+    }
+}
+
+fn write_err<E: std::fmt::Display>(format: ConfigFormat, e: E) -> VenumTdsError {
+    VenumTdsError::Generic {
+        msg: format!("can't write {} config: {}", format.name(), e),
+    }
+}
+
+/// The formats a `ConfigRoot` can be written in. They all describe the same structure (see
+/// JSONCONF.md), only toml needs a table at the top, so the passes go into `passes`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConfigFormat {
+    Json,
+    /// Needs the `yaml` feature.
+    Yaml,
+    /// Needs the `toml` feature.
+    Toml,
+}
+
+impl ConfigFormat {
+    /// The format by the extension of the file: `.yaml`/`.yml` and `.toml`, anything else is json.
+    pub fn from_path<P: AsRef<Path>>(path: P) -> Self {
+        match path.as_ref().extension().and_then(|e| e.to_str()) {
+            Some("yaml" | "yml") => ConfigFormat::Yaml,
+            Some("toml") => ConfigFormat::Toml,
+            _ => ConfigFormat::Json,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            ConfigFormat::Json => "json",
+            ConfigFormat::Yaml => "yaml",
+            ConfigFormat::Toml => "toml",
+        }
+    }
+}
+
+#[cfg(feature = "toml")]
+#[derive(serde::Deserialize)]
+struct TomlConfigRoot {
+    passes: Vec<TransformEnrichPassConfig>,
+}
+
+#[cfg(feature = "toml")]
+#[derive(serde::Serialize)]
+struct TomlConfigRootRef<'a> {
+    passes: &'a [TransformEnrichPassConfig],
+}
+
+impl ConfigRoot {
+    pub fn from_str_with_format(src: &str, format: ConfigFormat) -> Result<Self> {
+        match format {
+            ConfigFormat::Json => serde_json::from_str(src).map_err(|e| config_err(format, e)),
+            #[cfg(feature = "yaml")]
+            ConfigFormat::Yaml => serde_yaml::from_str(src).map_err(|e| config_err(format, e)),
+            #[cfg(feature = "toml")]
+            ConfigFormat::Toml => toml::from_str::<TomlConfigRoot>(src)
+                .map(|root| ConfigRoot(root.passes))
+                .map_err(|e| config_err(format, e)),
+            #[allow(unreachable_patterns)]
+            _ => Err(config_err(
+                format,
+                format!("needs the {} feature", format.name()),
+            )),
+        }
+    }
+
+    /// Writes the config in the format. Reading it back (see `from_str_with_format`) gives the same
+    /// config. Options that are not set are left out.
+    pub fn to_string_with_format(&self, format: ConfigFormat) -> Result<String> {
+        match format {
+            ConfigFormat::Json => {
+                serde_json::to_string_pretty(self).map_err(|e| write_err(format, e))
+            }
+            #[cfg(feature = "yaml")]
+            ConfigFormat::Yaml => serde_yaml::to_string(self).map_err(|e| write_err(format, e)),
+            #[cfg(feature = "toml")]
+            ConfigFormat::Toml => toml::to_string(&TomlConfigRootRef { passes: &self.0 })
+                .map_err(|e| write_err(format, e)),
+            #[allow(unreachable_patterns)]
+            _ => Err(write_err(
+                format,
+                format!("needs the {} feature", format.name()),
+            )),
+        }
+    }
+
+    /// Loads the config from a file, in the format given by its extension (see
+    /// `ConfigFormat::from_path`).
+    pub fn from_path<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let src = fs::read_to_string(path.as_ref()).map_err(|e| {
+            VenumTdsError::Io(IoErrors::File {
+                path: path.as_ref().display().to_string(),
+                msg: format!("{}", e),
+            })
+        })?;
+        Self::from_str_with_format(&src, ConfigFormat::from_path(path))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const JSON: &str = r#"
+    [
+        {
+            "comment": "pass1",
+            "transformers": [
+                { "type": "deleteItems", "cfg": [0, 1] },
+                {
+                    "type": "splitItem",
+                    "cfg": {
+                        "idx": 2,
+                        "spec": { "name": "pattern", "pattern": "(\\d+\\.\\d+) \\(([[:alpha:]].+)\\)" },
+                        "deleteAfterSplit": true,
+                        "targetLeft": { "idx": 10, "header": "amount", "targetType": "Float32" },
+                        "targetRight": { "idx": 11, "header": "currency", "targetType": "String" }
+                    }
+                }
+            ],
+            "orderItems": [{ "from": 10, "to": 0 }]
+        }
+    ]
+    "#;
+
+    #[test]
+    fn format_from_path() {
+        assert_eq!(ConfigFormat::Yaml, ConfigFormat::from_path("conf/pass.yml"));
+        assert_eq!(ConfigFormat::Yaml, ConfigFormat::from_path("pass.yaml"));
+        assert_eq!(ConfigFormat::Toml, ConfigFormat::from_path("pass.toml"));
+        assert_eq!(ConfigFormat::Json, ConfigFormat::from_path("pass.json"));
+        assert_eq!(ConfigFormat::Json, ConfigFormat::from_path("pass"));
+    }
+
+    #[test]
+    fn json() {
+        let config = ConfigRoot::from_str_with_format(JSON, ConfigFormat::Json).unwrap();
+        assert_eq!(1, config.0.len());
+        assert!(ConfigRoot::from_str_with_format("{}", ConfigFormat::Json).is_err());
+    }
+
+    #[test]
+    fn round_trip() {
+        let config = ConfigRoot::from_str_with_format(JSON, ConfigFormat::Json).unwrap();
+        let formats = [
+            ConfigFormat::Json,
+            #[cfg(feature = "yaml")]
+            ConfigFormat::Yaml,
+            #[cfg(feature = "toml")]
+            ConfigFormat::Toml,
+        ];
+        for format in formats {
+            let written = config.to_string_with_format(format).unwrap();
+            assert_eq!(
+                config,
+                ConfigRoot::from_str_with_format(&written, format).unwrap(),
+                "{}",
+                written
+            );
+        }
+    }
+
+    #[cfg(feature = "yaml")]
+    #[test]
+    fn yaml() {
+        let data = r#"
+# a real comment
+- comment: pass1
+  transformers:
+    - type: deleteItems
+      cfg: [0, 1]
+    - type: splitItem
+      cfg:
+        idx: 2
+        spec:
+          name: pattern
+          # no double escaping
+          pattern: '(\d+\.\d+) \(([[:alpha:]].+)\)'
+        deleteAfterSplit: true
+        targetLeft: { idx: 10, header: amount, targetType: Float32 }
+        targetRight: { idx: 11, header: currency, targetType: String }
+  orderItems:
+    - { from: 10, to: 0 }
+"#;
+        assert_eq!(
+            ConfigRoot::from_str_with_format(JSON, ConfigFormat::Json).unwrap(),
+            ConfigRoot::from_str_with_format(data, ConfigFormat::Yaml).unwrap()
+        );
+    }
+
+    #[cfg(feature = "toml")]
+    #[test]
+    fn toml() {
+        let data = r#"
+# a real comment
+[[passes]]
+comment = "pass1"
+orderItems = [{ from = 10, to = 0 }]
+
+[[passes.transformers]]
+type = "deleteItems"
+cfg = [0, 1]
+
+[[passes.transformers]]
+type = "splitItem"
+
+[passes.transformers.cfg]
+idx = 2
+# no double escaping
+spec = { name = "pattern", pattern = '(\d+\.\d+) \(([[:alpha:]].+)\)' }
+deleteAfterSplit = true
+targetLeft = { idx = 10, header = "amount", targetType = "Float32" }
+targetRight = { idx = 11, header = "currency", targetType = "String" }
+"#;
+        assert_eq!(
+            ConfigRoot::from_str_with_format(JSON, ConfigFormat::Json).unwrap(),
+            ConfigRoot::from_str_with_format(data, ConfigFormat::Toml).unwrap()
+        );
+    }
+}
@@ -1,4 +1,12 @@
 #[cfg(feature = "jsonconf")]
+pub mod builder;
+#[cfg(feature = "jsonconf")]
 pub mod from_jsonconf;
+#[cfg(feature = "jsonschema")]
+pub mod json_schema;
 #[cfg(feature = "jsonconf")]
 pub mod jsonconf;
+#[cfg(feature = "jsonconf")]
+pub mod load;
+#[cfg(feature = "jsonconf")]
+pub mod to_jsonconf;
@@ -0,0 +1,149 @@
+use venum::value_type::ValueType;
+
+use crate::{
+    conf::jsonconf::{
+        ColumnRulesConfig, ConditionConfig, ItemTargetConfig, MapValuesConfig, ModifierConfig,
+        RuleConfig,
+    },
+    constraints::{ColumnRules, Rule},
+    data_cell::DataCell,
+    transform::{
+        data_cell_row::{condition::RowCondition, transrich_inplace::MapItemValue},
+        value::modifying::StringModifier,
+    },
+    value_fmt::value_to_string,
+};
+
+// The way back, i.e. from what we built from a config to the config, see `TransrichPass::to_config`.
+
+impl From<&DataCell> for ItemTargetConfig {
+    fn from(dc: &DataCell) -> Self {
+        ItemTargetConfig {
+            idx: dc.get_idx(),
+            header: Some(String::from(dc.get_name())),
+            target_type: dc.get_type_info().clone(),
+        }
+    }
+}
+
+impl From<&ColumnRules> for ColumnRulesConfig {
+    fn from(cr: &ColumnRules) -> Self {
+        // only range and oneOf need the type, and their values have it
+        let dtype = cr
+            .rules
+            .iter()
+            .find_map(|rule| match rule {
+                Rule::Range { min, max } => min.as_ref().or(max.as_ref()),
+                Rule::OneOf(values) => values.first(),
+                _ => None,
+            })
+            .and_then(|val| ValueType::try_from(val).ok());
+        ColumnRulesConfig {
+            idx: cr.idx,
+            dtype,
+            rules: cr.rules.iter().map(RuleConfig::from).collect(),
+        }
+    }
+}
+
+impl From<&MapItemValue> for MapValuesConfig {
+    fn from(miv: &MapItemValue) -> Self {
+        let target = miv.target_idx.map(|idx| ItemTargetConfig {
+            idx,
+            header: miv.target_header.clone(),
+            target_type: miv.value_type.clone(),
+        });
+        MapValuesConfig {
+            idx: miv.idx,
+            key_type: miv.key_type.clone(),
+            // (the target has it)
+            value_type: target.is_none().then(|| miv.value_type.clone()),
+            target,
+            table: Some(
+                miv.table
+                    .iter()
+                    .map(|(k, v)| (k.clone(), value_to_string(v)))
+                    .collect(),
+            ),
+            table_file: None,
+            default: miv.default.as_ref().map(value_to_string),
+            strict: Some(miv.strict),
+        }
+    }
+}
+
+impl From<&StringModifier> for ModifierConfig {
+    fn from(sm: &StringModifier) -> Self {
+        match sm {
+            StringModifier::Trim { side, chars } => ModifierConfig::Trim {
+                side: Some(*side),
+                chars: chars.as_ref().map(|cs| cs.iter().collect()),
+            },
+            StringModifier::Case(to) => ModifierConfig::Case { to: *to },
+            StringModifier::Replace { from, to } => ModifierConfig::Replace {
+                from: from.clone(),
+                to: to.clone(),
+            },
+            StringModifier::ReplacePattern {
+                pattern,
+                replacement,
+            } => ModifierConfig::ReplacePattern {
+                pattern: String::from(pattern.as_str()),
+                replacement: replacement.clone(),
+            },
+            StringModifier::Pad { side, width, fill } => ModifierConfig::Pad {
+                side: Some(*side),
+                width: *width,
+                fill: Some(*fill),
+            },
+            StringModifier::Substring { start, len } => ModifierConfig::Substring {
+                start: *start,
+                len: *len,
+            },
+            StringModifier::Normalize(form) => ModifierConfig::Normalize { form: *form },
+            StringModifier::CollapseWhitespace => ModifierConfig::CollapseWhitespace,
+        }
+    }
+}
+
+impl From<&RowCondition> for ConditionConfig {
+    fn from(rc: &RowCondition) -> Self {
+        let convert_all = |conditions: &[RowCondition]| {
+            conditions
+                .iter()
+                .map(ConditionConfig::from)
+                .collect::<Vec<ConditionConfig>>()
+        };
+        match rc {
+            RowCondition::Equals { idx, value } => ConditionConfig::Equals {
+                idx: *idx,
+                value: value.clone(),
+            },
+            RowCondition::OneOf { idx, values } => ConditionConfig::OneOf {
+                idx: *idx,
+                values: values.clone(),
+            },
+            RowCondition::Matches { idx, pattern } => ConditionConfig::Matches {
+                idx: *idx,
+                pattern: String::from(pattern.as_str()),
+            },
+            RowCondition::IsNone { idx } => ConditionConfig::IsNone { idx: *idx },
+            RowCondition::All(conditions) => ConditionConfig::All {
+                conditions: convert_all(conditions),
+            },
+            RowCondition::Any(conditions) => ConditionConfig::Any {
+                conditions: convert_all(conditions),
+            },
+            RowCondition::Not(condition) => match condition.as_ref() {
+                RowCondition::Equals { idx, value } => ConditionConfig::NotEquals {
+                    idx: *idx,
+                    value: value.clone(),
+                },
+                RowCondition::IsNone { idx } => ConditionConfig::IsSome { idx: *idx },
+                other => ConditionConfig::Not {
+                    condition: Box::new(ConditionConfig::from(other)),
+                },
+            },
+        }
+    }
+}
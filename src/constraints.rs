@@ -0,0 +1,482 @@
+use std::collections::HashMap;
+use std::fmt::{Display, Formatter};
+
+use regex::Regex;
+use venum::value::Value;
+use venum::value_type::ValueType;
+
+use crate::{
+    data_cell_row::DataCellRow,
+    errors::{Result, TransformErrors, VenumTdsError},
+    value_fmt::value_to_string,
+};
+
+/// A rule, a single value has to obey. Except for `NotNull`, rules only apply to values that are
+/// there, i.e. a `None` (or a missing column) never breaks them.
+#[derive(Debug, Clone)]
+pub enum Rule {
+    NotNull,
+    /// Inclusive bounds. For numeric and date/time values, really, but works on all values of the
+    /// same type as the bounds.
+    Range {
+        min: Option<Value>,
+        max: Option<Value>,
+    },
+    /// Inclusive bounds on the number of chars of the (stringified) value.
+    Length {
+        min: Option<usize>,
+        max: Option<usize>,
+    },
+    /// The (stringified) value must match the pattern.
+    Pattern(Regex),
+    OneOf(Vec<Value>),
+}
+
+impl Rule {
+    pub fn pattern(pattern: &str) -> Result<Self> {
+        Regex::new(pattern).map(Rule::Pattern).map_err(|e| {
+            VenumTdsError::Transform(TransformErrors::Generic {
+                msg: format!("invalid pattern {:?}: {}", pattern, e),
+            })
+        })
+    }
+
+    pub(crate) fn is_broken_by(&self, val: &Value) -> bool {
+        if val.is_none() {
+            return matches!(self, Rule::NotNull);
+        }
+        match self {
+            Rule::NotNull => false,
+            Rule::Range { min, max } => {
+                let below = min.as_ref().is_some_and(|m| !same_type_and_le(m, val));
+                let above = max.as_ref().is_some_and(|m| !same_type_and_le(val, m));
+                below || above
+            }
+            Rule::Length { min, max } => {
+                let len = value_to_string(val).chars().count();
+                min.is_some_and(|m| len < m) || max.is_some_and(|m| len > m)
+            }
+            Rule::Pattern(re) => !re.is_match(&value_to_string(val)),
+            Rule::OneOf(values) => !values.contains(val),
+        }
+    }
+}
+
+/// A rule, as given in a config (see `validateItems` and `assertSchema`). Range bounds and allowed
+/// values are strings, typed via the column's type, see `to_rule`. Except for `notNull`, a `None`
+/// never breaks a rule.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "jsonconf",
+    derive(serde::Deserialize, serde::Serialize),
+    serde(tag = "name", rename_all = "camelCase")
+)]
+#[cfg_attr(feature = "jsonschema", derive(schemars::JsonSchema))]
+pub enum RuleConfig {
+    NotNull,
+    /// Inclusive bounds, for numbers and dates/times.
+    Range {
+        /// Typed via the column's type
+        #[cfg_attr(feature = "jsonconf", serde(skip_serializing_if = "Option::is_none"))]
+        min: Option<String>,
+        /// Typed via the column's type
+        #[cfg_attr(feature = "jsonconf", serde(skip_serializing_if = "Option::is_none"))]
+        max: Option<String>,
+    },
+    /// Inclusive bounds of the number of characters of the (stringified) value.
+    Length {
+        #[cfg_attr(feature = "jsonconf", serde(skip_serializing_if = "Option::is_none"))]
+        min: Option<usize>,
+        #[cfg_attr(feature = "jsonconf", serde(skip_serializing_if = "Option::is_none"))]
+        max: Option<usize>,
+    },
+    Pattern {
+        pattern: String,
+    },
+    OneOf {
+        /// Typed via the column's type
+        values: Vec<String>,
+    },
+}
+
+impl RuleConfig {
+    /// The rule for the column with the given idx. Range bounds and allowed values need the type
+    /// of the column.
+    pub fn to_rule(&self, idx: usize, dtype: Option<&ValueType>) -> Result<Rule> {
+        let typed = |name: &str, s: &str| -> Result<Value> {
+            let dtype = dtype.ok_or_else(|| VenumTdsError::Generic {
+                msg: format!("rule {} on idx={} needs a type", name, idx),
+            })?;
+            Ok(Value::from_str_and_type(s, dtype)?)
+        };
+
+        Ok(match self {
+            RuleConfig::NotNull => Rule::NotNull,
+            RuleConfig::Range { min, max } => Rule::Range {
+                min: min.as_deref().map(|m| typed("range", m)).transpose()?,
+                max: max.as_deref().map(|m| typed("range", m)).transpose()?,
+            },
+            RuleConfig::Length { min, max } => Rule::Length {
+                min: *min,
+                max: *max,
+            },
+            RuleConfig::Pattern { pattern } => Rule::pattern(pattern)?,
+            RuleConfig::OneOf { values } => Rule::OneOf(
+                values
+                    .iter()
+                    .map(|v| typed("oneOf", v))
+                    .collect::<Result<Vec<Value>>>()?,
+            ),
+        })
+    }
+}
+
+impl From<&Rule> for RuleConfig {
+    fn from(rule: &Rule) -> Self {
+        match rule {
+            Rule::NotNull => RuleConfig::NotNull,
+            Rule::Range { min, max } => RuleConfig::Range {
+                min: min.as_ref().map(value_to_string),
+                max: max.as_ref().map(value_to_string),
+            },
+            Rule::Length { min, max } => RuleConfig::Length {
+                min: *min,
+                max: *max,
+            },
+            Rule::Pattern(re) => RuleConfig::Pattern {
+                pattern: String::from(re.as_str()),
+            },
+            Rule::OneOf(values) => RuleConfig::OneOf {
+                values: values.iter().map(value_to_string).collect(),
+            },
+        }
+    }
+}
+
+/// `a <= b`, but only if they are of the same type. (Comparing different `Value` variants makes no
+/// sense.)
+fn same_type_and_le(a: &Value, b: &Value) -> bool {
+    ValueType::try_from(a).ok() == ValueType::try_from(b).ok() && a <= b
+}
+
+fn fmt_bound(val: &Option<Value>) -> String {
+    val.as_ref()
+        .map(value_to_string)
+        .unwrap_or_else(|| String::from("_"))
+}
+
+impl Display for Rule {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Rule::NotNull => write!(f, "notNull"),
+            Rule::Range { min, max } => {
+                write!(f, "range [{}, {}]", fmt_bound(min), fmt_bound(max))
+            }
+            Rule::Length { min, max } => write!(
+                f,
+                "length [{}, {}]",
+                min.map_or_else(|| String::from("_"), |m| m.to_string()),
+                max.map_or_else(|| String::from("_"), |m| m.to_string())
+            ),
+            Rule::Pattern(re) => write!(f, "pattern {:?}", re.as_str()),
+            Rule::OneOf(values) => write!(
+                f,
+                "oneOf [{}]",
+                values
+                    .iter()
+                    .map(value_to_string)
+                    .collect::<Vec<String>>()
+                    .join(", ")
+            ),
+        }
+    }
+}
+
+/// The rules for a single column.
+#[derive(Debug, Clone)]
+pub struct ColumnRules {
+    pub idx: usize,
+    pub rules: Vec<Rule>,
+}
+
+/// A rule that was broken by a row.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RuleViolation {
+    /// The (1-based) number of the row, counted by the validator.
+    pub row: usize,
+    /// The column(s) the rule is about.
+    pub idxs: Vec<usize>,
+    pub rule: String,
+    /// The offending value(s), stringified.
+    pub value: String,
+    pub details: Option<String>,
+}
+
+impl Display for RuleViolation {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "row {} {:?} {}: {:?}",
+            self.row, self.idxs, self.rule, self.value
+        )?;
+        if let Some(d) = &self.details {
+            write!(f, " ({})", d)?;
+        }
+        Ok(())
+    }
+}
+
+/// Which rows broke which rules.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct ValidationReport {
+    pub rows_checked: usize,
+    pub violations: Vec<RuleViolation>,
+}
+
+impl ValidationReport {
+    pub fn is_valid(&self) -> bool {
+        self.violations.is_empty()
+    }
+
+    /// The numbers of the rows that broke at least one rule, ascending.
+    pub fn failed_rows(&self) -> Vec<usize> {
+        let mut rows: Vec<usize> = self.violations.iter().map(|v| v.row).collect();
+        rows.dedup(); // violations are recorded row by row
+        rows
+    }
+}
+
+impl Display for ValidationReport {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        writeln!(
+            f,
+            "rows checked: {}, rows failed: {}, violations: {}",
+            self.rows_checked,
+            self.failed_rows().len(),
+            self.violations.len()
+        )?;
+        for v in &self.violations {
+            writeln!(f, "{}", v)?;
+        }
+        Ok(())
+    }
+}
+
+/// Checks rows against per column rules and, across rows, the uniqueness of key columns. Keeps
+/// track of the results in a `ValidationReport`. Since it has to remember the keys it has seen, it
+/// is stateful.
+///
+/// Like in SQL, nulls don't take part in uniqueness: a key where any column is missing or
+/// `Value::None` is never a duplicate (use `notNull` to forbid those).
+#[derive(Debug, Clone, Default)]
+pub struct RowValidator {
+    columns: Vec<ColumnRules>,
+    unique: Vec<Vec<usize>>,
+    seen_keys: Vec<HashMap<Vec<Option<String>>, usize>>,
+    report: ValidationReport,
+}
+
+impl RowValidator {
+    /// `unique` is a list of keys, where every key is a list of column idxs, the combined values of
+    /// which must be unique across all rows.
+    pub fn new(columns: Vec<ColumnRules>, unique: Vec<Vec<usize>>) -> Self {
+        Self {
+            columns,
+            seen_keys: vec![HashMap::new(); unique.len()],
+            unique,
+            report: ValidationReport::default(),
+        }
+    }
+
+    pub fn columns(&self) -> &[ColumnRules] {
+        &self.columns
+    }
+
+    pub fn unique(&self) -> &[Vec<usize>] {
+        &self.unique
+    }
+
+    /// Checks the next row. Returns what this row broke, which is also recorded in the report.
+    pub fn validate(&mut self, row: &DataCellRow) -> &[RuleViolation] {
+        self.report.rows_checked += 1;
+        let row_num = self.report.rows_checked;
+        let num_before = self.report.violations.len();
+
+        for col in &self.columns {
+            let val = row
+                .get_by_idx(col.idx)
+                .map(|dc| dc.get_data())
+                .unwrap_or(&Value::None);
+            for rule in col.rules.iter().filter(|r| r.is_broken_by(val)) {
+                self.report.violations.push(RuleViolation {
+                    row: row_num,
+                    idxs: vec![col.idx],
+                    rule: rule.to_string(),
+                    value: value_to_string(val),
+                    details: None,
+                });
+            }
+        }
+
+        for (key_idxs, seen) in self.unique.iter().zip(self.seen_keys.iter_mut()) {
+            // We can't hash floats, so we go with the string representation, including the type
+            let key: Vec<Option<String>> = key_idxs
+                .iter()
+                .map(|idx| {
+                    row.get_by_idx(*idx)
+                        .filter(|dc| !dc.get_data().is_none())
+                        .map(|dc| {
+                            format!("{}:{}", dc.get_type_info(), value_to_string(dc.get_data()))
+                        })
+                })
+                .collect();
+            if key.iter().any(Option::is_none) {
+                continue;
+            }
+
+            if let Some(first_row) = seen.get(&key) {
+                self.report.violations.push(RuleViolation {
+                    row: row_num,
+                    idxs: key_idxs.clone(),
+                    rule: String::from("unique"),
+                    value: key_idxs
+                        .iter()
+                        .map(|idx| {
+                            row.get_by_idx(*idx)
+                                .map(|dc| value_to_string(dc.get_data()))
+                                .unwrap_or_default()
+                        })
+                        .collect::<Vec<String>>()
+                        .join(", "),
+                    details: Some(format!("same as row {}", first_row)),
+                });
+            } else {
+                seen.insert(key, row_num);
+            }
+        }
+
+        &self.report.violations[num_before..]
+    }
+
+    pub fn report(&self) -> &ValidationReport {
+        &self.report
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data_cell::DataCell;
+
+    fn row(ccy: Option<&str>, amount: i32) -> DataCellRow {
+        let mut row = DataCellRow::new();
+        row.push(
+            DataCell::new_with_type_info(
+                ValueType::String,
+                String::from("currency"),
+                0,
+                ccy.map(|c| Value::String(String::from(c)))
+                    .unwrap_or(Value::None),
+            )
+            .unwrap(),
+        );
+        row.push(DataCell::new(String::from("amount"), 1, Value::Int32(amount)).unwrap());
+        row
+    }
+
+    #[test]
+    fn rules() {
+        let mut validator = RowValidator::new(
+            vec![
+                ColumnRules {
+                    idx: 0,
+                    rules: vec![
+                        Rule::NotNull,
+                        Rule::Length {
+                            min: Some(3),
+                            max: Some(3),
+                        },
+                        Rule::pattern("^[A-Z]+$").unwrap(),
+                        Rule::OneOf(vec![
+                            Value::String(String::from("CHF")),
+                            Value::String(String::from("EUR")),
+                        ]),
+                    ],
+                },
+                ColumnRules {
+                    idx: 1,
+                    rules: vec![Rule::Range {
+                        min: Some(Value::Int32(0)),
+                        max: Some(Value::Int32(100)),
+                    }],
+                },
+            ],
+            Vec::new(),
+        );
+
+        assert!(validator.validate(&row(Some("CHF"), 100)).is_empty());
+        assert_eq!(
+            vec!["notNull", "range [0, 100]"],
+            validator
+                .validate(&row(None, -1))
+                .iter()
+                .map(|v| v.rule.as_str())
+                .collect::<Vec<&str>>()
+        );
+        assert_eq!(
+            vec!["length [3, 3]", "pattern \"^[A-Z]+$\"", "oneOf [CHF, EUR]"],
+            validator
+                .validate(&row(Some("usd1"), 1))
+                .iter()
+                .map(|v| v.rule.as_str())
+                .collect::<Vec<&str>>()
+        );
+        assert_eq!(3, validator.report().rows_checked);
+        assert_eq!(vec![2, 3], validator.report().failed_rows());
+    }
+
+    #[test]
+    fn range_of_other_type_is_broken() {
+        let rule = Rule::Range {
+            min: Some(Value::Int64(0)),
+            max: None,
+        };
+        assert!(rule.is_broken_by(&Value::Int32(5)));
+        assert!(!rule.is_broken_by(&Value::Int64(5)));
+    }
+
+    #[test]
+    fn unique_keys() {
+        let mut validator = RowValidator::new(Vec::new(), vec![vec![0, 1]]);
+        assert!(validator.validate(&row(Some("CHF"), 1)).is_empty());
+        assert!(validator.validate(&row(Some("CHF"), 2)).is_empty());
+        assert_eq!(
+            vec![RuleViolation {
+                row: 3,
+                idxs: vec![0, 1],
+                rule: String::from("unique"),
+                value: String::from("CHF, 1"),
+                details: Some(String::from("same as row 1")),
+            }],
+            validator.validate(&row(Some("CHF"), 1))
+        );
+    }
+
+    #[test]
+    fn unique_keys_ignore_nulls() {
+        let mut validator = RowValidator::new(Vec::new(), vec![vec![0, 1], vec![2]]);
+        // column 2 is missing in every row, and currency is null twice, neither is a duplicate
+        assert!(validator.validate(&row(None, 1)).is_empty());
+        assert!(validator.validate(&row(None, 1)).is_empty());
+        assert!(validator.validate(&row(Some("CHF"), 1)).is_empty());
+        assert!(validator.report().violations.is_empty());
+    }
+
+    #[test]
+    fn unique_keys_null_is_not_empty_string() {
+        let mut validator = RowValidator::new(Vec::new(), vec![vec![0]]);
+        assert!(validator.validate(&row(Some(""), 1)).is_empty());
+        assert!(validator.validate(&row(None, 2)).is_empty());
+        assert_eq!(1, validator.validate(&row(Some(""), 3)).len());
+    }
+}
@@ -1,16 +1,227 @@
+use std::cmp::Ordering;
+use std::collections::hash_map::Entry;
+use std::collections::HashMap;
+use std::fmt::{Debug, Display};
+
+use venum::value::Value;
+use venum::value_type::ValueType;
+
 use crate::errors::{DataAccessErrors, Result, VenumTdsError};
+use crate::render::RowRenderer;
+use crate::schema::{Schema, SchemaViolation};
 
 use super::data_cell::DataCell;
 
-#[derive(Debug, Clone, PartialEq, PartialOrd)]
-pub struct DataCellRow(pub Vec<DataCell>); // TODO: we actually don't want this to be public, but we still have code in patti_csv that relies on it.
+/// A cell, that has been handed out mutably, together with its idx and name at that time. Before
+/// the index is used again, we check whether they were changed.
+#[derive(Clone)]
+struct Touched {
+    pos: usize,
+    idx: usize,
+    name: String,
+}
+
+/// Maps idx and name to the position of the (first) cell with that idx/name in the row. The index
+/// is kept up to date eagerly, by every change through the row, so lookups through `&self` can use
+/// it as well. Only cells that were handed out mutably can change behind its back, see `stale` and
+/// `touched`.
+#[derive(Clone, Default)]
+struct RowIndex {
+    /// Set, when the cells were handed out mutably as a whole (`iter_mut`). Until the index is
+    /// rebuilt (by the next change through the row), lookups fall back to scanning the cells.
+    stale: bool,
+    touched: Option<Touched>,
+    /// Whether there is any idx (name) that is used by more than one cell. As long as there isn't,
+    /// changes of idx (name) can be applied to the index in O(1), otherwise we rebuild it.
+    dup_idx: bool,
+    dup_name: bool,
+    by_idx: HashMap<usize, usize>,
+    by_name: HashMap<String, usize>,
+}
+
+impl RowIndex {
+    fn insert_idx(&mut self, idx: usize, pos: usize) {
+        match self.by_idx.entry(idx) {
+            Entry::Vacant(e) => {
+                e.insert(pos);
+            }
+            Entry::Occupied(mut e) => {
+                self.dup_idx = true;
+                if pos < *e.get() {
+                    e.insert(pos);
+                }
+            }
+        }
+    }
+
+    fn insert_name(&mut self, name: &str, pos: usize) {
+        match self.by_name.get_mut(name) {
+            None => {
+                self.by_name.insert(String::from(name), pos);
+            }
+            Some(p) => {
+                self.dup_name = true;
+                if pos < *p {
+                    *p = pos;
+                }
+            }
+        }
+    }
+
+    fn rebuild(&mut self, cells: &[DataCell]) {
+        self.by_idx.clear();
+        self.by_name.clear();
+        self.dup_idx = false;
+        self.dup_name = false;
+        for (pos, dc) in cells.iter().enumerate() {
+            self.insert_idx(dc.idx, pos);
+            self.insert_name(&dc.name, pos);
+        }
+        self.stale = false;
+        self.touched = None;
+    }
+
+    /// Whether the index can be used as is, for the given cells.
+    fn is_usable(&self, cells: &[DataCell]) -> bool {
+        !self.stale
+            && !matches!(&self.touched, Some(t) if cells[t.pos].idx != t.idx || cells[t.pos].name != t.name)
+    }
+
+    /// Brings the index up to date with the given cells, building it if need be.
+    fn sync(&mut self, cells: &[DataCell]) {
+        if self.stale {
+            self.rebuild(cells);
+            return;
+        }
+        if let Some(t) = self.touched.take() {
+            let dc = &cells[t.pos];
+            if dc.idx == t.idx && dc.name == t.name {
+                return;
+            }
+            if self.dup_idx || self.dup_name {
+                self.rebuild(cells);
+                return;
+            }
+            if dc.idx != t.idx {
+                self.by_idx.remove(&t.idx);
+                self.insert_idx(dc.idx, t.pos);
+            }
+            if dc.name != t.name {
+                self.by_name.remove(&t.name);
+                self.insert_name(&dc.name, t.pos);
+            }
+        }
+    }
+
+    /// Fixes up the index, after the cell at `pos` was removed. (The cells after it moved up.)
+    fn remove(&mut self, cells: &[DataCell], pos: usize, removed: &DataCell) {
+        if self.dup_idx || self.dup_name {
+            self.rebuild(cells);
+            return;
+        }
+        self.by_idx.remove(&removed.idx);
+        self.by_name.remove(&removed.name);
+        for p in self.by_idx.values_mut().chain(self.by_name.values_mut()) {
+            if *p > pos {
+                *p -= 1;
+            }
+        }
+    }
+
+    fn touch(&mut self, pos: usize, dc: &DataCell) {
+        self.touched = Some(Touched {
+            pos,
+            idx: dc.idx,
+            name: dc.name.clone(),
+        });
+    }
+}
+
+/// A row of `DataCell`s. Lookups by idx and name go through an index, which is kept consistent when
+/// cells are pushed, deleted, reindexed or renamed (also through `get_by_idx_mut` and friends). The
+/// physical order of the cells is stable, i.e. only ever changed by sorting explicitly.
+///
+/// If there are several cells with the same idx (name), lookups return the first one. To prevent
+/// that from happening, a row can be put into strict mode (see `set_strict`).
+///
+/// A row is built from a `Vec<DataCell>` through `From`, and `into_cells`/`as_slice` hand the cells
+/// back.
+#[derive(Clone)]
+pub struct DataCellRow {
+    cells: Vec<DataCell>,
+    index: RowIndex,
+    strict: bool,
+}
+
+/// What to do, when adding a cell whose idx or name is already in use.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "jsonconf",
+    derive(serde::Deserialize, serde::Serialize),
+    serde(rename_all = "camelCase")
+)]
+#[cfg_attr(feature = "jsonschema", derive(schemars::JsonSchema))]
+pub enum OnConflict {
+    /// Fail with `DataAccessErrors::DuplicateIdx` or `DuplicateName`.
+    Error,
+    /// Replace the conflicting cell(s). The new cell takes the position of the (first) old one.
+    Replace,
+    /// Leave the row as it is.
+    Skip,
+}
+
+/// Refers to a cell, either by idx or by name. (In a config, a number is an idx and a string is a
+/// name.)
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(
+    feature = "jsonconf",
+    derive(serde::Deserialize, serde::Serialize),
+    serde(untagged)
+)]
+#[cfg_attr(feature = "jsonschema", derive(schemars::JsonSchema))]
+pub enum ColumnRef {
+    Idx(usize),
+    Name(String),
+}
+
+impl ColumnRef {
+    /// The referred to cell of the row. Fails with `DataAccessErrors::IllegalIdxAccess` or
+    /// `IllegalNameAccess`, if there is none.
+    pub fn get<'a>(&self, row: &'a DataCellRow) -> Result<&'a DataCell> {
+        match self {
+            ColumnRef::Idx(idx) => row
+                .get_by_idx(*idx)
+                .ok_or_else(|| DataCellRow::idx_err(*idx)),
+            ColumnRef::Name(name) => row
+                .get_by_name(name)
+                .ok_or_else(|| DataCellRow::name_err(name)),
+        }
+    }
+}
+
+impl Display for ColumnRef {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ColumnRef::Idx(idx) => write!(f, "idx {}", idx),
+            ColumnRef::Name(name) => write!(f, "{:?}", name),
+        }
+    }
+}
 
 impl DataCellRow {
     pub fn new() -> Self {
-        Self(Vec::new())
+        Self {
+            cells: Vec::new(),
+            index: RowIndex::default(),
+            strict: false,
+        }
     }
     pub fn with_capacity(capacity: usize) -> Self {
-        Self(Vec::with_capacity(capacity))
+        Self {
+            cells: Vec::with_capacity(capacity),
+            index: RowIndex::default(),
+            strict: false,
+        }
     }
 }
 
@@ -20,45 +231,348 @@ impl Default for DataCellRow {
     }
 }
 
+impl From<Vec<DataCell>> for DataCellRow {
+    fn from(cells: Vec<DataCell>) -> Self {
+        let mut index = RowIndex::default();
+        index.rebuild(&cells);
+        Self {
+            cells,
+            index,
+            strict: false,
+        }
+    }
+}
+
+impl From<DataCellRow> for Vec<DataCell> {
+    fn from(row: DataCellRow) -> Self {
+        row.into_cells()
+    }
+}
+
+// The index is an implementation detail, rows are equal if their cells are.
+impl PartialEq for DataCellRow {
+    fn eq(&self, other: &Self) -> bool {
+        self.cells == other.cells
+    }
+}
+
+impl PartialOrd for DataCellRow {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        self.cells.partial_cmp(&other.cells)
+    }
+}
+
+impl Debug for DataCellRow {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_tuple("DataCellRow").field(&self.cells).finish()
+    }
+}
+
 impl DataCellRow {
+    /// In strict mode, `push_checked` (used by all transrichers that add cells) fails on a
+    /// duplicate idx or name, unless told otherwise. Also `insert_at`, `rename` and
+    /// `replace_by_idx` refuse to create duplicates. (`push` never checks.)
+    pub fn set_strict(&mut self, strict: bool) {
+        self.strict = strict;
+    }
+
+    pub fn is_strict(&self) -> bool {
+        self.strict
+    }
+
+    fn pos_by_idx(&self, idx: usize) -> Option<usize> {
+        if self.index.is_usable(&self.cells) {
+            self.index.by_idx.get(&idx).copied()
+        } else {
+            self.cells
+                .iter()
+                .position(|vec_elem| vec_elem.get_idx() == idx)
+        }
+    }
+
+    fn pos_by_name(&self, name: &str) -> Option<usize> {
+        if self.index.is_usable(&self.cells) {
+            self.index.by_name.get(name).copied()
+        } else {
+            self.cells
+                .iter()
+                .position(|vec_elem| vec_elem.get_name() == name)
+        }
+    }
+
+    /// Hands out the cell at `pos` mutably, remembering it, so that the index can be fixed up, in
+    /// case its idx or name are changed.
+    fn touch(&mut self, pos: usize) -> &mut DataCell {
+        self.index.touch(pos, &self.cells[pos]);
+        &mut self.cells[pos]
+    }
+
     pub fn get_by_idx(&self, idx: usize) -> Option<&DataCell> {
-        self.0.iter().find(|&vec_elem| vec_elem.get_idx() == idx)
+        self.pos_by_idx(idx).map(|pos| &self.cells[pos])
     }
     pub fn get_by_idx_mut(&mut self, idx: usize) -> Option<&mut DataCell> {
-        self.0.iter_mut().find(|vec_elem| vec_elem.get_idx() == idx)
+        self.index.sync(&self.cells);
+        let pos = self.pos_by_idx(idx)?;
+        Some(self.touch(pos))
     }
 
     pub fn get_by_name(&self, name: &str) -> Option<&DataCell> {
-        self.0.iter().find(|&vec_elem| vec_elem.get_name() == name)
+        self.pos_by_name(name).map(|pos| &self.cells[pos])
     }
     pub fn get_by_name_mut(&mut self, name: &str) -> Option<&mut DataCell> {
-        self.0
-            .iter_mut()
-            .find(|vec_elem| vec_elem.get_name() == name)
+        self.index.sync(&self.cells);
+        let pos = self.pos_by_name(name)?;
+        Some(self.touch(pos))
+    }
+
+    fn idx_err(idx: usize) -> VenumTdsError {
+        VenumTdsError::DataAccess(DataAccessErrors::IllegalIdxAccess { idx })
+    }
+
+    fn name_err(name: &str) -> VenumTdsError {
+        VenumTdsError::DataAccess(DataAccessErrors::IllegalNameAccess {
+            name: String::from(name),
+        })
+    }
+
+    /// Fails, if the given idx or name is used by any cell other than the one at `except`.
+    fn check_conflict(&self, idx: usize, name: &str, except: Option<usize>) -> Result<()> {
+        if self.pos_by_idx(idx).is_some_and(|pos| Some(pos) != except) {
+            return Err(VenumTdsError::DataAccess(DataAccessErrors::DuplicateIdx {
+                idx,
+            }));
+        }
+        if self
+            .pos_by_name(name)
+            .is_some_and(|pos| Some(pos) != except)
+        {
+            return Err(VenumTdsError::DataAccess(DataAccessErrors::DuplicateName {
+                name: String::from(name),
+            }));
+        }
+        Ok(())
+    }
+
+    fn remove_at(&mut self, pos: usize) -> DataCell {
+        let removed = self.cells.remove(pos);
+        self.index.remove(&self.cells, pos, &removed);
+        removed
     }
 
+    /// Deletes the (first) cell with the given idx. The order of the remaining cells is kept.
     pub fn del_by_idx(&mut self, idx: usize) -> Result<DataCell> {
-        let idx = self
-            .0
-            .iter()
-            .position(|vec_elem| vec_elem.get_idx() == idx)
-            .ok_or(VenumTdsError::DataAccess(
-                DataAccessErrors::IllegalIdxAccess { idx },
-            ))?;
-        Ok(self.0.swap_remove(idx))
+        self.index.sync(&self.cells);
+        let pos = self.pos_by_idx(idx).ok_or_else(|| Self::idx_err(idx))?;
+        Ok(self.remove_at(pos))
+    }
+
+    /// Deletes the (first) cell with the given name. The order of the remaining cells is kept.
+    pub fn del_by_name(&mut self, name: &str) -> Result<DataCell> {
+        self.index.sync(&self.cells);
+        let pos = self.pos_by_name(name).ok_or_else(|| Self::name_err(name))?;
+        Ok(self.remove_at(pos))
+    }
+
+    /// Removes the cells with the given idxs and returns them as a new row, in the order of `idxs`.
+    /// If any of the idxs is not there (or given twice), nothing is removed.
+    pub fn take_by_idx(&mut self, idxs: &[usize]) -> Result<DataCellRow> {
+        self.index.sync(&self.cells);
+        let mut positions: Vec<usize> = Vec::with_capacity(idxs.len());
+        for idx in idxs {
+            match self.pos_by_idx(*idx) {
+                Some(pos) if !positions.contains(&pos) => positions.push(pos),
+                _ => return Err(Self::idx_err(*idx)),
+            }
+        }
+
+        // remove back to front, so the positions stay valid
+        let mut by_pos: Vec<(usize, usize)> = positions.into_iter().zip(0..).collect();
+        by_pos.sort_unstable_by(|a, b| b.0.cmp(&a.0));
+        let mut taken: Vec<Option<DataCell>> = vec![None; idxs.len()];
+        for (pos, target) in by_pos {
+            taken[target] = Some(self.cells.remove(pos));
+        }
+        self.index.rebuild(&self.cells);
+
+        Ok(DataCellRow::from(
+            taken.into_iter().flatten().collect::<Vec<DataCell>>(),
+        ))
     }
 
     pub fn push(&mut self, elem: DataCell) {
-        self.0.push(elem);
+        self.index.sync(&self.cells);
+        let pos = self.cells.len();
+        self.index.insert_idx(elem.idx, pos);
+        self.index.insert_name(&elem.name, pos);
+        self.cells.push(elem);
+    }
+
+    /// Fails with `DataAccessErrors::DuplicateIdx` or `DuplicateName`, instead of pushing a
+    /// duplicate.
+    pub fn try_push(&mut self, elem: DataCell) -> Result<()> {
+        self.push_with(elem, OnConflict::Error)
+    }
+
+    /// Pushes the cell, unless its idx or name is already in use. Then, `on_conflict` decides.
+    pub fn push_with(&mut self, elem: DataCell, on_conflict: OnConflict) -> Result<()> {
+        self.index.sync(&self.cells);
+        let pos_idx = self.pos_by_idx(elem.idx);
+        let pos_name = self.pos_by_name(&elem.name);
+
+        match (on_conflict, pos_idx, pos_name) {
+            (_, None, None) => self.push(elem),
+            (OnConflict::Error, _, _) => self.check_conflict(elem.idx, &elem.name, None)?,
+            (OnConflict::Skip, _, _) => {}
+            (OnConflict::Replace, Some(pos), None) | (OnConflict::Replace, None, Some(pos)) => {
+                *self.touch(pos) = elem;
+            }
+            (OnConflict::Replace, Some(pos_idx), Some(pos_name)) => {
+                *self.touch(pos_idx.min(pos_name)) = elem;
+                if pos_idx != pos_name {
+                    self.index.sync(&self.cells);
+                    self.remove_at(pos_idx.max(pos_name));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Pushes the cell, using the given policy if its idx or name is already in use. Without a
+    /// policy, strict rows fail and all others just get the duplicate (like `push`).
+    pub fn push_checked(&mut self, elem: DataCell, on_conflict: Option<OnConflict>) -> Result<()> {
+        match (on_conflict, self.strict) {
+            (Some(oc), _) => self.push_with(elem, oc),
+            (None, true) => self.push_with(elem, OnConflict::Error),
+            (None, false) => {
+                self.push(elem);
+                Ok(())
+            }
+        }
+    }
+
+    /// Inserts the cell at the given position (not idx!), shifting all cells after it.
+    pub fn insert_at(&mut self, pos: usize, elem: DataCell) -> Result<()> {
+        if pos > self.cells.len() {
+            return Err(VenumTdsError::DataAccess(
+                DataAccessErrors::IllegalPosAccess { pos },
+            ));
+        }
+        if self.strict {
+            self.index.sync(&self.cells);
+            self.check_conflict(elem.idx, &elem.name, None)?;
+        }
+        self.cells.insert(pos, elem);
+        self.index.rebuild(&self.cells);
+        Ok(())
+    }
+
+    /// Replaces the (first) cell with the given idx and returns the old one. The new cell keeps
+    /// the position, but not necessarily the idx, of the old one.
+    pub fn replace_by_idx(&mut self, idx: usize, elem: DataCell) -> Result<DataCell> {
+        self.index.sync(&self.cells);
+        let pos = self.pos_by_idx(idx).ok_or_else(|| Self::idx_err(idx))?;
+        if self.strict {
+            self.check_conflict(elem.idx, &elem.name, Some(pos))?;
+        }
+        Ok(std::mem::replace(self.touch(pos), elem))
+    }
+
+    /// Changes the name (header) of the (first) cell with the given idx.
+    pub fn rename(&mut self, idx: usize, name: String) -> Result<()> {
+        self.index.sync(&self.cells);
+        let pos = self.pos_by_idx(idx).ok_or_else(|| Self::idx_err(idx))?;
+        if self.strict {
+            self.check_conflict(idx, &name, Some(pos))?;
+        }
+        self.touch(pos).name = name;
+        Ok(())
+    }
+
+    /// Keeps only the cells for which `f` returns `true`. The order of the cells is kept.
+    pub fn retain<F>(&mut self, f: F)
+    where
+        F: FnMut(&DataCell) -> bool,
+    {
+        self.cells.retain(f);
+        self.index.rebuild(&self.cells);
+    }
+
+    /// Physically sorts the cells by idx, so that iteration order matches column order. (Stable.)
+    pub fn sort_by_idx(&mut self) {
+        self.cells.sort_by_key(|vec_elem| vec_elem.get_idx());
+        self.index.rebuild(&self.cells);
+    }
+
+    pub fn contains_idx(&self, idx: usize) -> bool {
+        self.pos_by_idx(idx).is_some()
+    }
+
+    pub fn max_idx(&self) -> Option<usize> {
+        self.cells.iter().map(|vec_elem| vec_elem.get_idx()).max()
     }
 
     pub fn len(&self) -> usize {
-        self.0.len()
+        self.cells.len()
     }
 
     pub fn is_empty(&self) -> bool {
         self.len() == 0
     }
+
+    /// The cells in their physical order.
+    pub fn as_slice(&self) -> &[DataCell] {
+        &self.cells
+    }
+
+    /// The cells in their physical order, see `as_slice`.
+    pub fn into_cells(self) -> Vec<DataCell> {
+        self.cells
+    }
+
+    /// Iterates the cells in their physical order.
+    pub fn iter(&self) -> std::slice::Iter<'_, DataCell> {
+        self.into_iter()
+    }
+
+    pub fn iter_mut(&mut self) -> std::slice::IterMut<'_, DataCell> {
+        self.into_iter()
+    }
+
+    /// Iterates the cells in column order, i.e. ordered by idx, without changing the row. (Cells
+    /// with the same idx keep their physical order.)
+    pub fn iter_sorted(&self) -> std::vec::IntoIter<&DataCell> {
+        let mut cells: Vec<&DataCell> = self.cells.iter().collect();
+        cells.sort_by_key(|vec_elem| vec_elem.get_idx());
+        cells.into_iter()
+    }
+
+    /// The headers (names) of the cells, in column order.
+    pub fn headers(&self) -> Vec<&str> {
+        self.iter_sorted().map(|dc| dc.get_name()).collect()
+    }
+
+    /// The types of the cells, in column order.
+    pub fn types(&self) -> Vec<&ValueType> {
+        self.iter_sorted().map(|dc| dc.get_type_info()).collect()
+    }
+
+    /// Checks the row against the schema, see `Schema::validate`.
+    pub fn validate(&self, schema: &Schema) -> Vec<SchemaViolation> {
+        schema.validate(self)
+    }
+
+    /// The values of the cells, in column order.
+    pub fn into_values(mut self) -> Vec<Value> {
+        self.sort_by_idx();
+        self.cells.into_iter().map(|dc| dc.data).collect()
+    }
+}
+
+/// Renders the row as a (text) table, see `RowRenderer` for more options.
+impl Display for DataCellRow {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", RowRenderer::default().render_row(self))
+    }
 }
 
 impl IntoIterator for DataCellRow {
@@ -66,7 +580,7 @@ impl IntoIterator for DataCellRow {
     type IntoIter = std::vec::IntoIter<Self::Item>;
 
     fn into_iter(self) -> Self::IntoIter {
-        self.0.into_iter()
+        self.cells.into_iter()
     }
 }
 
@@ -75,7 +589,7 @@ impl<'a> IntoIterator for &'a DataCellRow {
     type IntoIter = std::slice::Iter<'a, DataCell>;
 
     fn into_iter(self) -> Self::IntoIter {
-        self.0.iter()
+        self.cells.iter()
     }
 }
 
@@ -84,15 +598,22 @@ impl<'a> IntoIterator for &'a mut DataCellRow {
     type IntoIter = std::slice::IterMut<'a, DataCell>;
 
     fn into_iter(self) -> Self::IntoIter {
-        self.0.iter_mut()
+        // we can't tell what is changed through this, so the index has to be rebuilt afterwards
+        self.index.stale = true;
+        self.cells.iter_mut()
     }
 }
 
 #[cfg(test)]
 mod tests {
     use venum::value::Value;
+    use venum::value_type::ValueType;
 
-    use crate::{data_cell::DataCell, data_cell_row::DataCellRow};
+    use crate::{
+        data_cell::DataCell,
+        data_cell_row::{ColumnRef, DataCellRow, OnConflict},
+        errors::{DataAccessErrors, VenumTdsError},
+    };
 
     #[test]
     pub fn index_access() {
@@ -100,7 +621,7 @@ mod tests {
 
         let vc1 =
             DataCell::new(String::from("foo"), 123, Value::String(String::from("meh"))).unwrap();
-        c.0.push(vc1);
+        c.push(vc1);
 
         let res = c.get_by_idx(123).unwrap();
         assert_eq!(123, res.idx);
@@ -112,9 +633,255 @@ mod tests {
 
         let vc1 =
             DataCell::new(String::from("foo"), 123, Value::String(String::from("meh"))).unwrap();
-        c.0.push(vc1);
+        c.push(vc1);
 
         let res = c.get_by_name("foo").unwrap();
         assert_eq!("foo", res.name);
     }
+
+    fn cell(name: &str, idx: usize) -> DataCell {
+        DataCell::new(String::from(name), idx, Value::Int32(idx as i32)).unwrap()
+    }
+
+    fn names(c: &DataCellRow) -> Vec<&str> {
+        c.into_iter().map(|dc| dc.get_name()).collect()
+    }
+
+    #[test]
+    pub fn index_kept_consistent() {
+        let mut c = DataCellRow::new();
+        c.push(cell("a", 0));
+        c.push(cell("b", 1));
+        c.push(cell("c", 2));
+
+        // rename and reindex through the mutable accessors
+        c.get_by_idx_mut(1).unwrap().name = String::from("x");
+        c.get_by_name_mut("c").unwrap().idx = 5;
+        assert!(c.get_by_name("b").is_none());
+        assert_eq!(1, c.get_by_name("x").unwrap().get_idx());
+        assert!(c.get_by_idx(2).is_none());
+        assert_eq!("c", c.get_by_idx(5).unwrap().get_name());
+
+        // deleting keeps the order
+        c.del_by_idx(0).unwrap();
+        assert_eq!(vec!["x", "c"], names(&c));
+        assert!(c.get_by_name("a").is_none());
+        assert_eq!("c", c.get_by_idx(5).unwrap().get_name());
+
+        c.push(cell("d", 0));
+        assert_eq!(vec!["x", "c", "d"], names(&c));
+        assert_eq!("d", c.get_by_idx_mut(0).unwrap().get_name());
+
+        for dc in &mut c {
+            dc.idx += 10;
+        }
+        assert_eq!("x", c.get_by_idx(11).unwrap().get_name());
+        assert_eq!("d", c.get_by_idx_mut(10).unwrap().get_name());
+    }
+
+    #[test]
+    pub fn index_with_duplicates() {
+        let mut c = DataCellRow::new();
+        c.push(cell("a", 0));
+        c.push(cell("b", 0));
+        c.push(cell("a", 1));
+
+        assert_eq!("a", c.get_by_idx_mut(0).unwrap().get_name());
+        assert_eq!(0, c.get_by_name("a").unwrap().get_idx());
+
+        c.del_by_idx(0).unwrap();
+        assert_eq!("b", c.get_by_idx(0).unwrap().get_name());
+        assert_eq!(1, c.get_by_name("a").unwrap().get_idx());
+
+        c.get_by_idx_mut(1).unwrap().idx = 0;
+        assert_eq!("b", c.get_by_idx(0).unwrap().get_name());
+        c.del_by_idx(0).unwrap();
+        assert_eq!("a", c.get_by_idx(0).unwrap().get_name());
+    }
+
+    #[test]
+    pub fn equality_ignores_index() {
+        let mut c1 = DataCellRow::new();
+        c1.push(cell("a", 0));
+        c1.get_by_idx_mut(0).unwrap();
+
+        let c2 = DataCellRow::from(vec![cell("a", 0)]);
+        assert_eq!(c1, c2);
+        assert_eq!(format!("{:?}", c1), format!("{:?}", c2));
+    }
+
+    #[test]
+    pub fn index_built_eagerly() {
+        let mut c = DataCellRow::from(vec![cell("a", 0), cell("b", 1)]);
+        assert!(c.index.is_usable(&c.cells));
+        assert_eq!(Some(&1), c.index.by_idx.get(&1));
+
+        c.push(cell("c", 2));
+        c.sort_by_idx();
+        c.retain(|dc| dc.get_idx() != 0);
+        assert!(c.index.is_usable(&c.cells));
+        assert_eq!(Some(&1), c.index.by_name.get("c"));
+
+        // only handing out the cells mutably leaves the index behind, until the next change
+        c.iter_mut().for_each(|dc| dc.idx += 10);
+        assert!(!c.index.is_usable(&c.cells));
+        assert_eq!("b", c.get_by_idx(11).unwrap().get_name());
+        c.push(cell("d", 3));
+        assert!(c.index.is_usable(&c.cells));
+        assert_eq!("c", c.get_by_idx(12).unwrap().get_name());
+    }
+
+    #[test]
+    pub fn vec_conversion() {
+        let cells = vec![cell("b", 1), cell("a", 0)];
+        let c = DataCellRow::from(cells.clone());
+        assert_eq!(cells.as_slice(), c.as_slice());
+        assert_eq!("a", c.get_by_idx(0).unwrap().get_name());
+        assert_eq!(cells, c.clone().into_cells());
+        assert_eq!(cells, Vec::<DataCell>::from(c));
+    }
+
+    #[test]
+    pub fn mutation_api() {
+        let mut c = DataCellRow::new();
+        c.push(cell("b", 1));
+        c.push(cell("d", 3));
+        c.insert_at(0, cell("a", 0)).unwrap();
+        c.insert_at(2, cell("c", 2)).unwrap();
+        assert!(c.insert_at(5, cell("x", 9)).is_err());
+        assert_eq!(vec!["a", "b", "c", "d"], names(&c));
+        assert_eq!(Some(3), c.max_idx());
+
+        c.rename(1, String::from("bb")).unwrap();
+        assert_eq!(1, c.get_by_name("bb").unwrap().get_idx());
+        assert_eq!(
+            Err(VenumTdsError::DataAccess(
+                DataAccessErrors::IllegalIdxAccess { idx: 7 }
+            )),
+            c.rename(7, String::from("x"))
+        );
+
+        let old = c.replace_by_idx(2, cell("cc", 20)).unwrap();
+        assert_eq!("c", old.get_name());
+        assert!(!c.contains_idx(2));
+        assert!(c.contains_idx(20));
+        assert_eq!(vec!["a", "bb", "cc", "d"], names(&c));
+        assert_eq!(vec!["a", "bb", "d", "cc"], c.headers());
+
+        assert_eq!("d", c.del_by_name("d").unwrap().get_name());
+        assert_eq!(
+            Err(VenumTdsError::DataAccess(
+                DataAccessErrors::IllegalNameAccess {
+                    name: String::from("d")
+                }
+            )),
+            c.del_by_name("d")
+        );
+
+        c.retain(|dc| dc.get_idx() != 0);
+        assert_eq!(vec!["bb", "cc"], names(&c));
+        assert!(c.get_by_idx(0).is_none());
+    }
+
+    #[test]
+    pub fn take_by_idx() {
+        let mut c = DataCellRow::from(vec![cell("a", 0), cell("b", 1), cell("c", 2)]);
+
+        assert!(c.take_by_idx(&[2, 5]).is_err());
+        assert!(c.take_by_idx(&[2, 2]).is_err());
+        assert_eq!(3, c.len());
+
+        let taken = c.take_by_idx(&[2, 0]).unwrap();
+        assert_eq!(vec!["c", "a"], names(&taken));
+        assert_eq!(vec!["b"], names(&c));
+        assert_eq!("b", c.get_by_idx(1).unwrap().get_name());
+    }
+
+    #[test]
+    pub fn column_order() {
+        let c = DataCellRow::from(vec![
+            cell("c", 2),
+            DataCell::new(String::from("a"), 0, Value::Bool(true)).unwrap(),
+            cell("b", 1),
+        ]);
+
+        assert_eq!(
+            vec![0, 1, 2],
+            c.iter_sorted()
+                .map(|dc| dc.get_idx())
+                .collect::<Vec<usize>>()
+        );
+        assert_eq!(
+            vec![&ValueType::Bool, &ValueType::Int32, &ValueType::Int32],
+            c.types()
+        );
+        assert_eq!(vec!["c", "a", "b"], names(&c));
+        assert_eq!(
+            vec![Value::Bool(true), Value::Int32(1), Value::Int32(2)],
+            c.into_values()
+        );
+    }
+
+    #[test]
+    pub fn push_on_conflict() {
+        let mut c = DataCellRow::from(vec![cell("a", 0), cell("b", 1)]);
+
+        assert_eq!(
+            Err(VenumTdsError::DataAccess(DataAccessErrors::DuplicateIdx {
+                idx: 1
+            })),
+            c.try_push(cell("x", 1))
+        );
+        assert_eq!(
+            Err(VenumTdsError::DataAccess(DataAccessErrors::DuplicateName {
+                name: String::from("a")
+            })),
+            c.try_push(cell("a", 5))
+        );
+        c.push_with(cell("x", 1), OnConflict::Skip).unwrap();
+        assert_eq!(vec!["a", "b"], names(&c));
+
+        // conflicts with both cells, the new one takes the place of the first one
+        c.push_with(cell("a", 1), OnConflict::Replace).unwrap();
+        assert_eq!(vec!["a"], names(&c));
+        assert_eq!(1, c.get_by_name("a").unwrap().get_idx());
+        assert!(c.get_by_idx(0).is_none());
+
+        c.try_push(cell("b", 0)).unwrap();
+        c.set_strict(true);
+        assert!(c.push_checked(cell("c", 0), None).is_err());
+        assert!(c.rename(0, String::from("a")).is_err());
+        assert!(c.replace_by_idx(0, cell("c", 1)).is_err());
+        assert!(c.insert_at(0, cell("b", 7)).is_err());
+        c.rename(0, String::from("b")).unwrap();
+        c.replace_by_idx(0, cell("c", 0)).unwrap();
+        assert_eq!(vec!["a", "c"], names(&c));
+    }
+
+    #[test]
+    pub fn column_ref() {
+        let mut row = DataCellRow::new();
+        row.push(DataCell::new(String::from("a"), 3, Value::Int32(1)).unwrap());
+
+        assert_eq!(
+            &Value::Int32(1),
+            ColumnRef::Idx(3).get(&row).unwrap().get_data()
+        );
+        assert_eq!(
+            &Value::Int32(1),
+            ColumnRef::Name(String::from("a"))
+                .get(&row)
+                .unwrap()
+                .get_data()
+        );
+        assert_eq!(
+            Err(VenumTdsError::DataAccess(
+                DataAccessErrors::IllegalNameAccess {
+                    name: String::from("b")
+                }
+            )),
+            ColumnRef::Name(String::from("b")).get(&row)
+        );
+        assert!(ColumnRef::Idx(0).get(&row).is_err());
+    }
 }
@@ -0,0 +1,108 @@
+use std::fmt::Display;
+
+use crate::{data_cell_row::DataCellRow, render::RowRenderer};
+
+/// A collection of rows, for everything that needs to look at more than one row at a time, e.g.
+/// reshaping (see `transform::data_cell_table`). The rows don't need to have the same cells.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct DataCellTable {
+    rows: Vec<DataCellRow>,
+}
+
+impl DataCellTable {
+    pub fn new() -> Self {
+        Self { rows: Vec::new() }
+    }
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self {
+            rows: Vec::with_capacity(capacity),
+        }
+    }
+
+    pub fn push(&mut self, row: DataCellRow) {
+        self.rows.push(row);
+    }
+
+    pub fn get(&self, pos: usize) -> Option<&DataCellRow> {
+        self.rows.get(pos)
+    }
+    pub fn get_mut(&mut self, pos: usize) -> Option<&mut DataCellRow> {
+        self.rows.get_mut(pos)
+    }
+
+    pub fn rows(&self) -> &[DataCellRow] {
+        &self.rows
+    }
+    pub fn into_rows(self) -> Vec<DataCellRow> {
+        self.rows
+    }
+
+    pub fn len(&self) -> usize {
+        self.rows.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rows.is_empty()
+    }
+
+    pub fn iter(&self) -> std::slice::Iter<'_, DataCellRow> {
+        self.rows.iter()
+    }
+    pub fn iter_mut(&mut self) -> std::slice::IterMut<'_, DataCellRow> {
+        self.rows.iter_mut()
+    }
+}
+
+impl From<Vec<DataCellRow>> for DataCellTable {
+    fn from(rows: Vec<DataCellRow>) -> Self {
+        Self { rows }
+    }
+}
+
+impl FromIterator<DataCellRow> for DataCellTable {
+    fn from_iter<T: IntoIterator<Item = DataCellRow>>(iter: T) -> Self {
+        Self {
+            rows: iter.into_iter().collect(),
+        }
+    }
+}
+
+impl Extend<DataCellRow> for DataCellTable {
+    fn extend<T: IntoIterator<Item = DataCellRow>>(&mut self, iter: T) {
+        self.rows.extend(iter)
+    }
+}
+
+/// Renders the rows as a (text) table, see `RowRenderer` for more options.
+impl Display for DataCellTable {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", RowRenderer::default().render_rows(&self.rows))
+    }
+}
+
+impl IntoIterator for DataCellTable {
+    type Item = DataCellRow;
+    type IntoIter = std::vec::IntoIter<Self::Item>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.rows.into_iter()
+    }
+}
+
+impl<'a> IntoIterator for &'a DataCellTable {
+    type Item = &'a DataCellRow;
+    type IntoIter = std::slice::Iter<'a, DataCellRow>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.rows.iter()
+    }
+}
+
+impl<'a> IntoIterator for &'a mut DataCellTable {
+    type Item = &'a mut DataCellRow;
+    type IntoIter = std::slice::IterMut<'a, DataCellRow>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.rows.iter_mut()
+    }
+}
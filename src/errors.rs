@@ -3,6 +3,8 @@ use thiserror::Error;
 
 use venum::{errors_result::VenumError, value::Value, value_type::ValueType};
 
+use crate::{constraints::RuleViolation, schema::SchemaViolation};
+
 #[derive(Debug, Display, PartialEq)]
 pub enum WrappedErrors {
     VenumError(VenumError),
@@ -12,6 +14,9 @@ pub enum WrappedErrors {
 pub enum DataAccessErrors {
     IllegalIdxAccess { idx: usize },
     IllegalNameAccess { name: String },
+    IllegalPosAccess { pos: usize },
+    DuplicateIdx { idx: usize },
+    DuplicateName { name: String },
 }
 
 #[derive(Error, Debug, PartialEq)]
@@ -53,6 +58,25 @@ pub enum ContainerOpsErrors {
         idx: usize,
         msg: String,
     },
+    OrderItemsError {
+        msg: String,
+    },
+    SchemaError {
+        violations: Vec<SchemaViolation>,
+    },
+    ValidationError {
+        violations: Vec<RuleViolation>,
+    },
+    MapValueMiss {
+        idx: usize,
+        key: String,
+    },
+    ReshapeError {
+        msg: String,
+    },
+    GroupByError {
+        msg: String,
+    },
     ConvertAsError {
         src_idx: usize,
         src_data_type: ValueType,
@@ -61,6 +85,14 @@ pub enum ContainerOpsErrors {
     },
 }
 
+#[derive(Debug, Display, PartialEq, Eq)]
+pub enum IoErrors {
+    Generic { msg: String },
+    File { path: String, msg: String },
+    Parquet { msg: String },
+    Csv { msg: String },
+}
+
 #[derive(Debug, PartialEq, Display)]
 pub enum VenumTdsError {
     Generic { msg: String },
@@ -68,6 +100,7 @@ pub enum VenumTdsError {
     DataAccess(DataAccessErrors),
     Transform(TransformErrors),
     ContainerOps(ContainerOpsErrors),
+    Io(IoErrors),
 }
 
 pub type Result<T> = std::result::Result<T, VenumTdsError>;
@@ -0,0 +1,237 @@
+use std::{
+    fs::File,
+    io::{Read, Write},
+    path::Path,
+};
+
+use csv::{ReaderBuilder, StringRecord, WriterBuilder};
+use venum::{value::Value, value_type::ValueType};
+
+use crate::{
+    data_cell::DataCell,
+    data_cell_row::DataCellRow,
+    errors::{IoErrors, Result, VenumTdsError},
+    value_fmt::value_to_string,
+};
+
+fn csv_err<E: std::fmt::Display>(e: E) -> VenumTdsError {
+    VenumTdsError::Io(IoErrors::Csv {
+        msg: format!("{}", e),
+    })
+}
+
+fn file_err<P: AsRef<Path>, E: std::fmt::Display>(path: P, e: E) -> VenumTdsError {
+    VenumTdsError::Io(IoErrors::File {
+        path: path.as_ref().display().to_string(),
+        msg: format!("{}", e),
+    })
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CsvOptions {
+    pub delimiter: u8,
+    pub has_headers: bool,
+}
+
+impl Default for CsvOptions {
+    fn default() -> Self {
+        Self {
+            delimiter: b',',
+            has_headers: true,
+        }
+    }
+}
+
+/// Turns a csv record into a `DataCellRow`. Every cell is a `Value::String`, or `Value::None` if the
+/// field is empty. The idx of a cell is the position of the field in the record. If there are no
+/// headers, the idx is used as header.
+pub fn record_to_row(record: &StringRecord, headers: Option<&[String]>) -> DataCellRow {
+    let mut row = DataCellRow::with_capacity(record.len());
+    for (idx, field) in record.iter().enumerate() {
+        let name = headers
+            .and_then(|h| h.get(idx).cloned())
+            .unwrap_or_else(|| idx.to_string());
+        let data = if field.is_empty() {
+            Value::None
+        } else {
+            Value::String(String::from(field))
+        };
+        // we set the type info explicitly, so this can't fail
+        row.push(DataCell::new_with_type_info(ValueType::String, name, idx, data).unwrap());
+    }
+    row
+}
+
+/// Reads a csv source row by row, as `DataCellRow`s. (See `record_to_row`.)
+pub struct CsvRowReader<R: Read> {
+    reader: csv::Reader<R>,
+    headers: Option<Vec<String>>,
+}
+
+impl CsvRowReader<File> {
+    pub fn from_path<P: AsRef<Path>>(path: P, opts: &CsvOptions) -> Result<Self> {
+        let file = File::open(path.as_ref()).map_err(|e| file_err(path, e))?;
+        Self::from_reader(file, opts)
+    }
+}
+
+impl<R: Read> CsvRowReader<R> {
+    pub fn from_reader(rdr: R, opts: &CsvOptions) -> Result<Self> {
+        let mut reader = ReaderBuilder::new()
+            .delimiter(opts.delimiter)
+            .has_headers(opts.has_headers)
+            .flexible(true)
+            .from_reader(rdr);
+
+        let headers = if opts.has_headers {
+            Some(
+                reader
+                    .headers()
+                    .map_err(csv_err)?
+                    .iter()
+                    .map(String::from)
+                    .collect(),
+            )
+        } else {
+            None
+        };
+
+        Ok(Self { reader, headers })
+    }
+
+    pub fn headers(&self) -> Option<&[String]> {
+        self.headers.as_deref()
+    }
+}
+
+impl<R: Read> Iterator for CsvRowReader<R> {
+    type Item = Result<DataCellRow>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let mut record = StringRecord::new();
+        match self.reader.read_record(&mut record) {
+            Ok(true) => Some(Ok(record_to_row(&record, self.headers()))),
+            Ok(false) => None,
+            Err(e) => Some(Err(csv_err(e))),
+        }
+    }
+}
+
+/// Writes `DataCellRow`s as csv. Cells are written in column order, i.e. ordered by idx. The header
+/// line (if any) is taken from the first row written.
+pub struct CsvRowWriter<W: Write> {
+    writer: csv::Writer<W>,
+    write_headers: bool,
+}
+
+impl CsvRowWriter<File> {
+    pub fn from_path<P: AsRef<Path>>(path: P, opts: &CsvOptions) -> Result<Self> {
+        let file = File::create(path.as_ref()).map_err(|e| file_err(path, e))?;
+        Ok(Self::from_writer(file, opts))
+    }
+}
+
+impl<W: Write> CsvRowWriter<W> {
+    pub fn from_writer(wtr: W, opts: &CsvOptions) -> Self {
+        Self {
+            writer: WriterBuilder::new()
+                .delimiter(opts.delimiter)
+                .flexible(true)
+                .from_writer(wtr),
+            write_headers: opts.has_headers,
+        }
+    }
+
+    pub fn write(&mut self, row: &DataCellRow) -> Result<()> {
+        let cells: Vec<&DataCell> = row.iter_sorted().collect();
+
+        if self.write_headers {
+            self.writer
+                .write_record(cells.iter().map(|dc| dc.get_name()))
+                .map_err(csv_err)?;
+            self.write_headers = false;
+        }
+        self.writer
+            .write_record(cells.iter().map(|dc| value_to_string(dc.get_data())))
+            .map_err(csv_err)
+    }
+
+    /// Writes a "raw" record, e.g. for error reports.
+    pub fn write_record<I, T>(&mut self, record: I) -> Result<()>
+    where
+        I: IntoIterator<Item = T>,
+        T: AsRef<[u8]>,
+    {
+        self.writer.write_record(record).map_err(csv_err)
+    }
+
+    pub fn flush(&mut self) -> Result<()> {
+        self.writer.flush().map_err(csv_err)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use venum::value::Value;
+
+    use super::*;
+
+    #[test]
+    fn read_rows_with_headers() {
+        let data = "amount,currency\n10.10,CHF\n,EUR\n";
+        let rows = CsvRowReader::from_reader(data.as_bytes(), &CsvOptions::default())
+            .unwrap()
+            .collect::<Result<Vec<DataCellRow>>>()
+            .unwrap();
+
+        assert_eq!(2, rows.len());
+        assert_eq!("currency", rows[0].get_by_idx(1).unwrap().get_name());
+        assert_eq!(
+            &Value::String(String::from("10.10")),
+            rows[0].get_by_name("amount").unwrap().get_data()
+        );
+        assert_eq!(&Value::None, rows[1].get_by_idx(0).unwrap().get_data());
+    }
+
+    #[test]
+    fn read_rows_without_headers() {
+        let data = "10.10;CHF\n";
+        let opts = CsvOptions {
+            delimiter: b';',
+            has_headers: false,
+        };
+        let rows = CsvRowReader::from_reader(data.as_bytes(), &opts)
+            .unwrap()
+            .collect::<Result<Vec<DataCellRow>>>()
+            .unwrap();
+
+        assert_eq!(1, rows.len());
+        assert_eq!("1", rows[0].get_by_idx(1).unwrap().get_name());
+    }
+
+    #[test]
+    fn write_rows_in_column_order() {
+        let mut row = DataCellRow::new();
+        row.push(
+            DataCell::new(
+                String::from("currency"),
+                1,
+                Value::String(String::from("CHF")),
+            )
+            .unwrap(),
+        );
+        row.push(DataCell::new(String::from("amount"), 0, Value::Int32(10)).unwrap());
+
+        let mut buf: Vec<u8> = Vec::new();
+        {
+            let mut writer = CsvRowWriter::from_writer(&mut buf, &CsvOptions::default());
+            writer.write(&row).unwrap();
+            writer.write(&row).unwrap();
+            writer.flush().unwrap();
+        }
+        assert_eq!(
+            "amount,currency\n10,CHF\n10,CHF\n",
+            String::from_utf8(buf).unwrap()
+        );
+    }
+}
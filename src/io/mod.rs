@@ -0,0 +1,6 @@
+#[cfg(feature = "csv")]
+pub mod csv;
+#[cfg(feature = "jsonconf")]
+pub mod ndjson;
+#[cfg(feature = "parquet")]
+pub mod parquet;
@@ -0,0 +1,182 @@
+use std::io::Write;
+
+use serde::ser::{Serialize, SerializeMap, Serializer};
+use serde_json::{Map, Number};
+use venum::value::Value;
+
+use crate::{
+    data_cell_row::DataCellRow,
+    errors::{IoErrors, Result, VenumTdsError},
+    value_fmt::value_to_string,
+};
+
+/// Converts a `Value` into its JSON counterpart. Numbers that JSON can't represent losslessly
+/// (i.e. 128 bit integers out of the 64 bit range and `Decimal`s), as well as date/time values,
+/// become strings.
+pub fn value_to_json(val: &Value) -> serde_json::Value {
+    match val {
+        Value::None => serde_json::Value::Null,
+        Value::Bool(b) => serde_json::Value::Bool(*b),
+        Value::Int8(i) => serde_json::Value::from(*i),
+        Value::Int16(i) => serde_json::Value::from(*i),
+        Value::Int32(i) => serde_json::Value::from(*i),
+        Value::Int64(i) => serde_json::Value::from(*i),
+        Value::Int128(i) => i64::try_from(*i)
+            .map(serde_json::Value::from)
+            .unwrap_or_else(|_| serde_json::Value::String(i.to_string())),
+        Value::UInt8(u) => serde_json::Value::from(*u),
+        Value::UInt16(u) => serde_json::Value::from(*u),
+        Value::UInt32(u) => serde_json::Value::from(*u),
+        Value::UInt64(u) => serde_json::Value::from(*u),
+        Value::UInt128(u) => u64::try_from(*u)
+            .map(serde_json::Value::from)
+            .unwrap_or_else(|_| serde_json::Value::String(u.to_string())),
+        Value::Float32(f) => Number::from_f64(*f as f64)
+            .map(serde_json::Value::Number)
+            .unwrap_or(serde_json::Value::Null),
+        Value::Float64(f) => Number::from_f64(*f)
+            .map(serde_json::Value::Number)
+            .unwrap_or(serde_json::Value::Null),
+        _ => serde_json::Value::String(value_to_string(val)),
+    }
+}
+
+/// The cells of a row as (header, value) pairs, in column (i.e. idx) order. If headers are not
+/// unique, the cell with the highest idx wins (at the position of the first one).
+fn json_entries(row: &DataCellRow) -> Vec<(&str, &Value)> {
+    let mut entries: Vec<(&str, &Value)> = Vec::with_capacity(row.len());
+    for dc in row.iter_sorted() {
+        match entries.iter_mut().find(|(name, _)| *name == dc.get_name()) {
+            Some(entry) => entry.1 = dc.get_data(),
+            None => entries.push((dc.get_name(), dc.get_data())),
+        }
+    }
+    entries
+}
+
+/// Converts a row into a JSON object, with the headers as keys. If headers are not unique, the
+/// cell with the highest idx wins. (A `serde_json::Value` keeps its keys sorted, use `JsonRow` to
+/// get them in column order.)
+pub fn row_to_json(row: &DataCellRow) -> serde_json::Value {
+    let mut obj = Map::new();
+    for (name, val) in json_entries(row) {
+        obj.insert(String::from(name), value_to_json(val));
+    }
+    serde_json::Value::Object(obj)
+}
+
+/// Serializes a row as a JSON object like `row_to_json`, but with the keys in column (i.e. idx)
+/// order.
+pub struct JsonRow<'a>(pub &'a DataCellRow);
+
+impl Serialize for JsonRow<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        let entries = json_entries(self.0);
+        let mut map = serializer.serialize_map(Some(entries.len()))?;
+        for (name, val) in entries {
+            map.serialize_entry(name, &value_to_json(val))?;
+        }
+        map.end()
+    }
+}
+
+/// Writes `DataCellRow`s as newline delimited JSON, i.e. one JSON object per line.
+pub struct NdjsonRowWriter<W: Write> {
+    writer: W,
+}
+
+impl<W: Write> NdjsonRowWriter<W> {
+    pub fn new(writer: W) -> Self {
+        Self { writer }
+    }
+
+    pub fn write(&mut self, row: &DataCellRow) -> Result<()> {
+        serde_json::to_writer(&mut self.writer, &JsonRow(row)).map_err(|e| {
+            VenumTdsError::Io(IoErrors::Generic {
+                msg: format!("{}", e),
+            })
+        })?;
+        self.writer.write_all(b"\n").map_err(|e| {
+            VenumTdsError::Io(IoErrors::Generic {
+                msg: format!("{}", e),
+            })
+        })
+    }
+
+    pub fn flush(&mut self) -> Result<()> {
+        self.writer.flush().map_err(|e| {
+            VenumTdsError::Io(IoErrors::Generic {
+                msg: format!("{}", e),
+            })
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use venum::value::Value;
+    use venum::value_type::ValueType;
+
+    use super::*;
+    use crate::data_cell::DataCell;
+
+    #[test]
+    fn write_rows_as_ndjson() {
+        let mut row = DataCellRow::new();
+        row.push(
+            DataCell::new(
+                String::from("currency"),
+                1,
+                Value::String(String::from("CHF")),
+            )
+            .unwrap(),
+        );
+        row.push(DataCell::new(String::from("amount"), 0, Value::Int32(10)).unwrap());
+        row.push(DataCell::new_without_data(
+            ValueType::Bool,
+            String::from("flag"),
+            2,
+        ));
+
+        let mut buf: Vec<u8> = Vec::new();
+        let mut writer = NdjsonRowWriter::new(&mut buf);
+        writer.write(&row).unwrap();
+        writer.write(&row).unwrap();
+
+        assert_eq!(
+            "{\"amount\":10,\"currency\":\"CHF\",\"flag\":null}\n{\"amount\":10,\"currency\":\"CHF\",\"flag\":null}\n",
+            String::from_utf8(buf).unwrap()
+        );
+    }
+
+    #[test]
+    fn keys_in_column_order() {
+        let mut row = DataCellRow::new();
+        row.push(DataCell::new(String::from("zeta"), 0, Value::Int32(1)).unwrap());
+        row.push(DataCell::new(String::from("beta"), 2, Value::Int32(3)).unwrap());
+        row.push(DataCell::new(String::from("alpha"), 1, Value::Int32(2)).unwrap());
+
+        assert_eq!(
+            "{\"zeta\":1,\"alpha\":2,\"beta\":3}",
+            serde_json::to_string(&JsonRow(&row)).unwrap()
+        );
+        // (a Value has its keys sorted)
+        assert_eq!(
+            "{\"alpha\":2,\"beta\":3,\"zeta\":1}",
+            row_to_json(&row).to_string()
+        );
+    }
+
+    #[test]
+    fn duplicate_headers() {
+        let mut row = DataCellRow::new();
+        row.push(DataCell::new(String::from("b"), 0, Value::Int32(1)).unwrap());
+        row.push(DataCell::new(String::from("a"), 1, Value::Int32(2)).unwrap());
+        row.push(DataCell::new(String::from("b"), 2, Value::Int32(3)).unwrap());
+
+        assert_eq!(
+            "{\"b\":3,\"a\":2}",
+            serde_json::to_string(&JsonRow(&row)).unwrap()
+        );
+    }
+}
@@ -0,0 +1,655 @@
+use std::{fs::File, path::Path, sync::Arc};
+
+use arrow::{
+    array::{
+        ArrayRef, BooleanArray, Date32Array, Decimal128Array, Float32Array, Float64Array,
+        Int16Array, Int32Array, Int64Array, Int8Array, StringArray, TimestampMicrosecondArray,
+        UInt16Array, UInt32Array, UInt64Array, UInt8Array,
+    },
+    datatypes::{DataType, Field, Schema, SchemaRef, TimeUnit},
+    record_batch::RecordBatch,
+};
+use chrono::NaiveDate;
+use parquet::{
+    arrow::ArrowWriter,
+    basic::{Compression, GzipLevel, ZstdLevel},
+    file::properties::WriterProperties,
+};
+use rust_decimal::Decimal;
+use venum::{value::Value, value_type::ValueType};
+
+use crate::{
+    data_cell_row::DataCellRow,
+    errors::{DataAccessErrors, IoErrors, Result, VenumTdsError},
+};
+
+#[cfg(feature = "transform")]
+use crate::transform::data_cell_row::{
+    transrich_iter::{IntoRowResult, TransrichIterExt},
+    transrich_pass::TransrichPasses,
+};
+
+/// Max. precision of a parquet `DECIMAL` backed by 128 bits (which is what we write).
+pub const DECIMAL_MAX_PRECISION: u8 = 38;
+
+/// How many rows we collect into one arrow `RecordBatch`, before we hand it over to the parquet writer.
+const BATCH_SIZE: usize = 1024;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ParquetCompression {
+    Uncompressed,
+    Snappy,
+    Gzip,
+    Zstd,
+}
+
+impl From<ParquetCompression> for Compression {
+    fn from(c: ParquetCompression) -> Self {
+        match c {
+            ParquetCompression::Uncompressed => Compression::UNCOMPRESSED,
+            ParquetCompression::Snappy => Compression::SNAPPY,
+            ParquetCompression::Gzip => Compression::GZIP(GzipLevel::default()),
+            ParquetCompression::Zstd => Compression::ZSTD(ZstdLevel::default()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParquetWriterOptions {
+    /// Max. number of rows per row group.
+    pub row_group_size: usize,
+    pub compression: ParquetCompression,
+    /// Default precision for `Decimal` columns, unless overridden per column.
+    pub decimal_precision: u8,
+    /// Default scale for `Decimal` columns, unless overridden per column.
+    pub decimal_scale: i8,
+    /// The timezone we annotate `DateTime` columns with. Values are always written as UTC instants.
+    pub timezone: String,
+}
+
+impl Default for ParquetWriterOptions {
+    fn default() -> Self {
+        Self {
+            row_group_size: 1024 * 1024,
+            compression: ParquetCompression::Snappy,
+            decimal_precision: DECIMAL_MAX_PRECISION,
+            decimal_scale: 10,
+            timezone: String::from("UTC"),
+        }
+    }
+}
+
+/// Describes one parquet column and where its data comes from, i.e. the `idx` of the `DataCell` in a `DataCellRow`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParquetColumn {
+    pub idx: usize,
+    pub name: String,
+    pub dtype: ValueType,
+    pub nullable: bool,
+    /// (precision, scale) for `Decimal` columns. Falls back to the writer options, if not set.
+    pub decimal: Option<(u8, i8)>,
+}
+
+impl ParquetColumn {
+    pub fn new(idx: usize, name: String, dtype: ValueType) -> Self {
+        Self {
+            idx,
+            name,
+            dtype,
+            nullable: true,
+            decimal: None,
+        }
+    }
+
+    /// Derives the columns from (the type info of) the cells of a row, in column order, i.e. ordered by `idx`.
+    pub fn from_row(row: &DataCellRow) -> Vec<Self> {
+        let mut cols: Vec<Self> = row
+            .into_iter()
+            .map(|dc| Self::new(dc.get_idx(), String::from(dc.get_name()), dc.dtype.clone()))
+            .collect();
+        cols.sort_by_key(|c| c.idx);
+        cols
+    }
+
+    fn arrow_data_type(&self, opts: &ParquetWriterOptions) -> DataType {
+        match self.dtype {
+            ValueType::Bool => DataType::Boolean,
+            ValueType::Char | ValueType::String => DataType::Utf8,
+            ValueType::Int8 => DataType::Int8,
+            ValueType::Int16 => DataType::Int16,
+            ValueType::Int32 => DataType::Int32,
+            ValueType::Int64 => DataType::Int64,
+            // parquet has no 128 bit integers, a DECIMAL(38, 0) is the closest thing
+            ValueType::Int128 | ValueType::UInt128 => {
+                DataType::Decimal128(DECIMAL_MAX_PRECISION, 0)
+            }
+            ValueType::UInt8 => DataType::UInt8,
+            ValueType::UInt16 => DataType::UInt16,
+            ValueType::UInt32 => DataType::UInt32,
+            ValueType::UInt64 => DataType::UInt64,
+            ValueType::Float32 => DataType::Float32,
+            ValueType::Float64 => DataType::Float64,
+            ValueType::Decimal => {
+                let (precision, scale) = self.decimal_precision_and_scale(opts);
+                DataType::Decimal128(precision, scale)
+            }
+            ValueType::NaiveDate => DataType::Date32,
+            ValueType::NaiveDateTime => DataType::Timestamp(TimeUnit::Microsecond, None),
+            ValueType::DateTime => {
+                DataType::Timestamp(TimeUnit::Microsecond, Some(opts.timezone.as_str().into()))
+            }
+        }
+    }
+
+    fn decimal_precision_and_scale(&self, opts: &ParquetWriterOptions) -> (u8, i8) {
+        self.decimal
+            .unwrap_or((opts.decimal_precision, opts.decimal_scale))
+    }
+
+    fn arrow_field(&self, opts: &ParquetWriterOptions) -> Field {
+        Field::new(&self.name, self.arrow_data_type(opts), self.nullable)
+    }
+}
+
+fn parquet_err<E: std::fmt::Display>(e: E) -> VenumTdsError {
+    VenumTdsError::Io(IoErrors::Parquet {
+        msg: format!("{}", e),
+    })
+}
+
+fn type_mismatch(col: &ParquetColumn, val: &Value) -> VenumTdsError {
+    VenumTdsError::Io(IoErrors::Parquet {
+        msg: format!(
+            "column '{}' (idx={}) is of type {}, but got value {:?}",
+            col.name, col.idx, col.dtype, val
+        ),
+    })
+}
+
+fn value_at<'a>(row: &'a DataCellRow, col: &ParquetColumn) -> Result<&'a Value> {
+    row.get_by_idx(col.idx)
+        .map(|dc| dc.get_data())
+        .ok_or(VenumTdsError::DataAccess(
+            DataAccessErrors::IllegalIdxAccess { idx: col.idx },
+        ))
+}
+
+fn unix_epoch() -> NaiveDate {
+    NaiveDate::from_ymd_opt(1970, 1, 1).unwrap() // This date exists for sure. Unwrap is safe here
+}
+
+fn i128_to_decimal_mantissa(col: &ParquetColumn, val: &Value, i: i128) -> Result<i128> {
+    if i.unsigned_abs() >= 10_u128.pow(DECIMAL_MAX_PRECISION as u32) {
+        return Err(type_mismatch(col, val));
+    }
+    Ok(i)
+}
+
+/// The mantissa of `d` at the given scale. Unlike `Decimal::rescale`, this never rounds: a value
+/// with more (significant) fractional digits than `scale`, or more digits than `precision`, is an
+/// error.
+fn decimal_to_mantissa(
+    col: &ParquetColumn,
+    val: &Value,
+    d: &Decimal,
+    precision: u8,
+    scale: u32,
+) -> Result<i128> {
+    let mut rescaled = *d;
+    rescaled.rescale(scale);
+    if rescaled != *d || rescaled.scale() != scale {
+        return Err(parquet_err(format!(
+            "column '{}' (idx={}) has a scale of {}, value {:?} would lose digits",
+            col.name, col.idx, scale, val
+        )));
+    }
+    let mantissa = rescaled.mantissa();
+    if mantissa.unsigned_abs() >= 10_u128.pow(precision as u32) {
+        return Err(parquet_err(format!(
+            "column '{}' (idx={}) has a precision of {}, value {:?} has too many digits",
+            col.name, col.idx, precision, val
+        )));
+    }
+    Ok(mantissa)
+}
+
+/// Collects the values of one column into a `Vec<Option<T>>`. `Value::None` becomes `None`, everything
+/// else goes through the given match arms. Anything not matched is a type mismatch.
+macro_rules! column_values {
+    ($rows:expr, $col:expr, $val:ident => { $($pat:pat => $res:expr),+ $(,)? }) => {
+        $rows
+            .iter()
+            .map(|row| {
+                let $val = value_at(row, $col)?;
+                match $val {
+                    Value::None => Ok(None),
+                    $($pat => Ok(Some($res)),)+
+                    other => Err(type_mismatch($col, other)),
+                }
+            })
+            .collect::<Result<Vec<_>>>()?
+    };
+}
+
+fn build_array(
+    col: &ParquetColumn,
+    rows: &[DataCellRow],
+    opts: &ParquetWriterOptions,
+) -> Result<ArrayRef> {
+    let arr: ArrayRef = match col.dtype {
+        ValueType::Bool => Arc::new(BooleanArray::from(
+            column_values!(rows, col, v => { Value::Bool(b) => *b }),
+        )),
+        ValueType::Char | ValueType::String => {
+            Arc::new(StringArray::from(column_values!(rows, col, v => {
+                Value::String(s) => s.clone(),
+                Value::Char(c) => c.to_string(),
+            })))
+        }
+        ValueType::Int8 => Arc::new(Int8Array::from(
+            column_values!(rows, col, v => { Value::Int8(i) => *i }),
+        )),
+        ValueType::Int16 => Arc::new(Int16Array::from(
+            column_values!(rows, col, v => { Value::Int16(i) => *i }),
+        )),
+        ValueType::Int32 => Arc::new(Int32Array::from(
+            column_values!(rows, col, v => { Value::Int32(i) => *i }),
+        )),
+        ValueType::Int64 => Arc::new(Int64Array::from(
+            column_values!(rows, col, v => { Value::Int64(i) => *i }),
+        )),
+        ValueType::UInt8 => Arc::new(UInt8Array::from(
+            column_values!(rows, col, v => { Value::UInt8(i) => *i }),
+        )),
+        ValueType::UInt16 => Arc::new(UInt16Array::from(
+            column_values!(rows, col, v => { Value::UInt16(i) => *i }),
+        )),
+        ValueType::UInt32 => Arc::new(UInt32Array::from(
+            column_values!(rows, col, v => { Value::UInt32(i) => *i }),
+        )),
+        ValueType::UInt64 => Arc::new(UInt64Array::from(
+            column_values!(rows, col, v => { Value::UInt64(i) => *i }),
+        )),
+        ValueType::Int128 | ValueType::UInt128 => {
+            let vals = rows
+                .iter()
+                .map(|row| {
+                    let v = value_at(row, col)?;
+                    match v {
+                        Value::None => Ok(None),
+                        Value::Int128(i) => i128_to_decimal_mantissa(col, v, *i).map(Some),
+                        Value::UInt128(u) => i128::try_from(*u)
+                            .map_err(|_| type_mismatch(col, v))
+                            .and_then(|i| i128_to_decimal_mantissa(col, v, i))
+                            .map(Some),
+                        other => Err(type_mismatch(col, other)),
+                    }
+                })
+                .collect::<Result<Vec<_>>>()?;
+            Arc::new(
+                Decimal128Array::from(vals)
+                    .with_precision_and_scale(DECIMAL_MAX_PRECISION, 0)
+                    .map_err(parquet_err)?,
+            )
+        }
+        ValueType::Float32 => Arc::new(Float32Array::from(
+            column_values!(rows, col, v => { Value::Float32(f) => *f }),
+        )),
+        ValueType::Float64 => Arc::new(Float64Array::from(
+            column_values!(rows, col, v => { Value::Float64(f) => *f }),
+        )),
+        ValueType::Decimal => {
+            let (precision, scale) = col.decimal_precision_and_scale(opts);
+            if scale < 0 {
+                return Err(parquet_err(format!(
+                    "negative scale ({}) for column '{}' is not supported",
+                    scale, col.name
+                )));
+            }
+            let vals = rows
+                .iter()
+                .map(|row| {
+                    let v = value_at(row, col)?;
+                    match v {
+                        Value::None => Ok(None),
+                        Value::Decimal(d) => {
+                            decimal_to_mantissa(col, v, d, precision, scale as u32).map(Some)
+                        }
+                        other => Err(type_mismatch(col, other)),
+                    }
+                })
+                .collect::<Result<Vec<_>>>()?;
+            Arc::new(
+                Decimal128Array::from(vals)
+                    .with_precision_and_scale(precision, scale)
+                    .map_err(parquet_err)?,
+            )
+        }
+        ValueType::NaiveDate => Arc::new(Date32Array::from(column_values!(rows, col, v => {
+            Value::NaiveDate(d) => d.signed_duration_since(unix_epoch()).num_days() as i32
+        }))),
+        ValueType::NaiveDateTime => Arc::new(TimestampMicrosecondArray::from(
+            column_values!(rows, col, v => {
+                Value::NaiveDateTime(dt) => dt.and_utc().timestamp_micros()
+            }),
+        )),
+        ValueType::DateTime => Arc::new(
+            TimestampMicrosecondArray::from(column_values!(rows, col, v => {
+                Value::DateTime(dt) => dt.timestamp_micros()
+            }))
+            .with_timezone(opts.timezone.as_str()),
+        ),
+    };
+    Ok(arr)
+}
+
+/// Writes `DataCellRow`s into a parquet file. Rows are buffered and written in batches, so the
+/// writer can be fed row by row, e.g. straight out of a transrichment.
+///
+/// CAUTION: You MUST call `close()` in the end, otherwise the file will not be valid parquet!
+pub struct DataCellRowParquetWriter {
+    columns: Vec<ParquetColumn>,
+    schema: SchemaRef,
+    opts: ParquetWriterOptions,
+    writer: ArrowWriter<File>,
+    buffer: Vec<DataCellRow>,
+    rows_written: usize,
+}
+
+impl DataCellRowParquetWriter {
+    pub fn create<P: AsRef<Path>>(
+        path: P,
+        columns: Vec<ParquetColumn>,
+        opts: ParquetWriterOptions,
+    ) -> Result<Self> {
+        let file = File::create(path.as_ref()).map_err(|e| {
+            VenumTdsError::Io(IoErrors::File {
+                path: path.as_ref().display().to_string(),
+                msg: e.to_string(),
+            })
+        })?;
+
+        let schema: SchemaRef = Arc::new(Schema::new(
+            columns
+                .iter()
+                .map(|c| c.arrow_field(&opts))
+                .collect::<Vec<Field>>(),
+        ));
+
+        let props = WriterProperties::builder()
+            .set_max_row_group_size(opts.row_group_size)
+            .set_compression(opts.compression.into())
+            .build();
+
+        let writer =
+            ArrowWriter::try_new(file, schema.clone(), Some(props)).map_err(parquet_err)?;
+
+        Ok(Self {
+            columns,
+            schema,
+            opts,
+            writer,
+            buffer: Vec::with_capacity(BATCH_SIZE),
+            rows_written: 0,
+        })
+    }
+
+    pub fn columns(&self) -> &[ParquetColumn] {
+        &self.columns
+    }
+
+    pub fn write(&mut self, row: DataCellRow) -> Result<()> {
+        self.buffer.push(row);
+        if self.buffer.len() >= BATCH_SIZE {
+            self.flush_batch()?;
+        }
+        Ok(())
+    }
+
+    fn flush_batch(&mut self) -> Result<()> {
+        if self.buffer.is_empty() {
+            return Ok(());
+        }
+        let arrays = self
+            .columns
+            .iter()
+            .map(|c| build_array(c, &self.buffer, &self.opts))
+            .collect::<Result<Vec<ArrayRef>>>()?;
+        let batch = RecordBatch::try_new(self.schema.clone(), arrays).map_err(parquet_err)?;
+        self.writer.write(&batch).map_err(parquet_err)?;
+        self.rows_written += self.buffer.len();
+        self.buffer.clear();
+        Ok(())
+    }
+
+    /// Writes all remaining (buffered) rows and the parquet footer. Returns the number of rows written.
+    pub fn close(mut self) -> Result<usize> {
+        self.flush_batch()?;
+        self.writer.close().map_err(parquet_err)?;
+        Ok(self.rows_written)
+    }
+}
+
+/// Writes all rows into a parquet file. The columns are derived from the first row.
+pub fn write_rows<P: AsRef<Path>>(
+    path: P,
+    rows: Vec<DataCellRow>,
+    opts: ParquetWriterOptions,
+) -> Result<usize> {
+    let columns = match rows.first() {
+        Some(first) => ParquetColumn::from_row(first),
+        None => Vec::new(),
+    };
+    let mut writer = DataCellRowParquetWriter::create(path, columns, opts)?;
+    for row in rows {
+        writer.write(row)?;
+    }
+    writer.close()
+}
+
+/// Runs every row through the transrichment passes (see `TransrichIterExt::transrich`, i.e. rows
+/// may be exploded or filtered out) and writes the resulting rows into a parquet file. Since the
+/// layout of a row is only known after the transrichment, the columns are derived from the first
+/// resulting row, unless given explicitly. Returns the number of rows written.
+#[cfg(feature = "transform")]
+pub fn transrich_to_parquet<P, I>(
+    path: P,
+    rows: I,
+    passes: &mut TransrichPasses,
+    columns: Option<Vec<ParquetColumn>>,
+    opts: ParquetWriterOptions,
+) -> Result<usize>
+where
+    P: AsRef<Path>,
+    I: IntoIterator,
+    I::Item: IntoRowResult,
+{
+    let mut rows = rows.into_iter().transrich(passes);
+    let first = match rows.next() {
+        Some(r) => r?,
+        None => {
+            return DataCellRowParquetWriter::create(path, columns.unwrap_or_default(), opts)?
+                .close()
+        }
+    };
+
+    let columns = columns.unwrap_or_else(|| ParquetColumn::from_row(&first));
+    let mut writer = DataCellRowParquetWriter::create(path, columns, opts)?;
+    writer.write(first)?;
+
+    for row in rows {
+        writer.write(row?)?;
+    }
+    writer.close()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs::File;
+
+    use parquet::file::reader::{FileReader, SerializedFileReader};
+    use tempfile::NamedTempFile;
+    use venum::value::Value;
+
+    use super::*;
+    use crate::data_cell::DataCell;
+
+    fn test_rows() -> Vec<DataCellRow> {
+        (0..3)
+            .map(|i| {
+                let mut row = DataCellRow::new();
+                row.push(DataCell::new(String::from("id"), 0, Value::Int32(i)).unwrap());
+                row.push(
+                    DataCell::new(
+                        String::from("amount"),
+                        1,
+                        Value::Decimal(Decimal::new(1050 + i as i64, 2)),
+                    )
+                    .unwrap(),
+                );
+                row.push(
+                    DataCell::new(
+                        String::from("date"),
+                        2,
+                        Value::NaiveDate(NaiveDate::from_ymd_opt(2022, 7, 20).unwrap()),
+                    )
+                    .unwrap(),
+                );
+                row.push(DataCell::new_without_data(
+                    ValueType::String,
+                    String::from("comment"),
+                    3,
+                ));
+                row
+            })
+            .collect()
+    }
+
+    #[test]
+    fn columns_from_row() {
+        let rows = test_rows();
+        let cols = ParquetColumn::from_row(&rows[0]);
+        assert_eq!(4, cols.len());
+        assert_eq!(ValueType::Decimal, cols[1].dtype);
+        assert_eq!(
+            DataType::Decimal128(38, 10),
+            cols[1].arrow_data_type(&ParquetWriterOptions::default())
+        );
+        assert_eq!(
+            DataType::Date32,
+            cols[2].arrow_data_type(&ParquetWriterOptions::default())
+        );
+    }
+
+    #[test]
+    fn write_rows_to_file() {
+        let file = NamedTempFile::new().unwrap();
+        let opts = ParquetWriterOptions {
+            compression: ParquetCompression::Zstd,
+            row_group_size: 2,
+            ..Default::default()
+        };
+
+        let written = write_rows(file.path(), test_rows(), opts).unwrap();
+        assert_eq!(3, written);
+
+        let reader = SerializedFileReader::new(File::open(file.path()).unwrap()).unwrap();
+        assert_eq!(3, reader.metadata().file_metadata().num_rows());
+        assert_eq!(2, reader.metadata().num_row_groups());
+    }
+
+    #[test]
+    #[should_panic(expected = "Io(Parquet")]
+    fn write_rows_type_mismatch() {
+        let file = NamedTempFile::new().unwrap();
+        let mut rows = test_rows();
+        rows[1]
+            .get_by_idx_mut(0)
+            .unwrap()
+            .set_data(Value::Bool(true));
+        write_rows(file.path(), rows, ParquetWriterOptions::default()).unwrap();
+    }
+
+    #[test]
+    fn write_rows_decimal_out_of_range() {
+        let file = NamedTempFile::new().unwrap();
+        let opts = ParquetWriterOptions {
+            decimal_precision: 5,
+            decimal_scale: 2,
+            ..Default::default()
+        };
+        // trailing zeros are fine, they don't get lost
+        let mut rows = test_rows();
+        rows[0]
+            .get_by_idx_mut(1)
+            .unwrap()
+            .set_data(Value::Decimal(Decimal::new(105000, 4)));
+        assert_eq!(3, write_rows(file.path(), rows, opts.clone()).unwrap());
+
+        // more fractional digits than the scale
+        let mut rows = test_rows();
+        rows[2]
+            .get_by_idx_mut(1)
+            .unwrap()
+            .set_data(Value::Decimal(Decimal::new(10505, 3)));
+        assert!(write_rows(file.path(), rows, opts.clone()).is_err());
+
+        // more digits than the precision
+        let mut rows = test_rows();
+        rows[2]
+            .get_by_idx_mut(1)
+            .unwrap()
+            .set_data(Value::Decimal(Decimal::new(100000, 2)));
+        assert!(write_rows(file.path(), rows, opts).is_err());
+    }
+
+    #[cfg(feature = "transform")]
+    #[test]
+    fn transrich_exploding_to_parquet() {
+        use crate::transform::{
+            data_cell_row::{transrich_explode::ExplodeItem, transrich_pass::TransrichPass},
+            value::spliting::ValueStringSeparatorCharTokens,
+        };
+
+        let mut passes = TransrichPasses(vec![TransrichPass::new(Vec::new(), Vec::new(), None)
+            .with_exploding(vec![Box::new(ExplodeItem {
+                idx: 3,
+                splitter: ValueStringSeparatorCharTokens { sep_char: ',' },
+                target_type: ValueType::String,
+                keep_empty: false,
+            })])]);
+
+        // the first row has no comment, i.e. it is filtered out, the second one is exploded
+        let mut rows = test_rows();
+        rows[1]
+            .get_by_idx_mut(3)
+            .unwrap()
+            .set_data(Value::String(String::from("x, y")));
+        rows[2]
+            .get_by_idx_mut(3)
+            .unwrap()
+            .set_data(Value::String(String::from("z")));
+
+        let file = NamedTempFile::new().unwrap();
+        let written = transrich_to_parquet(
+            file.path(),
+            rows,
+            &mut passes,
+            None,
+            ParquetWriterOptions::default(),
+        )
+        .unwrap();
+        assert_eq!(3, written);
+
+        let reader = SerializedFileReader::new(File::open(file.path()).unwrap()).unwrap();
+        assert_eq!(3, reader.metadata().file_metadata().num_rows());
+        assert_eq!(
+            4,
+            reader
+                .metadata()
+                .file_metadata()
+                .schema_descr()
+                .num_columns()
+        );
+    }
+}
@@ -1,5 +1,11 @@
 pub mod conf;
+pub mod constraints;
 pub mod data_cell;
 pub mod data_cell_row;
+pub mod data_cell_table;
 pub mod errors;
+pub mod io;
+pub mod render;
+pub mod schema;
 pub mod transform;
+pub mod value_fmt;
@@ -0,0 +1,294 @@
+use std::collections::BTreeMap;
+
+use venum::value::Value;
+
+use crate::{data_cell::DataCell, data_cell_row::DataCellRow, value_fmt::value_to_string};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RenderMode {
+    /// One line per row, one column per cell. Cells are aligned by their `idx`.
+    Table,
+    /// One block per row, one line per cell. Better suited for wide rows.
+    Record,
+}
+
+/// Renders `DataCellRow`s as aligned, human readable text. Mostly meant for debugging.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RowRenderer {
+    pub mode: RenderMode,
+    /// Values longer than this (in chars) are truncated. `0` means: never truncate.
+    pub max_value_width: usize,
+    pub show_idx: bool,
+    pub show_types: bool,
+    /// How a `Value::None` is shown.
+    pub none_repr: String,
+}
+
+impl Default for RowRenderer {
+    fn default() -> Self {
+        Self {
+            mode: RenderMode::Table,
+            max_value_width: 32,
+            show_idx: true,
+            show_types: true,
+            none_repr: String::from("None"),
+        }
+    }
+}
+
+impl RowRenderer {
+    pub fn new(mode: RenderMode) -> Self {
+        Self {
+            mode,
+            ..Default::default()
+        }
+    }
+
+    pub fn render_row(&self, row: &DataCellRow) -> String {
+        self.render_rows(std::slice::from_ref(row))
+    }
+
+    pub fn render_rows(&self, rows: &[DataCellRow]) -> String {
+        match self.mode {
+            RenderMode::Table => self.render_table(rows),
+            RenderMode::Record => self.render_records(rows),
+        }
+    }
+
+    fn truncate(&self, s: String) -> String {
+        // we want everything on one line
+        let s = s.replace('\n', "\\n");
+        if self.max_value_width == 0 || s.chars().count() <= self.max_value_width {
+            s
+        } else {
+            let mut t: String = s.chars().take(self.max_value_width - 1).collect();
+            t.push('…');
+            t
+        }
+    }
+
+    fn value_str(&self, val: &Value) -> String {
+        if val.is_none() {
+            self.none_repr.clone()
+        } else {
+            self.truncate(value_to_string(val))
+        }
+    }
+
+    fn render_table(&self, rows: &[DataCellRow]) -> String {
+        // The "columns" are the union of all idx in all rows. Header and type are taken from the first
+        // row that has a cell with the respective idx.
+        let mut columns: BTreeMap<usize, (String, String)> = BTreeMap::new();
+        for row in rows {
+            for dc in row {
+                columns.entry(dc.get_idx()).or_insert_with(|| {
+                    (
+                        self.truncate(String::from(dc.get_name())),
+                        dc.get_type_info().to_string(),
+                    )
+                });
+            }
+        }
+
+        let mut header: Vec<Vec<String>> = Vec::with_capacity(3);
+        if self.show_idx {
+            header.push(columns.keys().map(|idx| idx.to_string()).collect());
+        }
+        header.push(columns.values().map(|(name, _)| name.clone()).collect());
+        if self.show_types {
+            header.push(columns.values().map(|(_, dtype)| dtype.clone()).collect());
+        }
+
+        let body: Vec<Vec<String>> = rows
+            .iter()
+            .map(|row| {
+                columns
+                    .keys()
+                    .map(|idx| {
+                        row.get_by_idx(*idx)
+                            .map(|dc| self.value_str(dc.get_data()))
+                            .unwrap_or_default()
+                    })
+                    .collect()
+            })
+            .collect();
+
+        let widths: Vec<usize> = (0..columns.len())
+            .map(|i| {
+                header
+                    .iter()
+                    .chain(body.iter())
+                    .map(|line| line[i].chars().count())
+                    .max()
+                    .unwrap_or(0)
+            })
+            .collect();
+
+        let separator = |c: char| -> String {
+            let mut s = String::from("+");
+            for w in &widths {
+                s.push_str(&c.to_string().repeat(w + 2));
+                s.push('+');
+            }
+            s
+        };
+        let line = |cells: &Vec<String>| -> String {
+            let mut s = String::from("|");
+            for (cell, w) in cells.iter().zip(widths.iter()) {
+                s.push_str(&format!(" {:<width$} |", cell, width = w));
+            }
+            s
+        };
+
+        let mut out: Vec<String> = Vec::with_capacity(header.len() + body.len() + 3);
+        out.push(separator('-'));
+        out.extend(header.iter().map(line));
+        out.push(separator('='));
+        out.extend(body.iter().map(line));
+        if !body.is_empty() {
+            out.push(separator('-'));
+        }
+        out.join("\n")
+    }
+
+    fn render_records(&self, rows: &[DataCellRow]) -> String {
+        let mut out: Vec<String> = Vec::new();
+        for (num, row) in rows.iter().enumerate() {
+            let mut sorted: Vec<&DataCell> = row.into_iter().collect();
+            sorted.sort_by_key(|dc| dc.get_idx());
+            let cells: Vec<[String; 4]> = sorted
+                .into_iter()
+                .map(|dc| {
+                    [
+                        dc.get_idx().to_string(),
+                        self.truncate(String::from(dc.get_name())),
+                        dc.get_type_info().to_string(),
+                        self.value_str(dc.get_data()),
+                    ]
+                })
+                .collect();
+
+            let mut widths = [0_usize; 4];
+            for c in &cells {
+                for (i, s) in c.iter().enumerate() {
+                    widths[i] = widths[i].max(s.chars().count());
+                }
+            }
+
+            let lines: Vec<String> = cells
+                .iter()
+                .map(|c| {
+                    let mut parts: Vec<String> = Vec::with_capacity(4);
+                    if self.show_idx {
+                        parts.push(format!("{:>width$}", c[0], width = widths[0]));
+                    }
+                    parts.push(format!("{:<width$}", c[1], width = widths[1]));
+                    if self.show_types {
+                        parts.push(format!("{:<width$}", c[2], width = widths[2]));
+                    }
+                    parts.push(c[3].clone());
+                    parts.join(" | ")
+                })
+                .collect();
+
+            let mut title = format!("-[ RECORD {} ]", num + 1);
+            let total_width = lines.iter().map(|l| l.chars().count()).max().unwrap_or(0);
+            while title.chars().count() < total_width {
+                title.push('-');
+            }
+            out.push(title);
+            out.extend(lines);
+        }
+        out.join("\n")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use venum::value::Value;
+    use venum::value_type::ValueType;
+
+    use super::*;
+    use crate::data_cell::DataCell;
+
+    fn row() -> DataCellRow {
+        let mut row = DataCellRow::new();
+        row.push(
+            DataCell::new(
+                String::from("currency"),
+                1,
+                Value::String(String::from("CHF")),
+            )
+            .unwrap(),
+        );
+        row.push(DataCell::new(String::from("amount"), 0, Value::Int32(10)).unwrap());
+        row
+    }
+
+    #[test]
+    fn render_table() {
+        let exp = "\
++--------+----------+
+| 0      | 1        |
+| amount | currency |
+| Int32  | String   |
++========+==========+
+| 10     | CHF      |
++--------+----------+";
+        assert_eq!(exp, RowRenderer::default().render_row(&row()));
+        assert_eq!(exp, format!("{}", row()));
+    }
+
+    #[test]
+    fn render_table_missing_cells_and_none() {
+        let mut row2 = DataCellRow::new();
+        row2.push(DataCell::new_without_data(
+            ValueType::Int32,
+            String::from("amount"),
+            0,
+        ));
+
+        let exp = "\
++--------+----------+
+| amount | currency |
++========+==========+
+| 10     | CHF      |
+| None   |          |
++--------+----------+";
+        let renderer = RowRenderer {
+            show_idx: false,
+            show_types: false,
+            ..Default::default()
+        };
+        assert_eq!(exp, renderer.render_rows(&[row(), row2]));
+    }
+
+    #[test]
+    fn render_table_truncates() {
+        let mut row = DataCellRow::new();
+        row.push(
+            DataCell::new(
+                String::from("c"),
+                0,
+                Value::String(String::from("abcdefgh")),
+            )
+            .unwrap(),
+        );
+        let renderer = RowRenderer {
+            max_value_width: 5,
+            show_idx: false,
+            show_types: false,
+            ..Default::default()
+        };
+        assert!(renderer.render_row(&row).contains("| abcd… |"));
+    }
+
+    #[test]
+    fn render_records() {
+        let exp = "\
+-[ RECORD 1 ]--------------
+0 | amount   | Int32  | 10
+1 | currency | String | CHF";
+        assert_eq!(exp, RowRenderer::new(RenderMode::Record).render_row(&row()));
+    }
+}
@@ -0,0 +1,485 @@
+use std::collections::HashSet;
+use std::fmt::{Display, Formatter};
+
+use venum::value_type::ValueType;
+
+use crate::{
+    constraints::{ColumnRules, RuleConfig},
+    data_cell_row::DataCellRow,
+    errors::Result,
+    value_fmt::value_to_string,
+};
+
+/// The expected shape of a single column.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "jsonconf",
+    derive(serde::Deserialize, serde::Serialize),
+    serde(rename_all = "camelCase")
+)]
+#[cfg_attr(feature = "jsonschema", derive(schemars::JsonSchema))]
+pub struct SchemaColumn {
+    pub idx: usize,
+    /// If given, the header must match.
+    #[cfg_attr(feature = "jsonconf", serde(skip_serializing_if = "Option::is_none"))]
+    pub name: Option<String>,
+    #[cfg_attr(feature = "jsonconf", serde(rename = "type"))]
+    #[cfg_attr(
+        feature = "jsonschema",
+        schemars(with = "crate::conf::json_schema::ValueTypeSchema")
+    )]
+    pub dtype: ValueType,
+    /// Whether the value may be `None`. (We default to `true`.)
+    #[cfg_attr(feature = "jsonconf", serde(default = "default_nullable"))]
+    pub nullable: bool,
+    /// Rules the value has to obey (see `validateItems`). Range bounds and allowed values are typed
+    /// via `dtype`.
+    #[cfg_attr(
+        feature = "jsonconf",
+        serde(default, skip_serializing_if = "Vec::is_empty")
+    )]
+    pub constraints: Vec<RuleConfig>,
+}
+
+#[cfg(feature = "jsonconf")]
+fn default_nullable() -> bool {
+    true
+}
+
+impl SchemaColumn {
+    pub fn new(idx: usize, name: Option<String>, dtype: ValueType, nullable: bool) -> Self {
+        Self {
+            idx,
+            name,
+            dtype,
+            nullable,
+            constraints: Vec::new(),
+        }
+    }
+
+    pub fn with_constraints(mut self, constraints: Vec<RuleConfig>) -> Self {
+        self.constraints = constraints;
+        self
+    }
+}
+
+/// The expected shape of a row. Columns are identified by their idx.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+#[cfg_attr(
+    feature = "jsonconf",
+    derive(serde::Deserialize, serde::Serialize),
+    serde(rename_all = "camelCase")
+)]
+#[cfg_attr(feature = "jsonschema", derive(schemars::JsonSchema))]
+pub struct Schema {
+    pub columns: Vec<SchemaColumn>,
+    /// Whether the row may have columns, that are not part of the schema. (We default to `false`.)
+    #[cfg_attr(feature = "jsonconf", serde(default))]
+    pub allow_extra: bool,
+}
+
+impl Schema {
+    pub fn new(columns: Vec<SchemaColumn>) -> Self {
+        Self {
+            columns,
+            allow_extra: false,
+        }
+    }
+
+    /// Derives a schema from a row, i.e. the given row is valid against it. All columns are
+    /// nullable.
+    pub fn from_row(row: &DataCellRow) -> Self {
+        Self::new(
+            row.iter_sorted()
+                .map(|dc| {
+                    SchemaColumn::new(
+                        dc.get_idx(),
+                        Some(String::from(dc.get_name())),
+                        dc.get_type_info().clone(),
+                        true,
+                    )
+                })
+                .collect(),
+        )
+    }
+
+    /// The constraints of the columns, typed via the column types. Fails, if a constraint is
+    /// invalid, e.g. a bad pattern or a bound that is not of the column's type.
+    pub fn rules(&self) -> Result<Vec<ColumnRules>> {
+        self.columns
+            .iter()
+            .filter(|col| !col.constraints.is_empty())
+            .map(|col| {
+                let rules = col
+                    .constraints
+                    .iter()
+                    .map(|rc| rc.to_rule(col.idx, Some(&col.dtype)))
+                    .collect::<Result<_>>()?;
+                Ok(ColumnRules {
+                    idx: col.idx,
+                    rules,
+                })
+            })
+            .collect()
+    }
+
+    /// Checks the row against the schema and returns all violations, in column order. An empty
+    /// result means the row is valid. The constraints are typed on every call, to check many rows,
+    /// type them once (see `rules`) and use `validate_with_rules`.
+    pub fn validate(&self, row: &DataCellRow) -> Vec<SchemaViolation> {
+        match self.rules() {
+            Ok(rules) => self.validate_with_rules(row, &rules),
+            Err(e) => vec![SchemaViolation::InvalidConstraints {
+                msg: format!("{:?}", e),
+            }],
+        }
+    }
+
+    /// Same as `validate`, with the constraints already typed, see `rules`.
+    pub fn validate_with_rules(
+        &self,
+        row: &DataCellRow,
+        rules: &[ColumnRules],
+    ) -> Vec<SchemaViolation> {
+        let mut violations: Vec<SchemaViolation> = Vec::new();
+        let mut seen: HashSet<usize> = HashSet::with_capacity(row.len());
+
+        for dc in row.iter_sorted() {
+            let idx = dc.get_idx();
+            if !seen.insert(idx) {
+                violations.push(SchemaViolation::DuplicateIdx { idx });
+                continue;
+            }
+
+            // the type info and the actual value must agree, no matter the schema
+            if let Some(value_type) = value_type_of(dc.get_data()) {
+                if &value_type != dc.get_type_info() {
+                    violations.push(SchemaViolation::ValueTypeMismatch {
+                        idx,
+                        dtype: dc.get_type_info().clone(),
+                        value_type,
+                    });
+                }
+            }
+
+            match self.columns.iter().find(|col| col.idx == idx) {
+                None => {
+                    if !self.allow_extra {
+                        violations.push(SchemaViolation::UnexpectedColumn {
+                            idx,
+                            name: String::from(dc.get_name()),
+                        });
+                    }
+                }
+                Some(col) => {
+                    if let Some(name) = &col.name {
+                        if name != dc.get_name() {
+                            violations.push(SchemaViolation::NameMismatch {
+                                idx,
+                                expected: name.clone(),
+                                actual: String::from(dc.get_name()),
+                            });
+                        }
+                    }
+                    if &col.dtype != dc.get_type_info() {
+                        violations.push(SchemaViolation::TypeMismatch {
+                            idx,
+                            expected: col.dtype.clone(),
+                            actual: dc.get_type_info().clone(),
+                        });
+                    }
+                    if !col.nullable && dc.get_data().is_none() {
+                        violations.push(SchemaViolation::UnexpectedNone { idx });
+                    }
+                    let broken = rules
+                        .iter()
+                        .filter(|cr| cr.idx == idx)
+                        .flat_map(|cr| &cr.rules)
+                        .filter(|rule| rule.is_broken_by(dc.get_data()));
+                    for rule in broken {
+                        violations.push(SchemaViolation::BrokenConstraint {
+                            idx,
+                            rule: rule.to_string(),
+                            value: value_to_string(dc.get_data()),
+                        });
+                    }
+                }
+            }
+        }
+
+        for col in &self.columns {
+            if !seen.contains(&col.idx) {
+                violations.push(SchemaViolation::MissingColumn {
+                    idx: col.idx,
+                    name: col.name.clone(),
+                });
+            }
+        }
+        violations
+    }
+}
+
+fn value_type_of(val: &venum::value::Value) -> Option<ValueType> {
+    if val.is_none() {
+        None
+    } else {
+        ValueType::try_from(val).ok()
+    }
+}
+
+/// A single way in which a row does not match a `Schema`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SchemaViolation {
+    MissingColumn {
+        idx: usize,
+        name: Option<String>,
+    },
+    UnexpectedColumn {
+        idx: usize,
+        name: String,
+    },
+    DuplicateIdx {
+        idx: usize,
+    },
+    NameMismatch {
+        idx: usize,
+        expected: String,
+        actual: String,
+    },
+    TypeMismatch {
+        idx: usize,
+        expected: ValueType,
+        actual: ValueType,
+    },
+    /// The value of the cell doesn't match the cell's own type info.
+    ValueTypeMismatch {
+        idx: usize,
+        dtype: ValueType,
+        value_type: ValueType,
+    },
+    UnexpectedNone {
+        idx: usize,
+    },
+    /// The value breaks one of the column's constraints.
+    BrokenConstraint {
+        idx: usize,
+        rule: String,
+        value: String,
+    },
+    /// The constraints of the schema itself are invalid, see `Schema::rules`.
+    InvalidConstraints {
+        msg: String,
+    },
+}
+
+impl Display for SchemaViolation {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SchemaViolation::MissingColumn { idx, name } => match name {
+                Some(n) => write!(f, "[{}] missing column {:?}", idx, n),
+                None => write!(f, "[{}] missing column", idx),
+            },
+            SchemaViolation::UnexpectedColumn { idx, name } => {
+                write!(f, "[{}] unexpected column {:?}", idx, name)
+            }
+            SchemaViolation::DuplicateIdx { idx } => write!(f, "[{}] idx used more than once", idx),
+            SchemaViolation::NameMismatch {
+                idx,
+                expected,
+                actual,
+            } => write!(
+                f,
+                "[{}] expected name {:?}, got {:?}",
+                idx, expected, actual
+            ),
+            SchemaViolation::TypeMismatch {
+                idx,
+                expected,
+                actual,
+            } => write!(f, "[{}] expected type {}, got {}", idx, expected, actual),
+            SchemaViolation::ValueTypeMismatch {
+                idx,
+                dtype,
+                value_type,
+            } => write!(
+                f,
+                "[{}] value of type {} doesn't match type info {}",
+                idx, value_type, dtype
+            ),
+            SchemaViolation::UnexpectedNone { idx } => write!(f, "[{}] value is None", idx),
+            SchemaViolation::BrokenConstraint { idx, rule, value } => {
+                write!(f, "[{}] breaks {}: {:?}", idx, rule, value)
+            }
+            SchemaViolation::InvalidConstraints { msg } => {
+                write!(f, "invalid constraints: {}", msg)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use venum::value::Value;
+
+    use super::*;
+    use crate::data_cell::DataCell;
+
+    fn row() -> DataCellRow {
+        let mut row = DataCellRow::new();
+        row.push(DataCell::new(String::from("amount"), 0, Value::Float32(10.1)).unwrap());
+        row.push(DataCell::new_without_data(
+            ValueType::String,
+            String::from("currency"),
+            1,
+        ));
+        row
+    }
+
+    #[test]
+    fn valid_row() {
+        let row = row();
+        assert!(row.validate(&Schema::from_row(&row)).is_empty());
+    }
+
+    #[test]
+    fn all_violations() {
+        let mut row = row();
+        row.push(DataCell::new(String::from("extra"), 5, Value::Bool(true)).unwrap());
+        // breaks the cell, behind the back of its type info
+        row.get_by_idx_mut(5).unwrap().set_data(Value::Int8(1));
+
+        let schema = Schema::new(vec![
+            SchemaColumn::new(0, Some(String::from("amount")), ValueType::Float64, true),
+            SchemaColumn::new(1, Some(String::from("ccy")), ValueType::String, false),
+            SchemaColumn::new(2, None, ValueType::Bool, true),
+        ]);
+
+        assert_eq!(
+            vec![
+                SchemaViolation::TypeMismatch {
+                    idx: 0,
+                    expected: ValueType::Float64,
+                    actual: ValueType::Float32
+                },
+                SchemaViolation::NameMismatch {
+                    idx: 1,
+                    expected: String::from("ccy"),
+                    actual: String::from("currency")
+                },
+                SchemaViolation::UnexpectedNone { idx: 1 },
+                SchemaViolation::ValueTypeMismatch {
+                    idx: 5,
+                    dtype: ValueType::Bool,
+                    value_type: ValueType::Int8
+                },
+                SchemaViolation::UnexpectedColumn {
+                    idx: 5,
+                    name: String::from("extra")
+                },
+                SchemaViolation::MissingColumn { idx: 2, name: None },
+            ],
+            row.validate(&schema)
+        );
+    }
+
+    #[test]
+    fn constraints() {
+        let mut row = row();
+        row.push(
+            DataCell::new(String::from("code"), 2, Value::String(String::from("x1"))).unwrap(),
+        );
+
+        let schema = Schema::new(vec![
+            SchemaColumn::new(0, None, ValueType::Float32, false).with_constraints(vec![
+                RuleConfig::Range {
+                    min: Some(String::from("0")),
+                    max: Some(String::from("10")),
+                },
+            ]),
+            SchemaColumn::new(1, None, ValueType::String, true)
+                .with_constraints(vec![RuleConfig::NotNull]),
+            SchemaColumn::new(2, None, ValueType::String, true).with_constraints(vec![
+                RuleConfig::Pattern {
+                    pattern: String::from("^[a-z]+$"),
+                },
+                RuleConfig::Length {
+                    min: None,
+                    max: Some(2),
+                },
+            ]),
+        ]);
+
+        // the constraints show up with the other violations, in column order
+        row.get_by_idx_mut(0)
+            .unwrap()
+            .set_data(Value::Float32(10.5));
+        assert_eq!(
+            vec![
+                SchemaViolation::BrokenConstraint {
+                    idx: 0,
+                    rule: String::from("range [0, 10]"),
+                    value: String::from("10.5")
+                },
+                SchemaViolation::BrokenConstraint {
+                    idx: 1,
+                    rule: String::from("notNull"),
+                    value: String::new()
+                },
+                SchemaViolation::BrokenConstraint {
+                    idx: 2,
+                    rule: String::from("pattern \"^[a-z]+$\""),
+                    value: String::from("x1")
+                },
+            ],
+            row.validate(&schema)
+        );
+        assert_eq!(
+            row.validate(&schema),
+            schema.validate_with_rules(&row, &schema.rules().unwrap())
+        );
+
+        // a bound must be of the column's type
+        let schema = Schema::new(vec![SchemaColumn::new(0, None, ValueType::Float32, true)
+            .with_constraints(vec![RuleConfig::OneOf {
+                values: vec![String::from("abc")],
+            }])]);
+        assert!(schema.rules().is_err());
+        assert!(matches!(
+            row.validate(&schema)[..],
+            [SchemaViolation::InvalidConstraints { .. }]
+        ));
+    }
+
+    #[cfg(feature = "jsonconf")]
+    #[test]
+    fn schema_from_json() {
+        let data = r#"
+        {
+            "columns": [
+                { "idx": 0, "name": "amount", "type": "Float32", "nullable": false },
+                {
+                    "idx": 1,
+                    "type": "String",
+                    "constraints": [{ "name": "length", "min": 3, "max": 3 }]
+                }
+            ],
+            "allowExtra": true
+        }
+        "#;
+        assert_eq!(
+            Schema {
+                columns: vec![
+                    SchemaColumn::new(0, Some(String::from("amount")), ValueType::Float32, false),
+                    SchemaColumn::new(1, None, ValueType::String, true).with_constraints(vec![
+                        RuleConfig::Length {
+                            min: Some(3),
+                            max: Some(3)
+                        }
+                    ]),
+                ],
+                allow_extra: true,
+            },
+            serde_json::from_str(data).unwrap()
+        );
+    }
+}
@@ -3,12 +3,21 @@ use std::fmt::Debug;
 use venum::value::Value;
 use venum::value_type::ValueType;
 
+#[cfg(feature = "jsonconf")]
+use crate::conf::jsonconf::SplitItemConfig;
 use crate::data_cell::DataCell;
 use crate::errors::{Result, SplitError, TransformErrors, VenumTdsError};
 use crate::transform::value::spliting::ValueSplit;
 
 pub trait SplitDataCell: Debug {
     fn split(&self, item: &DataCell) -> Result<(DataCell, DataCell)>;
+
+    /// The `splitItem` config for splitting the item with the given idx like this, if it can be
+    /// written as one.
+    #[cfg(feature = "jsonconf")]
+    fn to_config(&self, _idx: usize, _delete_after_split: bool) -> Option<SplitItemConfig> {
+        None
+    }
 }
 
 // TODO use implementations in venum!!!
@@ -61,6 +70,17 @@ where
 
         Ok((ctl, ctr))
     }
+
+    #[cfg(feature = "jsonconf")]
+    fn to_config(&self, idx: usize, delete_after_split: bool) -> Option<SplitItemConfig> {
+        Some(SplitItemConfig {
+            idx,
+            spec: self.splitter.to_config()?,
+            delete_after_split,
+            target_left: (&self.target_left).into(),
+            target_right: (&self.target_right).into(),
+        })
+    }
 }
 
 #[cfg(test)]
@@ -0,0 +1,116 @@
+use regex::Regex;
+use venum::value::Value;
+
+use crate::{
+    data_cell_row::DataCellRow,
+    errors::{Result, TransformErrors, VenumTdsError},
+    value_fmt::value_to_string,
+};
+
+/// A condition on a row. Values are compared by their stringified form, so they don't need to be
+/// typed. A missing column counts as `None`.
+#[derive(Debug, Clone)]
+pub enum RowCondition {
+    Equals {
+        idx: usize,
+        value: String,
+    },
+    OneOf {
+        idx: usize,
+        values: Vec<String>,
+    },
+    /// The stringified value matches the pattern. `None` never matches.
+    Matches {
+        idx: usize,
+        pattern: Regex,
+    },
+    IsNone {
+        idx: usize,
+    },
+    All(Vec<RowCondition>),
+    Any(Vec<RowCondition>),
+    Not(Box<RowCondition>),
+}
+
+fn value_of(row: &DataCellRow, idx: usize) -> &Value {
+    row.get_by_idx(idx)
+        .map(|dc| dc.get_data())
+        .unwrap_or(&Value::None)
+}
+
+impl RowCondition {
+    pub fn matches(idx: usize, pattern: &str) -> Result<Self> {
+        Regex::new(pattern)
+            .map(|re| RowCondition::Matches { idx, pattern: re })
+            .map_err(|e| {
+                VenumTdsError::Transform(TransformErrors::Generic {
+                    msg: format!("invalid pattern {:?}: {}", pattern, e),
+                })
+            })
+    }
+
+    pub fn holds_for(&self, row: &DataCellRow) -> bool {
+        match self {
+            RowCondition::Equals { idx, value } => {
+                let val = value_of(row, *idx);
+                val.is_some() && value_to_string(val) == *value
+            }
+            RowCondition::OneOf { idx, values } => {
+                let val = value_of(row, *idx);
+                val.is_some() && values.contains(&value_to_string(val))
+            }
+            RowCondition::Matches { idx, pattern } => {
+                let val = value_of(row, *idx);
+                val.is_some() && pattern.is_match(&value_to_string(val))
+            }
+            RowCondition::IsNone { idx } => value_of(row, *idx).is_none(),
+            RowCondition::All(conditions) => conditions.iter().all(|c| c.holds_for(row)),
+            RowCondition::Any(conditions) => conditions.iter().any(|c| c.holds_for(row)),
+            RowCondition::Not(condition) => !condition.holds_for(row),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data_cell::DataCell;
+
+    #[test]
+    fn conditions() {
+        let mut row = DataCellRow::new();
+        row.push(DataCell::new(String::from("type"), 0, Value::String(String::from("D"))).unwrap());
+        row.push(DataCell::new(String::from("amount"), 1, Value::Int32(10)).unwrap());
+
+        let is_detail = RowCondition::Equals {
+            idx: 0,
+            value: String::from("D"),
+        };
+        assert!(is_detail.holds_for(&row));
+        assert!(RowCondition::Equals {
+            idx: 1,
+            value: String::from("10")
+        }
+        .holds_for(&row));
+        assert!(RowCondition::matches(1, "^1").unwrap().holds_for(&row));
+        assert!(RowCondition::IsNone { idx: 2 }.holds_for(&row));
+        assert!(!RowCondition::All(vec![
+            is_detail.clone(),
+            RowCondition::Not(Box::new(RowCondition::OneOf {
+                idx: 0,
+                values: vec![String::from("D"), String::from("H")]
+            }))
+        ])
+        .holds_for(&row));
+        assert!(
+            RowCondition::Any(vec![RowCondition::IsNone { idx: 0 }, is_detail]).holds_for(&row)
+        );
+
+        // None is neither equal to, nor one of anything, not even the empty string
+        assert!(!RowCondition::Equals {
+            idx: 2,
+            value: String::new()
+        }
+        .holds_for(&row));
+    }
+}
@@ -1,3 +1,7 @@
+pub mod condition;
+pub mod transrich_explode;
 pub mod transrich_inplace;
 pub mod transrich_inplace_stateful;
+pub mod transrich_iter;
 pub mod transrich_pass;
+pub mod trace;
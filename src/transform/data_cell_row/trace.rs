@@ -0,0 +1,518 @@
+use std::{
+    collections::BTreeMap,
+    fmt::{Display, Formatter},
+};
+
+use venum::value::Value;
+use venum::value_type::ValueType;
+
+use crate::{
+    data_cell::DataCell, data_cell_row::DataCellRow, errors::Result, render::RowRenderer,
+    value_fmt::value_to_string,
+};
+
+/// A single change of a row, between two states of it. Cells are identified by their idx.
+#[derive(Debug, Clone, PartialEq)]
+pub enum CellChange {
+    Added(DataCell),
+    Removed(DataCell),
+    /// The cell is unchanged, except for its idx.
+    Reindexed {
+        from: usize,
+        to: usize,
+        name: String,
+    },
+    Renamed {
+        idx: usize,
+        from: String,
+        to: String,
+    },
+    TypeChanged {
+        idx: usize,
+        from: ValueType,
+        to: ValueType,
+    },
+    ValueChanged {
+        idx: usize,
+        from: Value,
+        to: Value,
+    },
+}
+
+fn fmt_cell(dc: &DataCell) -> String {
+    format!(
+        "[{}] {} ({}) = {}",
+        dc.get_idx(),
+        dc.get_name(),
+        dc.get_type_info(),
+        fmt_value(dc.get_data())
+    )
+}
+
+fn fmt_value(val: &Value) -> String {
+    if val.is_none() {
+        String::from("None")
+    } else {
+        format!("{:?}", value_to_string(val))
+    }
+}
+
+impl Display for CellChange {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CellChange::Added(dc) => write!(f, "+ {}", fmt_cell(dc)),
+            CellChange::Removed(dc) => write!(f, "- {}", fmt_cell(dc)),
+            CellChange::Reindexed { from, to, name } => {
+                write!(f, "> [{} -> {}] {}", from, to, name)
+            }
+            CellChange::Renamed { idx, from, to } => {
+                write!(f, "= [{}] renamed {:?} -> {:?}", idx, from, to)
+            }
+            CellChange::TypeChanged { idx, from, to } => {
+                write!(f, "~ [{}] type {} -> {}", idx, from, to)
+            }
+            CellChange::ValueChanged { idx, from, to } => {
+                write!(f, "* [{}] {} -> {}", idx, fmt_value(from), fmt_value(to))
+            }
+        }
+    }
+}
+
+fn first_by_idx(row: &DataCellRow) -> BTreeMap<usize, &DataCell> {
+    let mut m: BTreeMap<usize, &DataCell> = BTreeMap::new();
+    for dc in row {
+        m.entry(dc.get_idx()).or_insert(dc);
+    }
+    m
+}
+
+/// Computes the changes between two states of a row. Cells are matched by idx. A cell that vanished
+/// from one idx and showed up (unchanged) at another idx is reported as `Reindexed`.
+pub fn diff_rows(before: &DataCellRow, after: &DataCellRow) -> Vec<CellChange> {
+    let before = first_by_idx(before);
+    let after = first_by_idx(after);
+
+    let mut changes: Vec<CellChange> = Vec::new();
+    let mut removed: Vec<&DataCell> = Vec::new();
+    for (idx, b) in &before {
+        match after.get(idx) {
+            None => removed.push(*b),
+            Some(a) => {
+                if a.get_name() != b.get_name() {
+                    changes.push(CellChange::Renamed {
+                        idx: *idx,
+                        from: String::from(b.get_name()),
+                        to: String::from(a.get_name()),
+                    });
+                }
+                if a.get_type_info() != b.get_type_info() {
+                    changes.push(CellChange::TypeChanged {
+                        idx: *idx,
+                        from: b.get_type_info().clone(),
+                        to: a.get_type_info().clone(),
+                    });
+                }
+                if a.get_data() != b.get_data() {
+                    changes.push(CellChange::ValueChanged {
+                        idx: *idx,
+                        from: b.get_data().clone(),
+                        to: a.get_data().clone(),
+                    });
+                }
+            }
+        }
+    }
+
+    let mut added: Vec<&DataCell> = after
+        .iter()
+        .filter(|(idx, _)| !before.contains_key(*idx))
+        .map(|(_, a)| *a)
+        .collect();
+
+    for r in removed {
+        let same_cell = added.iter().position(|a| {
+            a.get_name() == r.get_name()
+                && a.get_type_info() == r.get_type_info()
+                && a.get_data() == r.get_data()
+        });
+        match same_cell {
+            Some(pos) => {
+                let a = added.remove(pos);
+                changes.push(CellChange::Reindexed {
+                    from: r.get_idx(),
+                    to: a.get_idx(),
+                    name: String::from(a.get_name()),
+                });
+            }
+            None => changes.push(CellChange::Removed(r.clone())),
+        }
+    }
+    changes.extend(added.into_iter().map(|a| CellChange::Added(a.clone())));
+    changes
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TransrichStage {
+    Stateless,
+    Stateful,
+    /// Only the first resulting row is traced further.
+    Exploding,
+    Ordering,
+}
+
+/// How many rows a single transricher left, i.e. whether we can follow the row any further.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StepOutcome {
+    /// Still the one row, (possibly) changed.
+    Transriched,
+    /// No rows left, the trace ends here.
+    Filtered,
+    /// More than one row (their number), only the first one is traced further.
+    Exploded(usize),
+}
+
+/// What a single transricher did to the row.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TransrichStep {
+    pub stage: TransrichStage,
+    /// The description of the transricher (see `Description`).
+    pub transricher: String,
+    pub outcome: StepOutcome,
+    pub changes: Vec<CellChange>,
+    /// The error, if the transricher failed.
+    pub error: Option<String>,
+    /// The state of the row after this step.
+    pub row: DataCellRow,
+}
+
+/// What a single `TransrichPass` did to the row, step by step.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct PassTrace {
+    input: DataCellRow,
+    pub steps: Vec<TransrichStep>,
+    /// The net changes of the whole pass.
+    pub changes: Vec<CellChange>,
+}
+
+impl PassTrace {
+    pub fn new(input: &DataCellRow) -> Self {
+        Self {
+            input: input.clone(),
+            steps: Vec::new(),
+            changes: Vec::new(),
+        }
+    }
+
+    pub(crate) fn trace_step<F>(
+        &mut self,
+        stage: TransrichStage,
+        transricher: String,
+        row: &mut DataCellRow,
+        f: F,
+    ) -> Result<()>
+    where
+        F: FnOnce(&mut DataCellRow) -> Result<()>,
+    {
+        self.trace_step_with_outcome(stage, transricher, row, |c| {
+            f(c).map(|()| StepOutcome::Transriched)
+        })
+        .map(|_| ())
+    }
+
+    /// Same as `trace_step`, but the step tells how many rows it left (see `StepOutcome`).
+    pub(crate) fn trace_step_with_outcome<F>(
+        &mut self,
+        stage: TransrichStage,
+        transricher: String,
+        row: &mut DataCellRow,
+        f: F,
+    ) -> Result<StepOutcome>
+    where
+        F: FnOnce(&mut DataCellRow) -> Result<StepOutcome>,
+    {
+        let before = row.clone();
+        let res = f(row);
+        self.steps.push(TransrichStep {
+            stage,
+            transricher,
+            outcome: *res.as_ref().unwrap_or(&StepOutcome::Transriched),
+            changes: diff_rows(&before, row),
+            error: res.as_ref().err().map(|e| format!("{:?}", e)),
+            row: row.clone(),
+        });
+        res
+    }
+
+    pub(crate) fn finish(&mut self, row: &DataCellRow) {
+        self.changes = diff_rows(&self.input, row);
+    }
+
+    /// Whether a step filtered the row out, i.e. the trace ended there.
+    pub fn is_filtered(&self) -> bool {
+        self.steps
+            .iter()
+            .any(|s| s.outcome == StepOutcome::Filtered)
+    }
+
+    /// The state of the row after the pass (or after the step that failed).
+    pub fn row_after(&self) -> &DataCellRow {
+        self.steps.last().map(|s| &s.row).unwrap_or(&self.input)
+    }
+}
+
+/// The full trace of a row through `TransrichPasses`. Its `Display` is a readable diff of every
+/// step of every pass.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct TransrichTrace {
+    pub input: DataCellRow,
+    pub passes: Vec<PassTrace>,
+}
+
+impl TransrichTrace {
+    pub fn new(input: &DataCellRow) -> Self {
+        Self {
+            input: input.clone(),
+            passes: Vec::new(),
+        }
+    }
+}
+
+impl Display for TransrichTrace {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let renderer = RowRenderer::default();
+        writeln!(f, "=== input ===")?;
+        writeln!(f, "{}", renderer.render_row(&self.input))?;
+
+        for (pass_num, pass) in self.passes.iter().enumerate() {
+            writeln!(f, "=== pass {} ===", pass_num + 1)?;
+            for (step_num, step) in pass.steps.iter().enumerate() {
+                writeln!(
+                    f,
+                    "[{} {:?}] {}",
+                    step_num + 1,
+                    step.stage,
+                    step.transricher
+                )?;
+                match step.outcome {
+                    StepOutcome::Filtered => writeln!(f, "    (filtered, no rows left)")?,
+                    StepOutcome::Exploded(n) => {
+                        writeln!(f, "    (exploded into {} rows, following the first)", n)?
+                    }
+                    StepOutcome::Transriched => {
+                        if step.changes.is_empty() && step.error.is_none() {
+                            writeln!(f, "    (no changes)")?;
+                        }
+                    }
+                }
+                for change in &step.changes {
+                    writeln!(f, "    {}", change)?;
+                }
+                if let Some(e) = &step.error {
+                    writeln!(f, "    ! FAILED: {}", e)?;
+                }
+            }
+            writeln!(f, "--- net changes of pass {} ---", pass_num + 1)?;
+            for change in &pass.changes {
+                writeln!(f, "    {}", change)?;
+            }
+            writeln!(f, "--- row after pass {} ---", pass_num + 1)?;
+            writeln!(f, "{}", renderer.render_row(pass.row_after()))?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use venum::value::Value;
+    use venum::value_type::ValueType;
+
+    use super::*;
+    use crate::transform::{
+        data_cell::splitting::SplitDataCellUsingValueSplit,
+        data_cell_row::{
+            transrich_explode::ExplodeItem,
+            transrich_inplace::*,
+            transrich_pass::{TransrichPass, TransrichPasses},
+        },
+        data_cell_table::reshape::{Pivot, PivotAggregation},
+        value::spliting::{ValueStringSeparatorCharSplit, ValueStringSeparatorCharTokens},
+    };
+
+    #[test]
+    fn diff_rows_changes() {
+        let mut before = DataCellRow::new();
+        before.push(DataCell::new(String::from("a"), 0, Value::Int32(1)).unwrap());
+        before.push(DataCell::new(String::from("b"), 1, Value::Int32(2)).unwrap());
+        before.push(DataCell::new(String::from("c"), 2, Value::Int32(3)).unwrap());
+
+        let mut after = DataCellRow::new();
+        after.push(DataCell::new(String::from("a"), 0, Value::Int32(10)).unwrap());
+        after.push(DataCell::new(String::from("c"), 5, Value::Int32(3)).unwrap());
+        after.push(DataCell::new(String::from("d"), 6, Value::Bool(true)).unwrap());
+
+        let changes = diff_rows(&before, &after);
+        assert_eq!(
+            vec![
+                CellChange::ValueChanged {
+                    idx: 0,
+                    from: Value::Int32(1),
+                    to: Value::Int32(10)
+                },
+                CellChange::Removed(DataCell::new(String::from("b"), 1, Value::Int32(2)).unwrap()),
+                CellChange::Reindexed {
+                    from: 2,
+                    to: 5,
+                    name: String::from("c")
+                },
+                CellChange::Added(DataCell::new(String::from("d"), 6, Value::Bool(true)).unwrap()),
+            ],
+            changes
+        );
+    }
+
+    #[test]
+    fn trace_passes() {
+        let trp = TransrichPass::new(
+            vec![Box::new(SplitItemAtIdx {
+                delete_source_item: true,
+                idx: 0,
+                splitter: SplitDataCellUsingValueSplit {
+                    splitter: ValueStringSeparatorCharSplit {
+                        sep_char: ' ',
+                        split_none: true,
+                    },
+                    target_left: DataCell::new_without_data(
+                        ValueType::Float32,
+                        String::from("amount"),
+                        1,
+                    ),
+                    target_right: DataCell::new_without_data(
+                        ValueType::String,
+                        String::from("currency"),
+                        2,
+                    ),
+                },
+            })],
+            Vec::new(),
+            Some(vec![
+                Box::new(MutateItemIdx { from: 1, to: 0 }),
+                Box::new(MutateItemIdx { from: 2, to: 1 }),
+            ]),
+        );
+        let mut passes = TransrichPasses(vec![trp]);
+
+        let mut data = DataCellRow::new();
+        data.push(
+            DataCell::new(
+                String::from("amount+currency"),
+                0,
+                Value::String(String::from("10.10 CHF")),
+            )
+            .unwrap(),
+        );
+
+        let mut trace = TransrichTrace::new(&data);
+        passes.transrich_traced(&mut data, &mut trace).unwrap();
+
+        assert_eq!(1, trace.passes.len());
+        let pass = &trace.passes[0];
+        assert_eq!(3, pass.steps.len());
+        assert_eq!(TransrichStage::Ordering, pass.steps[2].stage);
+        assert_eq!(
+            vec![CellChange::Reindexed {
+                from: 2,
+                to: 1,
+                name: String::from("currency")
+            }],
+            pass.steps[2].changes
+        );
+        assert_eq!(&data, pass.row_after());
+        assert_eq!(4, pass.changes.len()); // renamed, type and value changed on idx 0, idx 1 added
+        assert!(format!("{}", trace).contains("> [2 -> 1] currency"));
+    }
+
+    #[test]
+    fn trace_passes_error() {
+        let mut passes = TransrichPasses(vec![TransrichPass::new(
+            vec![Box::new(DeleteItemAtIdx(7))],
+            Vec::new(),
+            None,
+        )]);
+
+        let mut data = DataCellRow::new();
+        let mut trace = TransrichTrace::new(&data);
+        assert!(passes.transrich_traced(&mut data, &mut trace).is_err());
+        assert_eq!(
+            Some(String::from("DataAccess(IllegalIdxAccess { idx: 7 })")),
+            trace.passes[0].steps[0].error
+        );
+    }
+
+    fn explode_tags() -> TransrichPass {
+        TransrichPass::new(Vec::new(), Vec::new(), None).with_exploding(vec![Box::new(
+            ExplodeItem {
+                idx: 0,
+                splitter: ValueStringSeparatorCharTokens { sep_char: ',' },
+                target_type: ValueType::String,
+                keep_empty: false,
+            },
+        )])
+    }
+
+    fn tags(tags: &str) -> DataCellRow {
+        let mut data = DataCellRow::new();
+        data.push(
+            DataCell::new(String::from("tags"), 0, Value::String(String::from(tags))).unwrap(),
+        );
+        data
+    }
+
+    #[test]
+    fn trace_passes_exploded() {
+        let mut passes = TransrichPasses(vec![explode_tags(), explode_tags()]);
+
+        let mut data = tags("a, b, c");
+        let mut trace = TransrichTrace::new(&data);
+        passes.transrich_traced(&mut data, &mut trace).unwrap();
+
+        assert_eq!(StepOutcome::Exploded(3), trace.passes[0].steps[0].outcome);
+        assert_eq!(StepOutcome::Transriched, trace.passes[1].steps[0].outcome);
+        assert_eq!(tags("a"), data);
+        assert!(format!("{}", trace).contains("(exploded into 3 rows, following the first)"));
+    }
+
+    #[test]
+    fn trace_passes_filtered() {
+        let mut passes = TransrichPasses(vec![explode_tags(), explode_tags()]);
+
+        let mut data = tags(" , ");
+        let mut trace = TransrichTrace::new(&data);
+        passes.transrich_traced(&mut data, &mut trace).unwrap();
+
+        // the trace ends with the filtering step, the row is left as it was before
+        assert_eq!(1, trace.passes.len());
+        assert!(trace.passes[0].is_filtered());
+        assert_eq!(StepOutcome::Filtered, trace.passes[0].steps[0].outcome);
+        assert_eq!(tags(" , "), data);
+        assert!(format!("{}", trace).contains("(filtered, no rows left)"));
+    }
+
+    #[test]
+    fn trace_table_pass() {
+        let mut passes = TransrichPasses(vec![TransrichPass::new(Vec::new(), Vec::new(), None)
+            .with_table(vec![Box::new(Pivot {
+                id_idxs: Vec::new(),
+                key_idx: 0,
+                value_idx: 0,
+                aggregation: PivotAggregation::First,
+                columns: None,
+                header_prefix: String::new(),
+                start_idx: None,
+            })])]);
+
+        let mut data = tags("a");
+        let mut trace = TransrichTrace::new(&data);
+        assert!(passes.transrich_traced(&mut data, &mut trace).is_err());
+    }
+}
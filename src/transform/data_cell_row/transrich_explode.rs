@@ -0,0 +1,251 @@
+use std::fmt::Debug;
+
+use venum::value::Value;
+use venum::value_type::ValueType;
+
+#[cfg(feature = "jsonconf")]
+use crate::conf::jsonconf::{
+    ExplodeItemConfig, ItemTargetConfig, TransformerConfig, UnpivotConfig,
+};
+use crate::{
+    data_cell::DataCell,
+    data_cell_row::DataCellRow,
+    errors::{DataAccessErrors, Result, VenumTdsError},
+    transform::{description::Description, value::spliting::ValueSplitN},
+};
+
+/// Turns one row into zero, one or many rows. Unlike the other transrichers, this takes the row by
+/// value, since it (usually) ends up in the output, in one form or another.
+pub trait TransrichExplode: Debug {
+    fn explode(&self, data_cell_row: DataCellRow) -> Result<Vec<DataCellRow>>;
+
+    /// What this transricher does, leaving out any state, see `Description`.
+    fn describe(&self) -> Description;
+
+    /// The config this transricher is built from, if it can be written as one, see
+    /// `TransrichPass::to_config`.
+    #[cfg(feature = "jsonconf")]
+    fn to_config(&self) -> Option<TransformerConfig>;
+}
+
+fn illegal_idx(idx: usize) -> VenumTdsError {
+    VenumTdsError::DataAccess(DataAccessErrors::IllegalIdxAccess { idx })
+}
+
+/// Strings are parsed, everything else is converted. `None` stays `None`.
+pub(crate) fn convert_to(val: Value, value_type: &ValueType) -> Result<Value> {
+    match val {
+        Value::None => Ok(Value::None),
+        Value::String(s) => Ok(Value::from_str_and_type(&s, value_type)?),
+        other => Ok(other.try_convert_to(value_type)?),
+    }
+}
+
+/// Splits the value of an item and emits one row per resulting value, i.e. the item of every
+/// emitted row holds one of the values, converted to `target_type`. Everything else is copied.
+#[derive(Debug, PartialEq, Eq)]
+pub struct ExplodeItem<S: ValueSplitN> {
+    pub idx: usize,
+    pub splitter: S,
+    pub target_type: ValueType,
+    /// If the split gives no values at all, emit the row once, with `None` as value, instead of
+    /// dropping it.
+    pub keep_empty: bool,
+}
+
+impl<S> TransrichExplode for ExplodeItem<S>
+where
+    S: ValueSplitN,
+{
+    fn explode(&self, data_cell_row: DataCellRow) -> Result<Vec<DataCellRow>> {
+        let src = data_cell_row
+            .get_by_idx(self.idx)
+            .ok_or_else(|| illegal_idx(self.idx))?;
+        let mut values = self.splitter.split_n(src.get_data())?;
+        if values.is_empty() {
+            if !self.keep_empty {
+                return Ok(Vec::new());
+            }
+            values.push(Value::None);
+        }
+
+        let mut rows: Vec<DataCellRow> = Vec::with_capacity(values.len());
+        for val in values {
+            let mut row = data_cell_row.clone();
+            let dc = row.get_by_idx_mut(self.idx).unwrap(); // we checked it above
+            dc.set_type_info(self.target_type.clone());
+            dc.set_data(convert_to(val, &self.target_type)?);
+            rows.push(row);
+        }
+        Ok(rows)
+    }
+
+    fn describe(&self) -> Description {
+        Description::new("ExplodeItem")
+            .param("idx", self.idx)
+            .param("splitter", &self.splitter)
+            .param("target_type", &self.target_type)
+            .param("keep_empty", self.keep_empty)
+    }
+
+    #[cfg(feature = "jsonconf")]
+    fn to_config(&self) -> Option<TransformerConfig> {
+        Some(TransformerConfig::ExplodeItem {
+            cfg: ExplodeItemConfig {
+                idx: self.idx,
+                sep_char: self.splitter.sep_char()?,
+                target_type: Some(self.target_type.clone()),
+                keep_empty: Some(self.keep_empty),
+            },
+        })
+    }
+}
+
+/// Unpivots (melts) items into rows: every item in `idxs` becomes a row of its own, with its header
+/// as key item and its value as value item. The items in `idxs` are removed, everything else is
+/// copied. E.g. monthly columns `jan`..`dec` become twelve (month, value) rows.
+#[derive(Debug, PartialEq, Eq)]
+pub struct UnpivotItems {
+    pub idxs: Vec<usize>,
+    pub key_idx: usize,
+    pub key_header: Option<String>,
+    /// The headers are parsed as this type.
+    pub key_type: ValueType,
+    pub value_idx: usize,
+    pub value_header: Option<String>,
+    pub value_type: ValueType,
+}
+
+impl TransrichExplode for UnpivotItems {
+    fn explode(&self, mut data_cell_row: DataCellRow) -> Result<Vec<DataCellRow>> {
+        let mut unpivoted: Vec<DataCell> = Vec::with_capacity(self.idxs.len());
+        for idx in &self.idxs {
+            unpivoted.push(data_cell_row.del_by_idx(*idx)?);
+        }
+
+        let mut rows: Vec<DataCellRow> = Vec::with_capacity(unpivoted.len());
+        for dc in unpivoted {
+            let mut row = data_cell_row.clone();
+            row.push_checked(
+                DataCell::new_with_type_info(
+                    self.key_type.clone(),
+                    self.key_header
+                        .clone()
+                        .unwrap_or_else(|| self.key_idx.to_string()),
+                    self.key_idx,
+                    Value::from_str_and_type(dc.get_name(), &self.key_type)?,
+                )?,
+                None,
+            )?;
+            row.push_checked(
+                DataCell::new_with_type_info(
+                    self.value_type.clone(),
+                    self.value_header
+                        .clone()
+                        .unwrap_or_else(|| self.value_idx.to_string()),
+                    self.value_idx,
+                    convert_to(dc.data, &self.value_type)?,
+                )?,
+                None,
+            )?;
+            rows.push(row);
+        }
+        Ok(rows)
+    }
+
+    fn describe(&self) -> Description {
+        Description::new("UnpivotItems")
+            .param("idxs", &self.idxs)
+            .param("key_idx", self.key_idx)
+            .param("key_header", &self.key_header)
+            .param("key_type", &self.key_type)
+            .param("value_idx", self.value_idx)
+            .param("value_header", &self.value_header)
+            .param("value_type", &self.value_type)
+    }
+
+    #[cfg(feature = "jsonconf")]
+    fn to_config(&self) -> Option<TransformerConfig> {
+        Some(TransformerConfig::Unpivot {
+            cfg: UnpivotConfig {
+                idxs: self.idxs.clone(),
+                key: ItemTargetConfig {
+                    idx: self.key_idx,
+                    header: self.key_header.clone(),
+                    target_type: self.key_type.clone(),
+                },
+                value: ItemTargetConfig {
+                    idx: self.value_idx,
+                    header: self.value_header.clone(),
+                    target_type: self.value_type.clone(),
+                },
+            },
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::transform::value::spliting::ValueStringSeparatorCharTokens;
+
+    #[test]
+    fn explode_item() {
+        let mut row = DataCellRow::new();
+        row.push(DataCell::new(String::from("id"), 0, Value::Int32(7)).unwrap());
+        row.push(
+            DataCell::new(String::from("tags"), 1, Value::String(String::from("a, b"))).unwrap(),
+        );
+
+        let mut explode = ExplodeItem {
+            idx: 1,
+            splitter: ValueStringSeparatorCharTokens { sep_char: ',' },
+            target_type: ValueType::String,
+            keep_empty: false,
+        };
+        let rows = explode.explode(row.clone()).unwrap();
+        assert_eq!(2, rows.len());
+        for (row, tag) in rows.iter().zip(["a", "b"]) {
+            assert_eq!(&Value::Int32(7), row.get_by_idx(0).unwrap().get_data());
+            assert_eq!(
+                &Value::String(String::from(tag)),
+                row.get_by_idx(1).unwrap().get_data()
+            );
+        }
+
+        row.get_by_idx_mut(1).unwrap().set_data(Value::None);
+        assert!(explode.explode(row.clone()).unwrap().is_empty());
+        explode.keep_empty = true;
+        assert_eq!(vec![row.clone()], explode.explode(row).unwrap());
+    }
+
+    #[test]
+    fn unpivot_items() {
+        let mut row = DataCellRow::new();
+        row.push(DataCell::new(String::from("id"), 0, Value::Int32(7)).unwrap());
+        row.push(DataCell::new(String::from("1"), 1, Value::String(String::from("1.5"))).unwrap());
+        row.push(DataCell::new(String::from("2"), 2, Value::String(String::from("2.5"))).unwrap());
+
+        let unpivot = UnpivotItems {
+            idxs: vec![1, 2],
+            key_idx: 1,
+            key_header: Some(String::from("month")),
+            key_type: ValueType::UInt8,
+            value_idx: 2,
+            value_header: Some(String::from("value")),
+            value_type: ValueType::Float64,
+        };
+        let rows = unpivot.explode(row).unwrap();
+        assert_eq!(2, rows.len());
+        assert_eq!(vec!["id", "month", "value"], rows[1].headers());
+        assert_eq!(&Value::UInt8(2), rows[1].get_by_idx(1).unwrap().get_data());
+        assert_eq!(
+            &Value::Float64(2.5),
+            rows[1].get_by_idx(2).unwrap().get_data()
+        );
+
+        let mut row = DataCellRow::new();
+        row.push(DataCell::new(String::from("id"), 0, Value::Int32(7)).unwrap());
+        assert!(unpivot.explode(row).is_err());
+    }
+}
@@ -1,21 +1,49 @@
+use std::collections::{BTreeMap, HashMap};
 use std::fmt::Debug;
 
 use venum::value::Value;
 use venum::value_type::ValueType;
 
+#[cfg(feature = "jsonconf")]
+use crate::conf::jsonconf::{
+    AddItemConfig, AddItemType, ItemTargetConfig, ModifierConfig, ModifyItemConfig,
+    RoundItemConfig, TransformerConfig, WhenConfig,
+};
 use crate::{
+    constraints::ColumnRules,
     data_cell::DataCell,
-    data_cell_row::DataCellRow,
-    errors::{ContainerOpsErrors, DataAccessErrors, Result, VenumTdsError},
+    data_cell_row::{DataCellRow, OnConflict},
+    errors::{ContainerOpsErrors, DataAccessErrors, Result, TransformErrors, VenumTdsError},
+    schema::Schema,
     transform::{
         data_cell::splitting::SplitDataCell,
+        data_cell_row::condition::RowCondition,
+        description::Description,
         util::chrono_utils::utc_datetime_as_fixed_offset_datetime,
+        value::{
+            modifying::StringModifier,
+            numbers::{round_value, NumberFormat, RoundingMode},
+        },
     },
+    value_fmt::value_to_string,
 };
 
 /// The "simplest" transrichment. We get all info we need from the (static) transrichment config.
 pub trait TransrichInplace: Debug {
     fn transrich(&self, data_cell_row: &mut DataCellRow) -> Result<()>;
+
+    /// What this transricher does, leaving out any state, see `Description`.
+    fn describe(&self) -> Description;
+
+    /// The config this transricher is built from, if it can be written as one, see
+    /// `TransrichPass::to_config`. The ordering transrichers have none, see `order_step`.
+    #[cfg(feature = "jsonconf")]
+    fn to_config(&self) -> Option<TransformerConfig>;
+
+    /// What this transricher does in the ordering of a pass, if it is an ordering one.
+    fn order_step(&self) -> Option<OrderStep> {
+        None
+    }
 }
 
 #[derive(Debug, PartialEq, Eq)]
@@ -33,7 +61,7 @@ impl TransrichInplace for MutateItemIdx {
         let container_entry = data_cell_row.get_by_idx_mut(self.from);
         match container_entry {
             None => Err(VenumTdsError::ContainerOps(ContainerOpsErrors::Generic {
-                msg: String::from("No DataEntry with idx {self.from}. Can't mutate index."),
+                msg: format!("No DataEntry with idx {}. Can't mutate index.", self.from),
             })),
             Some(date_entry) => {
                 date_entry.set_idx(self.to);
@@ -41,6 +69,227 @@ impl TransrichInplace for MutateItemIdx {
             }
         }
     }
+
+    fn describe(&self) -> Description {
+        Description::new("MutateItemIdx")
+            .param("from", self.from)
+            .param("to", self.to)
+    }
+
+    #[cfg(feature = "jsonconf")]
+    fn to_config(&self) -> Option<TransformerConfig> {
+        None
+    }
+
+    fn order_step(&self) -> Option<OrderStep> {
+        Some(OrderStep::Entry {
+            from: self.from,
+            to: self.to,
+            mode: OrderMode::Move,
+        })
+    }
+}
+
+/// How the entries of `orderItems` are applied.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(
+    feature = "jsonconf",
+    derive(serde::Deserialize, serde::Serialize),
+    serde(rename_all = "camelCase")
+)]
+#[cfg_attr(feature = "jsonschema", derive(schemars::JsonSchema))]
+pub enum OrderMode {
+    /// Just (re-)set the idx of the item. If the target idx is in use, we end up with a duplicate idx!
+    #[default]
+    Move,
+    /// Like `Move`, but an item already occupying the target idx gets the source idx (i.e. they swap).
+    Swap,
+}
+
+/// What we check, after all `orderItems` entries are applied.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(
+    feature = "jsonconf",
+    derive(serde::Deserialize, serde::Serialize),
+    serde(rename_all = "camelCase")
+)]
+#[cfg_attr(feature = "jsonschema", derive(schemars::JsonSchema))]
+pub enum OrderValidation {
+    /// Anything goes (lenient)
+    #[default]
+    None,
+    /// No two items may have the same idx
+    Unique,
+    /// The indices must be exactly 0..n (strict)
+    Permutation,
+}
+
+/// A single step of the ordering of a pass, i.e. what an ordering transricher stands for in the
+/// `orderItems` and `orderItemsOptions` of a config.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OrderStep {
+    Entry {
+        from: usize,
+        to: usize,
+        mode: OrderMode,
+    },
+    Compact,
+    Validate(OrderValidation),
+    Sort,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct SwapItemIdx {
+    pub from: usize,
+    pub to: usize,
+}
+impl SwapItemIdx {
+    pub fn new(from: usize, to: usize) -> Self {
+        Self { from, to }
+    }
+}
+impl TransrichInplace for SwapItemIdx {
+    fn transrich(&self, data_cell_row: &mut DataCellRow) -> Result<()> {
+        if data_cell_row.get_by_idx(self.from).is_none() {
+            return Err(VenumTdsError::ContainerOps(ContainerOpsErrors::Generic {
+                msg: format!("No DataEntry with idx {}. Can't swap index.", self.from),
+            }));
+        }
+        if self.from == self.to {
+            return Ok(());
+        }
+        // We only touch the first item with the respective idx, just like `get_by_idx` would.
+        let (mut moved_from, mut moved_to) = (false, false);
+        for dc in data_cell_row {
+            if !moved_from && dc.get_idx() == self.from {
+                dc.set_idx(self.to);
+                moved_from = true;
+            } else if !moved_to && dc.get_idx() == self.to {
+                dc.set_idx(self.from);
+                moved_to = true;
+            }
+        }
+        Ok(())
+    }
+
+    fn describe(&self) -> Description {
+        Description::new("SwapItemIdx")
+            .param("from", self.from)
+            .param("to", self.to)
+    }
+
+    #[cfg(feature = "jsonconf")]
+    fn to_config(&self) -> Option<TransformerConfig> {
+        None
+    }
+
+    fn order_step(&self) -> Option<OrderStep> {
+        Some(OrderStep::Entry {
+            from: self.from,
+            to: self.to,
+            mode: OrderMode::Swap,
+        })
+    }
+}
+
+/// Re-assigns the indices of all items to 0..n, keeping their relative order.
+#[derive(Debug, PartialEq, Eq)]
+pub struct CompactItemIdx;
+impl TransrichInplace for CompactItemIdx {
+    fn transrich(&self, data_cell_row: &mut DataCellRow) -> Result<()> {
+        data_cell_row.sort_by_idx();
+        for (i, dc) in data_cell_row.into_iter().enumerate() {
+            dc.set_idx(i);
+        }
+        Ok(())
+    }
+
+    fn describe(&self) -> Description {
+        Description::new("CompactItemIdx")
+    }
+
+    #[cfg(feature = "jsonconf")]
+    fn to_config(&self) -> Option<TransformerConfig> {
+        None
+    }
+
+    fn order_step(&self) -> Option<OrderStep> {
+        Some(OrderStep::Compact)
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct ValidateItemIdx(pub OrderValidation);
+impl TransrichInplace for ValidateItemIdx {
+    fn transrich(&self, data_cell_row: &mut DataCellRow) -> Result<()> {
+        if self.0 == OrderValidation::None {
+            return Ok(());
+        }
+
+        let mut indices: Vec<usize> = data_cell_row.into_iter().map(|dc| dc.get_idx()).collect();
+        indices.sort_unstable();
+
+        if let Some(dup) = indices.windows(2).find(|w| w[0] == w[1]) {
+            return Err(VenumTdsError::ContainerOps(
+                ContainerOpsErrors::OrderItemsError {
+                    msg: format!("idx {} is used more than once", dup[0]),
+                },
+            ));
+        }
+        // sorted and unique, so we only need to check the last one
+        if self.0 == OrderValidation::Permutation
+            && indices
+                .last()
+                .is_some_and(|last| *last != indices.len() - 1)
+        {
+            return Err(VenumTdsError::ContainerOps(
+                ContainerOpsErrors::OrderItemsError {
+                    msg: format!(
+                        "indices are not a permutation of 0..{}, got: {:?}",
+                        indices.len(),
+                        indices
+                    ),
+                },
+            ));
+        }
+        Ok(())
+    }
+
+    fn describe(&self) -> Description {
+        Description::new("ValidateItemIdx").param("validation", self.0)
+    }
+
+    #[cfg(feature = "jsonconf")]
+    fn to_config(&self) -> Option<TransformerConfig> {
+        None
+    }
+
+    fn order_step(&self) -> Option<OrderStep> {
+        Some(OrderStep::Validate(self.0))
+    }
+}
+
+/// Physically sorts the items by idx, so that iteration order matches column order.
+#[derive(Debug, PartialEq, Eq)]
+pub struct SortItemsByIdx;
+impl TransrichInplace for SortItemsByIdx {
+    fn transrich(&self, data_cell_row: &mut DataCellRow) -> Result<()> {
+        data_cell_row.sort_by_idx();
+        Ok(())
+    }
+
+    fn describe(&self) -> Description {
+        Description::new("SortItemsByIdx")
+    }
+
+    #[cfg(feature = "jsonconf")]
+    fn to_config(&self) -> Option<TransformerConfig> {
+        None
+    }
+
+    fn order_step(&self) -> Option<OrderStep> {
+        Some(OrderStep::Sort)
+    }
 }
 
 #[derive(Debug, PartialEq, Eq)]
@@ -49,6 +298,15 @@ impl TransrichInplace for DeleteItemAtIdx {
     fn transrich(&self, data_cell_row: &mut DataCellRow) -> Result<()> {
         data_cell_row.del_by_idx(self.0).map(|_| ())
     }
+
+    fn describe(&self) -> Description {
+        Description::new("DeleteItemAtIdx").param("idx", self.0)
+    }
+
+    #[cfg(feature = "jsonconf")]
+    fn to_config(&self) -> Option<TransformerConfig> {
+        Some(TransformerConfig::DeleteItems { cfg: vec![self.0] })
+    }
 }
 
 #[derive(Debug, PartialEq, Eq)]
@@ -57,6 +315,10 @@ pub struct AddItemCopyConvertAs {
     pub target_header: Option<String>,
     pub target_idx: usize,
     pub target_data_type: ValueType,
+    pub on_conflict: Option<OnConflict>,
+    /// If given, string values are parsed as numbers of this format, instead of the standard
+    /// conversion.
+    pub number_format: Option<NumberFormat>,
 }
 impl TransrichInplace for AddItemCopyConvertAs {
     fn transrich(&self, data_cell_row: &mut DataCellRow) -> Result<()> {
@@ -64,7 +326,10 @@ impl TransrichInplace for AddItemCopyConvertAs {
             VenumTdsError::DataAccess(DataAccessErrors::IllegalIdxAccess { idx: self.src_idx })
         })?;
 
-        let converted_value = src.get_data().try_convert_to(&self.target_data_type)?;
+        let converted_value = match (src.get_data(), &self.number_format) {
+            (Value::String(s), Some(nf)) => nf.parse(s, &self.target_data_type)?,
+            (val, _) => val.try_convert_to(&self.target_data_type)?,
+        };
         let new_datacell = DataCell::new_with_type_info(
             self.target_data_type.clone(),
             self.target_header
@@ -73,31 +338,347 @@ impl TransrichInplace for AddItemCopyConvertAs {
             self.target_idx,
             converted_value,
         )?;
-        data_cell_row.push(new_datacell);
+        data_cell_row.push_checked(new_datacell, self.on_conflict)
+    }
+
+    fn describe(&self) -> Description {
+        Description::new("AddItemCopyConvertAs")
+            .param("src_idx", self.src_idx)
+            .param("target_header", &self.target_header)
+            .param("target_idx", self.target_idx)
+            .param("target_data_type", &self.target_data_type)
+            .param("on_conflict", self.on_conflict)
+            .param("number_format", &self.number_format)
+    }
+
+    #[cfg(feature = "jsonconf")]
+    fn to_config(&self) -> Option<TransformerConfig> {
+        Some(TransformerConfig::AddItem {
+            cfg: AddItemConfig {
+                spec: AddItemType::CopyConvertAs {
+                    from_idx: self.src_idx,
+                    number_format: self.number_format.clone(),
+                },
+                target: ItemTargetConfig {
+                    idx: self.target_idx,
+                    header: self.target_header.clone(),
+                    target_type: self.target_data_type.clone(),
+                },
+                on_conflict: self.on_conflict,
+            },
+        })
+    }
+}
+
+/// Maps the value of an item through a lookup table. The key is the stringified value of the item
+/// (converted to the `key_type` first, if there is one). On a miss, the `default` is used, if there
+/// is one. Otherwise, with `strict`, the row fails, and without, the item is left as it is (or,
+/// when writing to a new target item, it gets `None`).
+#[derive(Debug, PartialEq)]
+pub struct MapItemValue {
+    pub idx: usize,
+    /// If set, the mapped value goes into a new item, otherwise it replaces the value in place.
+    pub target_idx: Option<usize>,
+    pub target_header: Option<String>,
+    /// If set, the value of the item is converted to this type before it's stringified, the same
+    /// way as the keys of the table, so e.g. an `Int32` 1 finds the `Float64` key "1.0".
+    pub key_type: Option<ValueType>,
+    /// The type of the mapped values (and the default).
+    pub value_type: ValueType,
+    pub table: HashMap<String, Value>,
+    pub default: Option<Value>,
+    pub strict: bool,
+}
+impl MapItemValue {
+    fn lookup(&self, val: &Value) -> Result<Option<Value>> {
+        let key = match &self.key_type {
+            // a value that doesn't convert can't be in the table, so its plain string is fine
+            Some(kt) if !val.is_none() => Value::from_str_and_type(&value_to_string(val), kt)
+                .map(|v| value_to_string(&v))
+                .unwrap_or_else(|_| value_to_string(val)),
+            _ => value_to_string(val),
+        };
+        match (self.table.get(&key), &self.default) {
+            (Some(v), _) | (None, Some(v)) => Ok(Some(v.clone())),
+            (None, None) if self.strict => Err(VenumTdsError::ContainerOps(
+                ContainerOpsErrors::MapValueMiss { idx: self.idx, key },
+            )),
+            (None, None) => Ok(None),
+        }
+    }
+}
+impl TransrichInplace for MapItemValue {
+    fn transrich(&self, data_cell_row: &mut DataCellRow) -> Result<()> {
+        let src = data_cell_row.get_by_idx(self.idx).ok_or({
+            VenumTdsError::DataAccess(DataAccessErrors::IllegalIdxAccess { idx: self.idx })
+        })?;
+        let mapped = self.lookup(src.get_data())?;
+
+        match self.target_idx {
+            None => {
+                if let Some(val) = mapped {
+                    let dc = data_cell_row.get_by_idx_mut(self.idx).unwrap(); // we checked it above
+                    dc.set_type_info(self.value_type.clone());
+                    dc.set_data(val);
+                }
+                Ok(())
+            }
+            Some(target_idx) => {
+                let new_datacell = DataCell::new_with_type_info(
+                    self.value_type.clone(),
+                    self.target_header
+                        .clone()
+                        .unwrap_or_else(|| target_idx.to_string()),
+                    target_idx,
+                    mapped.unwrap_or(Value::None),
+                )?;
+                data_cell_row.push_checked(new_datacell, None)
+            }
+        }
+    }
+
+    fn describe(&self) -> Description {
+        Description::new("MapItemValue")
+            .param("idx", self.idx)
+            .param("target_idx", self.target_idx)
+            .param("target_header", &self.target_header)
+            .param("key_type", &self.key_type)
+            .param("value_type", &self.value_type)
+            .data("table", self.table.iter().collect::<BTreeMap<_, _>>())
+            .param("default", &self.default)
+            .param("strict", self.strict)
+    }
+
+    #[cfg(feature = "jsonconf")]
+    fn to_config(&self) -> Option<TransformerConfig> {
+        Some(TransformerConfig::MapValues { cfg: self.into() })
+    }
+}
+
+/// Modifies the (string) values of one or many items in place, applying the modifiers in order.
+/// `None` values are left as they are, other non-string values are an error.
+#[derive(Debug)]
+pub struct ModifyItems {
+    pub idxs: Vec<usize>,
+    pub modifiers: Vec<StringModifier>,
+}
+impl TransrichInplace for ModifyItems {
+    fn transrich(&self, data_cell_row: &mut DataCellRow) -> Result<()> {
+        for idx in &self.idxs {
+            let dc = data_cell_row.get_by_idx_mut(*idx).ok_or({
+                VenumTdsError::DataAccess(DataAccessErrors::IllegalIdxAccess { idx: *idx })
+            })?;
+            match dc.get_data_mut() {
+                Value::None => {}
+                Value::String(s) => {
+                    for m in &self.modifiers {
+                        *s = m.apply(s);
+                    }
+                }
+                _ => {
+                    return Err(VenumTdsError::Transform(TransformErrors::Generic {
+                        msg: format!("Not a Value::String. Can't modify item with idx={}.", idx),
+                    }))
+                }
+            }
+        }
         Ok(())
     }
+
+    fn describe(&self) -> Description {
+        Description::new("ModifyItems")
+            .param("idxs", &self.idxs)
+            .param("modifiers", &self.modifiers)
+    }
+
+    #[cfg(feature = "jsonconf")]
+    fn to_config(&self) -> Option<TransformerConfig> {
+        Some(TransformerConfig::ModifyItem {
+            cfg: ModifyItemConfig {
+                idxs: self.idxs.clone(),
+                modifiers: self.modifiers.iter().map(ModifierConfig::from).collect(),
+            },
+        })
+    }
+}
+
+/// Rounds the `Decimal` or float values of one or many items in place, to `scale` decimal places.
+#[derive(Debug, PartialEq, Eq)]
+pub struct RoundItems {
+    pub idxs: Vec<usize>,
+    pub scale: u32,
+    pub mode: RoundingMode,
+}
+impl TransrichInplace for RoundItems {
+    fn transrich(&self, data_cell_row: &mut DataCellRow) -> Result<()> {
+        for idx in &self.idxs {
+            let dc = data_cell_row.get_by_idx_mut(*idx).ok_or({
+                VenumTdsError::DataAccess(DataAccessErrors::IllegalIdxAccess { idx: *idx })
+            })?;
+            let rounded = round_value(dc.get_data(), self.scale, self.mode)?;
+            dc.set_data(rounded);
+        }
+        Ok(())
+    }
+
+    fn describe(&self) -> Description {
+        Description::new("RoundItems")
+            .param("idxs", &self.idxs)
+            .param("scale", self.scale)
+            .param("mode", self.mode)
+    }
+
+    #[cfg(feature = "jsonconf")]
+    fn to_config(&self) -> Option<TransformerConfig> {
+        Some(TransformerConfig::RoundItem {
+            cfg: RoundItemConfig {
+                idxs: self.idxs.clone(),
+                scale: self.scale,
+                mode: Some(self.mode),
+            },
+        })
+    }
+}
+
+/// Runs the `then` transrichers, if the condition holds for the row, and the `otherwise` ones, if
+/// not.
+#[derive(Debug)]
+pub struct When {
+    pub condition: RowCondition,
+    pub then: Vec<Box<dyn TransrichInplace + Send + Sync>>,
+    pub otherwise: Vec<Box<dyn TransrichInplace + Send + Sync>>,
+}
+impl TransrichInplace for When {
+    fn transrich(&self, data_cell_row: &mut DataCellRow) -> Result<()> {
+        let branch = if self.condition.holds_for(data_cell_row) {
+            &self.then
+        } else {
+            &self.otherwise
+        };
+        branch
+            .iter()
+            .try_for_each(|tri| tri.transrich(data_cell_row))
+    }
+
+    fn describe(&self) -> Description {
+        Description::new("When")
+            .param("condition", &self.condition)
+            .nested("then", self.then.iter().map(|tri| tri.describe()))
+            .nested("otherwise", self.otherwise.iter().map(|tri| tri.describe()))
+    }
+
+    #[cfg(feature = "jsonconf")]
+    fn to_config(&self) -> Option<TransformerConfig> {
+        Some(TransformerConfig::When {
+            cfg: WhenConfig {
+                condition: (&self.condition).into(),
+                then: self
+                    .then
+                    .iter()
+                    .map(|tri| tri.to_config())
+                    .collect::<Option<_>>()?,
+                otherwise: self
+                    .otherwise
+                    .iter()
+                    .map(|tri| tri.to_config())
+                    .collect::<Option<_>>()?,
+            },
+        })
+    }
+}
+
+/// Fails the row, if it doesn't match the schema (including its constraints). Meant to go last, so
+/// that malformed rows never leave the pipeline. Note, that it checks the row where it is in the
+/// pass, i.e. before the ordering of that pass.
+#[derive(Debug)]
+pub struct AssertSchema {
+    schema: Schema,
+    rules: Vec<ColumnRules>,
+}
+impl AssertSchema {
+    /// Fails, if the constraints of the schema are invalid, see `Schema::rules`.
+    pub fn new(schema: Schema) -> Result<Self> {
+        let rules = schema.rules()?;
+        Ok(Self { schema, rules })
+    }
+
+    pub fn schema(&self) -> &Schema {
+        &self.schema
+    }
+}
+impl TransrichInplace for AssertSchema {
+    fn transrich(&self, data_cell_row: &mut DataCellRow) -> Result<()> {
+        let violations = self.schema.validate_with_rules(data_cell_row, &self.rules);
+        if violations.is_empty() {
+            Ok(())
+        } else {
+            Err(VenumTdsError::ContainerOps(
+                ContainerOpsErrors::SchemaError { violations },
+            ))
+        }
+    }
+
+    fn describe(&self) -> Description {
+        Description::new("AssertSchema").param("schema", &self.schema)
+    }
+
+    #[cfg(feature = "jsonconf")]
+    fn to_config(&self) -> Option<TransformerConfig> {
+        Some(TransformerConfig::AssertSchema {
+            cfg: self.schema.clone(),
+        })
+    }
 }
 
 #[derive(Debug, PartialEq)]
-pub struct AddItemStatic(pub DataCell);
+pub struct AddItemStatic(pub DataCell, pub Option<OnConflict>);
 impl TransrichInplace for AddItemStatic {
     fn transrich(&self, data_cell_row: &mut DataCellRow) -> Result<()> {
-        data_cell_row.push(self.0.clone());
-        Ok(())
+        data_cell_row.push_checked(self.0.clone(), self.1)
+    }
+
+    fn describe(&self) -> Description {
+        Description::new("AddItemStatic")
+            .param("item", &self.0)
+            .param("on_conflict", self.1)
+    }
+
+    #[cfg(feature = "jsonconf")]
+    fn to_config(&self) -> Option<TransformerConfig> {
+        Some(TransformerConfig::AddItem {
+            cfg: AddItemConfig {
+                spec: AddItemType::Static {
+                    value: value_to_string(self.0.get_data()),
+                },
+                target: (&self.0).into(),
+                on_conflict: self.1,
+            },
+        })
     }
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
-#[cfg_attr(feature = "jsonconf", derive(serde::Deserialize))]
+#[cfg_attr(feature = "jsonconf", derive(serde::Deserialize, serde::Serialize))]
+#[cfg_attr(feature = "jsonschema", derive(schemars::JsonSchema))]
 pub enum RuntimeValue {
     CurrentDateTimeUtcAsFixedOffset,
 }
 
+impl RuntimeValue {
+    /// The type of the values added.
+    pub fn value_type(&self) -> ValueType {
+        match self {
+            RuntimeValue::CurrentDateTimeUtcAsFixedOffset => ValueType::DateTime,
+        }
+    }
+}
+
 #[derive(Debug, PartialEq, Eq)]
 pub struct AddItemRuntime {
     pub header: Option<String>,
     pub idx: usize,
     pub rtv: RuntimeValue,
+    pub on_conflict: Option<OnConflict>,
 }
 impl TransrichInplace for AddItemRuntime {
     fn transrich(&self, data_cell_row: &mut DataCellRow) -> Result<()> {
@@ -110,40 +691,93 @@ impl TransrichInplace for AddItemRuntime {
                         chrono::offset::Utc::now(),
                     )),
                 )?;
-                data_cell_row.push(curr_date_cell);
-                Ok(())
+                data_cell_row.push_checked(curr_date_cell, self.on_conflict)
             }
             // _ => Err(VenumTdsError::ContainerOps(ContainerOpsErrors::Generic {
             //     msg: format!("{:?} not implemented. (idx={}", &self.rtv, &self.idx),
             // })),
         }
     }
+
+    fn describe(&self) -> Description {
+        Description::new("AddItemRuntime")
+            .param("header", &self.header)
+            .param("idx", self.idx)
+            .param("rtv", &self.rtv)
+            .param("on_conflict", self.on_conflict)
+    }
+
+    #[cfg(feature = "jsonconf")]
+    fn to_config(&self) -> Option<TransformerConfig> {
+        Some(TransformerConfig::AddItem {
+            cfg: AddItemConfig {
+                spec: AddItemType::Runtime {
+                    rt_value: self.rtv.clone(),
+                    as_singleton: Some(false),
+                },
+                target: ItemTargetConfig {
+                    idx: self.idx,
+                    header: self.header.clone(),
+                    target_type: self.rtv.value_type(),
+                },
+                on_conflict: self.on_conflict,
+            },
+        })
+    }
 }
 
 #[derive(Debug, PartialEq)]
-pub struct AddItemRuntimeSingleton(DataCell);
+pub struct AddItemRuntimeSingleton(DataCell, Option<OnConflict>, RuntimeValue);
 impl AddItemRuntimeSingleton {
     pub fn new(header: Option<String>, idx: usize, rtv: RuntimeValue) -> Result<Self> {
         match rtv {
             RuntimeValue::CurrentDateTimeUtcAsFixedOffset => {
-                Ok(AddItemRuntimeSingleton(DataCell::new(
+                let curr_date_cell = DataCell::new(
                     header.unwrap_or_else(|| idx.to_string()),
                     idx,
                     Value::DateTime(utc_datetime_as_fixed_offset_datetime(
                         chrono::offset::Utc::now(),
                     )),
-                )?))
+                )?;
+                Ok(AddItemRuntimeSingleton(curr_date_cell, None, rtv))
             }
             // _ => Err(VenumTdsError::ContainerOps(ContainerOpsErrors::Generic {
             //     msg: format!("{:?} not implemented. (idx={}", &rtv, &idx),
             // })),
         }
     }
+
+    pub fn with_on_conflict(mut self, on_conflict: Option<OnConflict>) -> Self {
+        self.1 = on_conflict;
+        self
+    }
 }
 impl TransrichInplace for AddItemRuntimeSingleton {
     fn transrich(&self, data_cell_row: &mut DataCellRow) -> Result<()> {
-        data_cell_row.push(self.0.clone());
-        Ok(())
+        data_cell_row.push_checked(self.0.clone(), self.1)
+    }
+
+    fn describe(&self) -> Description {
+        // (the value is taken, when this is built, so it's not part of the description)
+        Description::new("AddItemRuntimeSingleton")
+            .param("header", self.0.get_name())
+            .param("idx", self.0.get_idx())
+            .param("type", self.0.get_type_info())
+            .param("on_conflict", self.1)
+    }
+
+    #[cfg(feature = "jsonconf")]
+    fn to_config(&self) -> Option<TransformerConfig> {
+        Some(TransformerConfig::AddItem {
+            cfg: AddItemConfig {
+                spec: AddItemType::Runtime {
+                    rt_value: self.2.clone(),
+                    as_singleton: Some(true),
+                },
+                target: (&self.0).into(),
+                on_conflict: self.1,
+            },
+        })
     }
 }
 
@@ -173,26 +807,49 @@ where
             data_cell_row.del_by_idx(self.idx).unwrap(); // we check it above already
         }
 
-        data_cell_row.push(left);
-        data_cell_row.push(right);
+        // in strict mode, the targets must not be in use already
+        data_cell_row.push_checked(left, None)?;
+        data_cell_row.push_checked(right, None)
+    }
+
+    fn describe(&self) -> Description {
+        Description::new("SplitItemAtIdx")
+            .param("idx", self.idx)
+            .param("splitter", &self.splitter)
+            .param("delete_source_item", self.delete_source_item)
+    }
 
-        Ok(())
+    #[cfg(feature = "jsonconf")]
+    fn to_config(&self) -> Option<TransformerConfig> {
+        self.splitter
+            .to_config(self.idx, self.delete_source_item)
+            .map(|cfg| TransformerConfig::SplitItem { cfg })
     }
 }
 
 #[cfg(test)]
 mod tests {
+    use std::collections::HashMap;
+
     use venum::value::Value;
     use venum::value_type::ValueType;
 
     use crate::{
+        constraints::RuleConfig,
         data_cell::DataCell,
-        data_cell_row::DataCellRow,
-        errors::Result,
+        data_cell_row::{DataCellRow, OnConflict},
+        errors::{ContainerOpsErrors, DataAccessErrors, Result, VenumTdsError},
+        schema::{Schema, SchemaColumn, SchemaViolation},
         transform::{
             data_cell::splitting::SplitDataCellUsingValueSplit,
-            data_cell_row::{transrich_inplace::*, transrich_inplace_stateful::*},
-            value::spliting::ValueStringSeparatorCharSplit,
+            data_cell_row::{
+                condition::RowCondition, transrich_inplace::*, transrich_inplace_stateful::*,
+            },
+            value::{
+                modifying::{CaseMode, StringModifier, TrimSide},
+                numbers::{NumberFormat, RoundingMode},
+                spliting::ValueStringSeparatorCharSplit,
+            },
         },
     };
 
@@ -211,6 +868,85 @@ mod tests {
         assert_eq!(1, c.get_by_idx(1).unwrap().idx);
     }
 
+    #[test]
+    fn swap_idx() {
+        let mut c = DataCellRow::new();
+        c.push(DataCell::new_without_data(
+            ValueType::Bool,
+            String::from("col1"),
+            0,
+        ));
+        c.push(DataCell::new_without_data(
+            ValueType::Bool,
+            String::from("col2"),
+            1,
+        ));
+
+        SwapItemIdx::new(0, 1).transrich(&mut c).unwrap();
+        assert_eq!("col1", c.get_by_idx(1).unwrap().get_name());
+        assert_eq!("col2", c.get_by_idx(0).unwrap().get_name());
+
+        // target not in use, same as a move
+        SwapItemIdx::new(0, 5).transrich(&mut c).unwrap();
+        assert_eq!("col2", c.get_by_idx(5).unwrap().get_name());
+        assert!(c.get_by_idx(0).is_none());
+    }
+
+    #[test]
+    fn compact_and_sort_idx() {
+        let mut c = DataCellRow::new();
+        c.push(DataCell::new_without_data(
+            ValueType::Bool,
+            String::from("col1"),
+            7,
+        ));
+        c.push(DataCell::new_without_data(
+            ValueType::Bool,
+            String::from("col2"),
+            3,
+        ));
+
+        CompactItemIdx.transrich(&mut c).unwrap();
+        let names: Vec<(usize, &str)> =
+            (&c).into_iter().map(|dc| (dc.idx, dc.get_name())).collect();
+        assert_eq!(vec![(0, "col2"), (1, "col1")], names);
+    }
+
+    #[test]
+    fn validate_idx() {
+        let mut c = DataCellRow::new();
+        c.push(DataCell::new_without_data(
+            ValueType::Bool,
+            String::from("col1"),
+            0,
+        ));
+        c.push(DataCell::new_without_data(
+            ValueType::Bool,
+            String::from("col2"),
+            2,
+        ));
+
+        ValidateItemIdx(OrderValidation::None)
+            .transrich(&mut c)
+            .unwrap();
+        ValidateItemIdx(OrderValidation::Unique)
+            .transrich(&mut c)
+            .unwrap();
+        assert!(ValidateItemIdx(OrderValidation::Permutation)
+            .transrich(&mut c)
+            .is_err());
+
+        MutateItemIdx::new(2, 0).transrich(&mut c).unwrap();
+        assert_eq!(
+            Err(VenumTdsError::ContainerOps(
+                ContainerOpsErrors::OrderItemsError {
+                    msg: String::from("idx 0 is used more than once")
+                }
+            )),
+            ValidateItemIdx(OrderValidation::Unique).transrich(&mut c)
+        );
+    }
+
     #[test]
     fn delete_from_container() {
         let mut c = DataCellRow::new();
@@ -245,16 +981,93 @@ mod tests {
     #[test]
     fn add_item_static() {
         let mut c = DataCellRow::new();
-        let container_transricher = AddItemStatic(DataCell::new_without_data(
-            ValueType::Bool,
-            String::from("col1"),
-            0,
-        ));
+        let container_transricher = AddItemStatic(
+            DataCell::new_without_data(ValueType::Bool, String::from("col1"), 0),
+            None,
+        );
         container_transricher.transrich(&mut c).unwrap();
 
         assert_eq!(1, c.len());
     }
 
+    #[test]
+    fn assert_schema() {
+        let mut c = DataCellRow::new();
+        c.push(DataCell::new(String::from("col1"), 0, Value::Bool(true)).unwrap());
+
+        let schema = Schema::new(vec![SchemaColumn::new(0, None, ValueType::Bool, false)]);
+        AssertSchema::new(schema.clone())
+            .unwrap()
+            .transrich(&mut c)
+            .unwrap();
+
+        c.get_by_idx_mut(0).unwrap().set_data(Value::None);
+        assert_eq!(
+            Err(VenumTdsError::ContainerOps(
+                ContainerOpsErrors::SchemaError {
+                    violations: vec![SchemaViolation::UnexpectedNone { idx: 0 }]
+                }
+            )),
+            AssertSchema::new(schema).unwrap().transrich(&mut c)
+        );
+
+        let schema = Schema::new(vec![SchemaColumn::new(0, None, ValueType::Bool, true)
+            .with_constraints(vec![RuleConfig::NotNull])]);
+        assert_eq!(
+            Err(VenumTdsError::ContainerOps(
+                ContainerOpsErrors::SchemaError {
+                    violations: vec![SchemaViolation::BrokenConstraint {
+                        idx: 0,
+                        rule: String::from("notNull"),
+                        value: String::new()
+                    }]
+                }
+            )),
+            AssertSchema::new(schema).unwrap().transrich(&mut c)
+        );
+
+        // invalid constraints fail right away
+        let schema = Schema::new(vec![SchemaColumn::new(0, None, ValueType::Bool, true)
+            .with_constraints(vec![RuleConfig::Pattern {
+                pattern: String::from("("),
+            }])]);
+        assert!(AssertSchema::new(schema).is_err());
+    }
+
+    #[test]
+    fn add_item_on_conflict() {
+        let mut c = DataCellRow::new();
+        c.push(DataCell::new(String::from("col1"), 0, Value::Bool(true)).unwrap());
+        let add = |on_conflict| {
+            AddItemStatic(
+                DataCell::new(String::from("col2"), 0, Value::Bool(false)).unwrap(),
+                on_conflict,
+            )
+        };
+
+        add(Some(OnConflict::Skip)).transrich(&mut c).unwrap();
+        assert_eq!(1, c.len());
+        assert_eq!("col1", c.get_by_idx(0).unwrap().get_name());
+
+        assert_eq!(
+            Err(VenumTdsError::DataAccess(DataAccessErrors::DuplicateIdx {
+                idx: 0
+            })),
+            add(Some(OnConflict::Error)).transrich(&mut c)
+        );
+
+        add(Some(OnConflict::Replace)).transrich(&mut c).unwrap();
+        assert_eq!(1, c.len());
+        assert_eq!("col2", c.get_by_idx(0).unwrap().get_name());
+
+        // legacy behaviour, unless the row is strict
+        add(None).transrich(&mut c).unwrap();
+        assert_eq!(2, c.len());
+        c.del_by_idx(0).unwrap();
+        c.set_strict(true);
+        assert!(add(None).transrich(&mut c).is_err());
+    }
+
     #[test]
     fn add_item_runtime_current_date_time_utc_as_fixed_offset() {
         let mut c1 = DataCellRow::new();
@@ -262,6 +1075,7 @@ mod tests {
             header: Some(String::from("col1")),
             idx: 0,
             rtv: RuntimeValue::CurrentDateTimeUtcAsFixedOffset,
+            on_conflict: None,
         };
         container_transricher.transrich(&mut c1).unwrap();
         assert_eq!(1, c1.len());
@@ -338,6 +1152,8 @@ mod tests {
             target_data_type: ValueType::String,
             target_header: Some(String::from("bool-2-string")),
             target_idx: 1,
+            on_conflict: None,
+            number_format: None,
         };
 
         container_transricher.transrich(&mut c).unwrap();
@@ -364,11 +1180,10 @@ mod tests {
 
         let mut transrichers: Vec<Box<dyn TransrichInplace>> = Vec::with_capacity(4);
 
-        transrichers.push(Box::new(AddItemStatic(DataCell::new_without_data(
-            ValueType::Bool,
-            String::from("col3"),
-            2,
-        ))));
+        transrichers.push(Box::new(AddItemStatic(
+            DataCell::new_without_data(ValueType::Bool, String::from("col3"), 2),
+            None,
+        )));
         transrichers.push(Box::new(DeleteItemAtIdx(1)));
         transrichers.push(Box::new(DeleteItemAtIdx(2)));
         transrichers.push(Box::new(MutateItemIdx::new(0, 10)));
@@ -547,4 +1362,177 @@ mod tests {
 
         split_item_at_idx.transrich(&mut c).unwrap();
     }
+
+    #[test]
+    fn map_item_value() {
+        let mut c = DataCellRow::new();
+        c.push(DataCell::new(String::from("ccy"), 0, Value::String(String::from("CHF"))).unwrap());
+
+        let mut m = MapItemValue {
+            idx: 0,
+            target_idx: Some(1),
+            target_header: Some(String::from("is_chf")),
+            key_type: None,
+            value_type: ValueType::Bool,
+            table: HashMap::from([(String::from("CHF"), Value::Bool(true))]),
+            default: None,
+            strict: false,
+        };
+        m.transrich(&mut c).unwrap();
+        assert_eq!(
+            &Value::Bool(true),
+            c.get_by_name("is_chf").unwrap().get_data()
+        );
+
+        // miss, without default: the target gets None
+        c.get_by_idx_mut(0)
+            .unwrap()
+            .set_data(Value::String(String::from("EUR")));
+        c.del_by_idx(1).unwrap();
+        m.transrich(&mut c).unwrap();
+        assert_eq!(&Value::None, c.get_by_idx(1).unwrap().get_data());
+
+        // miss, strict
+        c.del_by_idx(1).unwrap();
+        m.strict = true;
+        assert_eq!(
+            Err(VenumTdsError::ContainerOps(
+                ContainerOpsErrors::MapValueMiss {
+                    idx: 0,
+                    key: String::from("EUR")
+                }
+            )),
+            m.transrich(&mut c)
+        );
+
+        // miss, with default, in place
+        m.target_idx = None;
+        m.default = Some(Value::Bool(false));
+        m.transrich(&mut c).unwrap();
+        let dc = c.get_by_idx(0).unwrap();
+        assert_eq!(&ValueType::Bool, dc.get_type_info());
+        assert_eq!(&Value::Bool(false), dc.get_data());
+    }
+
+    #[test]
+    fn modify_items() {
+        let mut c = DataCellRow::new();
+        c.push(DataCell::new(String::from("a"), 0, Value::String(String::from(" chf "))).unwrap());
+        c.push(DataCell::new_without_data(
+            ValueType::String,
+            String::from("b"),
+            1,
+        ));
+        c.push(DataCell::new(String::from("c"), 2, Value::Int32(1)).unwrap());
+
+        let mut m = ModifyItems {
+            idxs: vec![0, 1],
+            modifiers: vec![
+                StringModifier::Trim {
+                    side: TrimSide::Both,
+                    chars: None,
+                },
+                StringModifier::Case(CaseMode::Upper),
+            ],
+        };
+        m.transrich(&mut c).unwrap();
+        assert_eq!(
+            &Value::String(String::from("CHF")),
+            c.get_by_idx(0).unwrap().get_data()
+        );
+        assert_eq!(&Value::None, c.get_by_idx(1).unwrap().get_data());
+
+        m.idxs = vec![2];
+        assert!(m.transrich(&mut c).is_err());
+    }
+
+    #[test]
+    fn add_item_copy_convert_as_number_format() {
+        let mut c = DataCellRow::new();
+        c.push(
+            DataCell::new(
+                String::from("amount"),
+                0,
+                Value::String(String::from("(1.234,50)")),
+            )
+            .unwrap(),
+        );
+
+        let container_transricher = AddItemCopyConvertAs {
+            src_idx: 0,
+            target_data_type: ValueType::Float64,
+            target_header: None,
+            target_idx: 1,
+            on_conflict: None,
+            number_format: Some(NumberFormat {
+                decimal_separator: ',',
+                grouping_separator: Some('.'),
+                parenthesized_negative: true,
+                ..NumberFormat::default()
+            }),
+        };
+
+        container_transricher.transrich(&mut c).unwrap();
+        assert_eq!(
+            &Value::Float64(-1234.5),
+            c.get_by_idx(1).unwrap().get_data()
+        );
+    }
+
+    #[test]
+    fn round_items() {
+        let mut c = DataCellRow::new();
+        c.push(DataCell::new(String::from("a"), 0, Value::Float64(1.005_1)).unwrap());
+        c.push(DataCell::new_without_data(
+            ValueType::Float32,
+            String::from("b"),
+            1,
+        ));
+        c.push(DataCell::new(String::from("c"), 2, Value::Int32(1)).unwrap());
+
+        let mut r = RoundItems {
+            idxs: vec![0, 1],
+            scale: 2,
+            mode: RoundingMode::HalfUp,
+        };
+        r.transrich(&mut c).unwrap();
+        assert_eq!(&Value::Float64(1.01), c.get_by_idx(0).unwrap().get_data());
+        assert_eq!(&Value::None, c.get_by_idx(1).unwrap().get_data());
+
+        r.idxs = vec![2];
+        assert!(r.transrich(&mut c).is_err());
+    }
+
+    #[test]
+    fn when() {
+        let when = When {
+            condition: RowCondition::Equals {
+                idx: 0,
+                value: String::from("D"),
+            },
+            then: vec![Box::new(DeleteItemAtIdx(1))],
+            otherwise: vec![Box::new(DeleteItemAtIdx(0))],
+        };
+        let row = |rec_type: &str| {
+            let mut c = DataCellRow::new();
+            c.push(
+                DataCell::new(
+                    String::from("type"),
+                    0,
+                    Value::String(String::from(rec_type)),
+                )
+                .unwrap(),
+            );
+            c.push(DataCell::new(String::from("data"), 1, Value::Int32(1)).unwrap());
+            c
+        };
+
+        let mut detail = row("D");
+        when.transrich(&mut detail).unwrap();
+        assert_eq!(vec!["type"], detail.headers());
+
+        let mut header = row("H");
+        when.transrich(&mut header).unwrap();
+        assert_eq!(vec!["data"], header.headers());
+    }
 }
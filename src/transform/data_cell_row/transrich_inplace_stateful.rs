@@ -1,8 +1,21 @@
 use std::fmt::Debug;
 
 use venum::value::Value;
+#[cfg(feature = "jsonconf")]
+use venum::value_type::ValueType;
 
-use crate::{data_cell::DataCell, data_cell_row::DataCellRow, errors::Result};
+#[cfg(feature = "jsonconf")]
+use crate::conf::jsonconf::{
+    AddItemConfig, AddItemType, ColumnRulesConfig, ItemTargetConfig, TransformerConfig,
+    ValidateItemsConfig,
+};
+use crate::{
+    constraints::{RowValidator, ValidationReport},
+    data_cell::DataCell,
+    data_cell_row::{DataCellRow, OnConflict},
+    errors::{ContainerOpsErrors, Result, VenumTdsError},
+    transform::description::Description,
+};
 
 /// We get all info we need from the (static) transrichment config, we need to maintain state though, meaning, this CANNOT be reused!
 /// In fact, this is (and enriching metadata values at runtime, i.e. with info from something exteranl) the main reason, why we cannot
@@ -10,10 +23,24 @@ use crate::{data_cell::DataCell, data_cell_row::DataCellRow, errors::Result};
 /// (The only workaround would be maintaining state outside of the implementing structs below)
 pub trait TransrichInplaceStateful: Debug {
     fn transrich(&mut self, data_cell_row: &mut DataCellRow) -> Result<()>;
+
+    /// Transrichers that collect a report along the way (i.e. `ValidateItems`), hand it out here.
+    fn validation_report(&self) -> Option<&ValidationReport> {
+        None
+    }
+
+    /// What this transricher does, leaving out any state, see `Description`.
+    fn describe(&self) -> Description;
+
+    /// The config this transricher is built from, if it can be written as one, see
+    /// `TransrichPass::to_config`.
+    #[cfg(feature = "jsonconf")]
+    fn to_config(&self) -> Option<TransformerConfig>;
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
-#[cfg_attr(feature = "jsonconf", derive(serde::Deserialize))]
+#[cfg_attr(feature = "jsonconf", derive(serde::Deserialize, serde::Serialize))]
+#[cfg_attr(feature = "jsonschema", derive(schemars::JsonSchema))]
 pub enum RuntimeValueStateful {
     RowEnumeration,
 }
@@ -23,6 +50,7 @@ pub struct AddItemRuntimeStatefulRowEnum {
     num_invoke: Option<u128>,
     pub header: Option<String>,
     pub idx: usize,
+    pub on_conflict: Option<OnConflict>,
 }
 impl TransrichInplaceStateful for AddItemRuntimeStatefulRowEnum {
     fn transrich(&mut self, data_cell_row: &mut DataCellRow) -> Result<()> {
@@ -36,9 +64,31 @@ impl TransrichInplaceStateful for AddItemRuntimeStatefulRowEnum {
             self.idx,
             Value::UInt128(self.num_invoke.unwrap()), // we set it right above!
         )?;
-        data_cell_row.push(curr_enum_cell);
+        data_cell_row.push_checked(curr_enum_cell, self.on_conflict)
+    }
 
-        Ok(())
+    fn describe(&self) -> Description {
+        Description::new("AddItemRuntimeStatefulRowEnum")
+            .param("header", &self.header)
+            .param("idx", self.idx)
+            .param("on_conflict", self.on_conflict)
+    }
+
+    #[cfg(feature = "jsonconf")]
+    fn to_config(&self) -> Option<TransformerConfig> {
+        Some(TransformerConfig::AddItem {
+            cfg: AddItemConfig {
+                spec: AddItemType::RuntimeStateful {
+                    rt_value: RuntimeValueStateful::RowEnumeration,
+                },
+                target: ItemTargetConfig {
+                    idx: self.idx,
+                    header: self.header.clone(),
+                    target_type: ValueType::UInt128,
+                },
+                on_conflict: self.on_conflict,
+            },
+        })
     }
 }
 impl AddItemRuntimeStatefulRowEnum {
@@ -47,7 +97,61 @@ impl AddItemRuntimeStatefulRowEnum {
             num_invoke: None,
             header,
             idx,
+            on_conflict: None,
+        }
+    }
+
+    pub fn with_on_conflict(mut self, on_conflict: Option<OnConflict>) -> Self {
+        self.on_conflict = on_conflict;
+        self
+    }
+}
+
+/// Checks every row with a `RowValidator`. Broken rules are recorded in the validation report and,
+/// if `fail_row` is set, also fail the row.
+#[derive(Debug)]
+pub struct ValidateItems {
+    pub validator: RowValidator,
+    pub fail_row: bool,
+}
+impl TransrichInplaceStateful for ValidateItems {
+    fn transrich(&mut self, data_cell_row: &mut DataCellRow) -> Result<()> {
+        let violations = self.validator.validate(data_cell_row);
+        if self.fail_row && !violations.is_empty() {
+            return Err(VenumTdsError::ContainerOps(
+                ContainerOpsErrors::ValidationError {
+                    violations: violations.to_vec(),
+                },
+            ));
         }
+        Ok(())
+    }
+
+    fn validation_report(&self) -> Option<&ValidationReport> {
+        Some(self.validator.report())
+    }
+
+    fn describe(&self) -> Description {
+        Description::new("ValidateItems")
+            .param("columns", self.validator.columns())
+            .param("unique", self.validator.unique())
+            .param("fail_row", self.fail_row)
+    }
+
+    #[cfg(feature = "jsonconf")]
+    fn to_config(&self) -> Option<TransformerConfig> {
+        Some(TransformerConfig::ValidateItems {
+            cfg: ValidateItemsConfig {
+                columns: self
+                    .validator
+                    .columns()
+                    .iter()
+                    .map(ColumnRulesConfig::from)
+                    .collect(),
+                unique: self.validator.unique().to_vec(),
+                fail_row: Some(self.fail_row),
+            },
+        })
     }
 }
 
@@ -55,9 +159,11 @@ impl AddItemRuntimeStatefulRowEnum {
 mod tests {
     use venum::value::Value;
 
+    use crate::constraints::{ColumnRules, RowValidator, Rule};
+    use crate::data_cell::DataCell;
     use crate::data_cell_row::DataCellRow;
     use crate::transform::data_cell_row::transrich_inplace_stateful::{
-        AddItemRuntimeStatefulRowEnum, TransrichInplaceStateful,
+        AddItemRuntimeStatefulRowEnum, TransrichInplaceStateful, ValidateItems,
     };
 
     #[test]
@@ -76,4 +182,37 @@ mod tests {
 
         assert_eq!(&Value::UInt128(2), c2.get_by_idx(0).unwrap().get_data());
     }
+
+    #[test]
+    fn validate_items() {
+        let mut validate = ValidateItems {
+            validator: RowValidator::new(
+                vec![ColumnRules {
+                    idx: 0,
+                    rules: vec![Rule::Range {
+                        min: Some(Value::Int32(0)),
+                        max: None,
+                    }],
+                }],
+                vec![vec![0]],
+            ),
+            fail_row: false,
+        };
+
+        for i in [1, -1, 1] {
+            let mut c = DataCellRow::new();
+            c.push(DataCell::new(String::from("col1"), 0, Value::Int32(i)).unwrap());
+            validate.transrich(&mut c).unwrap();
+        }
+
+        let report = validate.validation_report().unwrap();
+        assert_eq!(3, report.rows_checked);
+        assert_eq!(vec![2, 3], report.failed_rows());
+        assert_eq!("unique", report.violations[1].rule);
+
+        validate.fail_row = true;
+        let mut c = DataCellRow::new();
+        c.push(DataCell::new(String::from("col1"), 0, Value::Int32(-5)).unwrap());
+        assert!(validate.transrich(&mut c).is_err());
+    }
 }
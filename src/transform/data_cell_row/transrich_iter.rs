@@ -0,0 +1,189 @@
+use crate::{data_cell_row::DataCellRow, errors::Result};
+
+use super::transrich_pass::TransrichPasses;
+
+/// The rows `TransrichIterExt::transrich` works on: plain rows, or the results of a reader (e.g.
+/// `CsvRowReader`).
+pub trait IntoRowResult {
+    fn into_row_result(self) -> Result<DataCellRow>;
+}
+
+impl IntoRowResult for DataCellRow {
+    fn into_row_result(self) -> Result<DataCellRow> {
+        Ok(self)
+    }
+}
+
+impl IntoRowResult for Result<DataCellRow> {
+    fn into_row_result(self) -> Result<DataCellRow> {
+        self
+    }
+}
+
+/// Lazily transriches the rows of an iterator, see `TransrichIterExt::transrich`.
+pub struct TransrichIter<'a, I> {
+    rows: I,
+    passes: &'a mut TransrichPasses,
+    /// The remaining rows an exploding pass turned the last row into.
+    pending: std::vec::IntoIter<DataCellRow>,
+}
+
+impl<I> Iterator for TransrichIter<'_, I>
+where
+    I: Iterator,
+    I::Item: IntoRowResult,
+{
+    type Item = Result<DataCellRow>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(row) = self.pending.next() {
+                return Some(Ok(row));
+            }
+            // rows that are filtered out (i.e. turned into no rows at all) just take another round
+            let rows = self
+                .rows
+                .next()?
+                .into_row_result()
+                .and_then(|row| self.passes.transrich_rows(row));
+            match rows {
+                Ok(rows) => self.pending = rows.into_iter(),
+                Err(e) => return Some(Err(e)),
+            }
+        }
+    }
+}
+
+pub trait TransrichIterExt: Iterator + Sized
+where
+    Self::Item: IntoRowResult,
+{
+    /// Transriches the rows one by one, while iterating, i.e. only the current row (and the rows
+    /// an exploding pass turned it into) is held in memory. Every row results in zero, one or many
+    /// rows, see `TransrichPasses::transrich_rows`. A failing row (or an error of the underlying
+    /// reader) results in an error, after which the iteration goes on with the next row. (Passes
+    /// with table transrichers can't be used this way, they fail every row.)
+    fn transrich(self, passes: &mut TransrichPasses) -> TransrichIter<'_, Self> {
+        TransrichIter {
+            rows: self,
+            passes,
+            pending: Vec::new().into_iter(),
+        }
+    }
+}
+
+impl<I> TransrichIterExt for I
+where
+    I: Iterator,
+    I::Item: IntoRowResult,
+{
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::Cell;
+
+    use venum::value::Value;
+    use venum::value_type::ValueType;
+
+    use crate::{
+        data_cell::DataCell,
+        data_cell_row::ColumnRef,
+        errors::VenumTdsError,
+        transform::{
+            data_cell_row::{
+                transrich_explode::ExplodeItem, transrich_inplace::DeleteItemAtIdx,
+                transrich_pass::TransrichPass,
+            },
+            data_cell_table::dedup::DedupStream,
+            value::spliting::ValueStringSeparatorCharTokens,
+        },
+    };
+
+    use super::*;
+
+    fn row(id: i32, tags: &str) -> DataCellRow {
+        let mut row = DataCellRow::new();
+        row.push(DataCell::new(String::from("id"), 0, Value::Int32(id)).unwrap());
+        row.push(
+            DataCell::new(String::from("tags"), 1, Value::String(String::from(tags))).unwrap(),
+        );
+        row
+    }
+
+    fn passes() -> TransrichPasses {
+        TransrichPasses(vec![
+            TransrichPass::new(Vec::new(), Vec::new(), None).with_exploding(vec![Box::new(
+                DedupStream::new(Some(vec![ColumnRef::Idx(0)]), None),
+            )]),
+            TransrichPass::new(Vec::new(), Vec::new(), None).with_exploding(vec![Box::new(
+                ExplodeItem {
+                    idx: 1,
+                    splitter: ValueStringSeparatorCharTokens { sep_char: ',' },
+                    target_type: ValueType::String,
+                    keep_empty: false,
+                },
+            )]),
+        ])
+    }
+
+    fn tags(rows: &[DataCellRow]) -> Vec<&Value> {
+        rows.iter()
+            .map(|row| row.get_by_idx(1).unwrap().get_data())
+            .collect()
+    }
+
+    #[test]
+    fn transrich_lazily() {
+        let pulled = Cell::new(0);
+        let rows = vec![row(1, "a,b"), row(1, "c"), row(2, ""), row(3, "d")];
+        let mut passes = passes();
+        let mut iter = rows
+            .into_iter()
+            .inspect(|_| pulled.set(pulled.get() + 1))
+            .transrich(&mut passes);
+
+        assert_eq!(
+            &Value::String(String::from("a")),
+            iter.next()
+                .unwrap()
+                .unwrap()
+                .get_by_idx(1)
+                .unwrap()
+                .get_data()
+        );
+        assert_eq!(1, pulled.get());
+        iter.next().unwrap().unwrap();
+        assert_eq!(1, pulled.get());
+
+        // the duplicate and the row without tags are filtered out
+        let rest = iter.collect::<Result<Vec<_>>>().unwrap();
+        assert_eq!(vec![&Value::String(String::from("d"))], tags(&rest));
+        assert_eq!(4, pulled.get());
+    }
+
+    #[test]
+    fn transrich_errors() {
+        let mut passes = TransrichPasses(vec![TransrichPass::new(
+            vec![Box::new(DeleteItemAtIdx(1))],
+            Vec::new(),
+            None,
+        )]);
+        let mut without_tags = row(3, "c");
+        without_tags.del_by_idx(1).unwrap();
+        let rows: Vec<Result<DataCellRow>> = vec![
+            Ok(row(1, "a")),
+            Err(VenumTdsError::Generic {
+                msg: String::from("broken line"),
+            }),
+            Ok(without_tags),
+            Ok(row(2, "b")),
+        ];
+        // errors of the reader and of the passes don't end the iteration
+        let res: Vec<Result<DataCellRow>> = rows.into_iter().transrich(&mut passes).collect();
+        assert_eq!(4, res.len());
+        assert!(res[1].is_err());
+        assert!(res[2].is_err());
+        assert_eq!(1, res[3].as_ref().unwrap().len());
+    }
+}
@@ -1,16 +1,33 @@
 use std::fmt::Debug;
 
-use crate::{data_cell_row::DataCellRow, errors::Result};
+#[cfg(feature = "jsonconf")]
+use crate::{
+    conf::jsonconf::{ConfigRoot, OrderItemsEntry, OrderItemsOptions, TransformEnrichPassConfig},
+    transform::data_cell_row::transrich_inplace::{OrderStep, OrderValidation},
+};
+use crate::{
+    constraints::ValidationReport,
+    data_cell_row::DataCellRow,
+    data_cell_table::DataCellTable,
+    errors::{Result, TransformErrors, VenumTdsError},
+    transform::{data_cell_table::transrich_table::TransrichTable, description::PassDescription},
+};
 
 use super::{
-    transrich_inplace::TransrichInplace, transrich_inplace_stateful::TransrichInplaceStateful,
+    trace::{PassTrace, StepOutcome, TransrichStage, TransrichTrace},
+    transrich_explode::TransrichExplode,
+    transrich_inplace::TransrichInplace,
+    transrich_inplace_stateful::TransrichInplaceStateful,
 };
 
 #[derive(Debug)]
 pub struct TransrichPass {
     stateless_transrichers: Vec<Box<dyn TransrichInplace + Send + Sync>>,
     stateful_transrichers: Vec<Box<dyn TransrichInplaceStateful + Send>>,
+    exploding_transrichers: Vec<Box<dyn TransrichExplode + Send + Sync>>,
     ordering_transrichers: Option<Vec<Box<dyn TransrichInplace + Send + Sync>>>,
+    table_transrichers: Vec<Box<dyn TransrichTable + Send + Sync>>,
+    strict: bool,
 }
 
 impl TransrichPass {
@@ -22,13 +39,106 @@ impl TransrichPass {
         Self {
             stateless_transrichers: transformer,
             stateful_transrichers: transformer_stateful,
+            exploding_transrichers: Vec::new(),
             ordering_transrichers: order,
+            table_transrichers: Vec::new(),
+            strict: false,
+        }
+    }
+
+    /// Puts every row into strict mode (see `DataCellRow::set_strict`), while it goes through this
+    /// pass. Afterwards, the rows are as strict as before.
+    pub fn with_strict(mut self, strict: bool) -> Self {
+        self.strict = strict;
+        self
+    }
+
+    /// Adds transrichers, that turn one row into zero, one or many rows. They run after the stateless
+    /// and stateful ones, but before the ordering, which is applied to every resulting row.
+    pub fn with_exploding(
+        mut self,
+        transformer_exploding: Vec<Box<dyn TransrichExplode + Send + Sync>>,
+    ) -> Self {
+        self.exploding_transrichers = transformer_exploding;
+        self
+    }
+
+    /// Whether this pass may turn one row into zero or many rows.
+    pub fn is_exploding(&self) -> bool {
+        !self.exploding_transrichers.is_empty()
+    }
+
+    /// Adds transrichers, that work on all rows at once, e.g. reshaping. They run after every row
+    /// went through the rest of the pass (including the ordering), so such a pass can only be used
+    /// through `transrich_table`.
+    pub fn with_table(
+        mut self,
+        transformer_table: Vec<Box<dyn TransrichTable + Send + Sync>>,
+    ) -> Self {
+        self.table_transrichers = transformer_table;
+        self
+    }
+
+    /// The config of this pass, built from the configs of its transrichers (see
+    /// `TransrichInplace::to_config`), e.g. to write it with `ConfigRoot::to_string_with_format`.
+    /// The transformers are in the order they run in, i.e. stateless, stateful, then exploding.
+    /// Values taken from the enrichment map become static ones. If any transricher can't be
+    /// written as config (e.g. discarding rows to a channel), or the ordering doesn't fit
+    /// `orderItems`, there is none.
+    #[cfg(feature = "jsonconf")]
+    pub fn to_config(&self) -> Option<TransformEnrichPassConfig> {
+        let mut transformers = self
+            .stateless_transrichers
+            .iter()
+            .map(|tri| tri.to_config())
+            .collect::<Option<Vec<_>>>()?;
+        for tri in &self.stateful_transrichers {
+            transformers.push(tri.to_config()?);
         }
+        for tri in &self.exploding_transrichers {
+            transformers.push(tri.to_config()?);
+        }
+        let (order_items, order_items_options) = match &self.ordering_transrichers {
+            Some(orderings) => {
+                let (entries, opts) = order_config(orderings)?;
+                (Some(entries), Some(opts))
+            }
+            None => (None, None),
+        };
+        let table_transformers = self
+            .table_transrichers
+            .iter()
+            .map(|tri| tri.to_config())
+            .collect::<Option<Vec<_>>>()?;
+        Some(TransformEnrichPassConfig {
+            comment: None,
+            strict: Some(self.strict),
+            transformers,
+            order_items,
+            order_items_options,
+            table_transformers: (!table_transformers.is_empty()).then_some(table_transformers),
+        })
+    }
+
+    /// Whether this pass needs all rows at once, see `with_table`.
+    pub fn is_table(&self) -> bool {
+        !self.table_transrichers.is_empty()
+    }
+
+    fn check_row_wise(&self) -> Result<()> {
+        if self.is_table() {
+            return Err(VenumTdsError::Transform(TransformErrors::Generic {
+                msg: String::from(
+                    "the pass has table transformers, it can only be used with transrich_table",
+                ),
+            }));
+        }
+        Ok(())
     }
 }
 
 impl TransrichPass {
-    pub fn transrich(&mut self, container: &mut DataCellRow) -> Result<()> {
+    fn transrich_items(&mut self, container: &mut DataCellRow) -> Result<()> {
         self.stateless_transrichers
             .iter()
             .try_for_each(|tri| tri.transrich(container))?;
@@ -36,13 +146,243 @@ impl TransrichPass {
         // This is the reason why the transrich function needs to take &mut self, instead of just &self
         self.stateful_transrichers
             .iter_mut()
-            .try_for_each(|tri| tri.transrich(container))?;
+            .try_for_each(|tri| tri.transrich(container))
+    }
 
+    fn order_items(&self, container: &mut DataCellRow) -> Result<()> {
         if let Some(orderings) = &self.ordering_transrichers {
             orderings.iter().try_for_each(|o| o.transrich(container))?;
         }
         Ok(())
     }
+
+    /// Transriches the row in place. If the pass is exploding, it must result in exactly one row,
+    /// otherwise this is an error. (Use `transrich_rows` for those.) On errors of an exploding pass,
+    /// the row is left empty.
+    pub fn transrich(&mut self, container: &mut DataCellRow) -> Result<()> {
+        self.check_row_wise()?;
+        if self.is_exploding() {
+            let mut rows = self.transrich_rows(std::mem::take(container))?;
+            if rows.len() != 1 {
+                return Err(VenumTdsError::Transform(TransformErrors::Generic {
+                    msg: format!(
+                        "expected exactly one row, but the pass resulted in {}. Use transrich_rows.",
+                        rows.len()
+                    ),
+                }));
+            }
+            *container = rows.remove(0);
+            return Ok(());
+        }
+        let was_strict = container.is_strict();
+        container.set_strict(was_strict || self.strict);
+        let res = match self.transrich_items(container) {
+            Ok(()) => self.order_items(container),
+            Err(e) => Err(e),
+        };
+        container.set_strict(was_strict);
+        res
+    }
+
+    /// Transriches the row into zero, one or many rows (see `with_exploding`).
+    pub fn transrich_rows(&mut self, container: DataCellRow) -> Result<Vec<DataCellRow>> {
+        self.check_row_wise()?;
+        self.transrich_row(container)
+    }
+
+    fn transrich_row(&mut self, mut container: DataCellRow) -> Result<Vec<DataCellRow>> {
+        let was_strict = container.is_strict();
+        container.set_strict(was_strict || self.strict);
+        self.transrich_items(&mut container)?;
+
+        let mut rows = vec![container];
+        for tri in &self.exploding_transrichers {
+            let mut exploded: Vec<DataCellRow> = Vec::with_capacity(rows.len());
+            for row in rows {
+                exploded.extend(tri.explode(row)?);
+            }
+            rows = exploded;
+        }
+
+        for row in rows.iter_mut() {
+            self.order_items(row)?;
+            row.set_strict(was_strict);
+        }
+        Ok(rows)
+    }
+
+    /// Transriches every row of the table (see `transrich_rows`), then runs the table transrichers on
+    /// the result (see `with_table`).
+    pub fn transrich_table(&mut self, table: DataCellTable) -> Result<DataCellTable> {
+        let mut res = DataCellTable::with_capacity(table.len());
+        for row in table {
+            res.extend(self.transrich_row(row)?);
+        }
+        self.table_transrichers
+            .iter()
+            .try_fold(res, |table, tri| tri.transrich(table))
+    }
+
+    /// Same as `transrich`, but records what every single transricher did to the row. (For debugging.)
+    /// Like `transrich`, this fails for passes with table transrichers.
+    ///
+    /// Exploding transrichers run for real, i.e. any state they keep is updated (e.g. `DedupStream`
+    /// records the keys it has seen, and writes dropped rows to its discard sink). If one of them
+    /// explodes the row, only the first resulting row is traced further (see `StepOutcome`). If one
+    /// filters the row out, the trace ends there, and the row is left as it was before that step.
+    pub fn transrich_traced(
+        &mut self,
+        container: &mut DataCellRow,
+        trace: &mut PassTrace,
+    ) -> Result<()> {
+        self.check_row_wise()?;
+        let was_strict = container.is_strict();
+        container.set_strict(was_strict || self.strict);
+        let res = self.trace_steps(container, trace);
+        container.set_strict(was_strict);
+        res
+    }
+
+    fn trace_steps(&mut self, container: &mut DataCellRow, trace: &mut PassTrace) -> Result<()> {
+        for tri in &self.stateless_transrichers {
+            trace.trace_step(
+                TransrichStage::Stateless,
+                tri.describe().to_string(),
+                container,
+                |c| tri.transrich(c),
+            )?;
+        }
+        for tri in self.stateful_transrichers.iter_mut() {
+            let label = tri.describe().to_string();
+            trace.trace_step(TransrichStage::Stateful, label, container, |c| {
+                tri.transrich(c)
+            })?;
+        }
+        for tri in &self.exploding_transrichers {
+            // we can only follow a single row, so we go with the first one
+            let outcome = trace.trace_step_with_outcome(
+                TransrichStage::Exploding,
+                tri.describe().to_string(),
+                container,
+                |c| {
+                    let mut rows = tri.explode(c.clone())?;
+                    Ok(match rows.len() {
+                        0 => StepOutcome::Filtered,
+                        1 => {
+                            *c = rows.swap_remove(0);
+                            StepOutcome::Transriched
+                        }
+                        n => {
+                            *c = rows.swap_remove(0);
+                            StepOutcome::Exploded(n)
+                        }
+                    })
+                },
+            )?;
+            if outcome == StepOutcome::Filtered {
+                return Ok(());
+            }
+        }
+        if let Some(orderings) = &self.ordering_transrichers {
+            for o in orderings {
+                trace.trace_step(
+                    TransrichStage::Ordering,
+                    o.describe().to_string(),
+                    container,
+                    |c| o.transrich(c),
+                )?;
+            }
+        }
+        Ok(())
+    }
+
+    /// What this pass does, transricher by transricher. Unlike the `Debug` output, this leaves out
+    /// any state, so two passes built from the same config are equal, and hash the same.
+    pub fn describe(&self) -> PassDescription {
+        PassDescription {
+            stateless: self
+                .stateless_transrichers
+                .iter()
+                .map(|tri| tri.describe())
+                .collect(),
+            stateful: self
+                .stateful_transrichers
+                .iter()
+                .map(|tri| tri.describe())
+                .collect(),
+            exploding: self
+                .exploding_transrichers
+                .iter()
+                .map(|tri| tri.describe())
+                .collect(),
+            ordering: self
+                .ordering_transrichers
+                .as_ref()
+                .map(|orderings| orderings.iter().map(|o| o.describe()).collect()),
+            table: self
+                .table_transrichers
+                .iter()
+                .map(|tri| tri.describe())
+                .collect(),
+            strict: self.strict,
+        }
+    }
+
+    /// The reports of all validating transrichers (see `ValidateItems`) of this pass.
+    pub fn validation_reports(&self) -> Vec<&ValidationReport> {
+        self.stateful_transrichers
+            .iter()
+            .filter_map(|tri| tri.validation_report())
+            .collect()
+    }
+}
+
+/// The `orderItems` (and options) the ordering is built from, see `TransrichPass::try_from`. That
+/// is, entries of a single mode, then maybe compacting and validating, and sorting last.
+#[cfg(feature = "jsonconf")]
+fn order_config(
+    orderings: &[Box<dyn TransrichInplace + Send + Sync>],
+) -> Option<(Vec<OrderItemsEntry>, OrderItemsOptions)> {
+    let mut steps = orderings
+        .iter()
+        .map(|o| o.order_step())
+        .collect::<Option<Vec<OrderStep>>>()?;
+    if steps.pop()? != OrderStep::Sort {
+        return None;
+    }
+    let mut entries: Vec<OrderItemsEntry> = Vec::with_capacity(steps.len());
+    let mut mode = None;
+    let (mut compact, mut validation) = (false, OrderValidation::None);
+    for step in steps {
+        match step {
+            OrderStep::Entry { from, to, mode: m }
+                if !compact && validation == OrderValidation::None =>
+            {
+                // all entries share the mode of the config
+                if *mode.get_or_insert(m) != m {
+                    return None;
+                }
+                entries.push(OrderItemsEntry { from, to });
+            }
+            OrderStep::Compact if !compact && validation == OrderValidation::None => {
+                compact = true;
+            }
+            OrderStep::Validate(v)
+                if validation == OrderValidation::None && v != OrderValidation::None =>
+            {
+                validation = v;
+            }
+            _ => return None,
+        }
+    }
+    Some((
+        entries,
+        OrderItemsOptions {
+            mode: Some(mode.unwrap_or_default()),
+            validation: Some(validation),
+            compact: Some(compact),
+        },
+    ))
 }
 
 pub struct TransrichPasses(pub Vec<TransrichPass>);
@@ -53,6 +393,83 @@ impl TransrichPasses {
             .iter_mut()
             .try_for_each(|pass| pass.transrich(container))
     }
+
+    /// Transriches the row into zero, one or many rows, i.e. every row coming out of a pass goes
+    /// through the next one.
+    pub fn transrich_rows(&mut self, container: DataCellRow) -> Result<Vec<DataCellRow>> {
+        let mut rows = vec![container];
+        for pass in self.0.iter_mut() {
+            let mut next: Vec<DataCellRow> = Vec::with_capacity(rows.len());
+            for row in rows {
+                next.extend(pass.transrich_rows(row)?);
+            }
+            rows = next;
+        }
+        Ok(rows)
+    }
+
+    /// Whether any of the passes may turn one row into zero or many rows.
+    pub fn is_exploding(&self) -> bool {
+        self.0.iter().any(|pass| pass.is_exploding())
+    }
+
+    /// Transriches all rows of the table, pass by pass, i.e. every pass sees all rows coming out of
+    /// the previous one. This is the only way to use passes with table transrichers.
+    pub fn transrich_table(&mut self, table: DataCellTable) -> Result<DataCellTable> {
+        self.0
+            .iter_mut()
+            .try_fold(table, |table, pass| pass.transrich_table(table))
+    }
+
+    /// Whether any of the passes needs all rows at once (see `TransrichPass::with_table`).
+    pub fn is_table(&self) -> bool {
+        self.0.iter().any(|pass| pass.is_table())
+    }
+
+    /// Same as `transrich`, but records the state of the row after every transricher and every pass.
+    /// In case of an error, the trace contains everything up to (and including) the failing step. If
+    /// a pass filters the row out, the trace ends with that pass (see `TransrichPass::transrich_traced`).
+    pub fn transrich_traced(
+        &mut self,
+        container: &mut DataCellRow,
+        trace: &mut TransrichTrace,
+    ) -> Result<()> {
+        for pass in self.0.iter_mut() {
+            let mut pass_trace = PassTrace::new(container);
+            let res = pass.transrich_traced(container, &mut pass_trace);
+            pass_trace.finish(container);
+            let filtered = pass_trace.is_filtered();
+            trace.passes.push(pass_trace);
+            res?;
+            if filtered {
+                break;
+            }
+        }
+        Ok(())
+    }
+
+    /// What the passes do, pass by pass, see `TransrichPass::describe`.
+    pub fn describe(&self) -> Vec<PassDescription> {
+        self.0.iter().map(|pass| pass.describe()).collect()
+    }
+
+    /// The config of the passes, if every pass has one, see `TransrichPass::to_config`.
+    #[cfg(feature = "jsonconf")]
+    pub fn to_config(&self) -> Option<ConfigRoot> {
+        self.0
+            .iter()
+            .map(|pass| pass.to_config())
+            .collect::<Option<_>>()
+            .map(ConfigRoot)
+    }
+
+    /// The reports of all validating transrichers (see `ValidateItems`) of all passes.
+    pub fn validation_reports(&self) -> Vec<&ValidationReport> {
+        self.0
+            .iter()
+            .flat_map(|pass| pass.validation_reports())
+            .collect()
+    }
 }
 
 #[cfg(test)]
@@ -66,14 +483,17 @@ mod tests {
     use crate::{
         data_cell::DataCell,
         data_cell_row::DataCellRow,
+        data_cell_table::DataCellTable,
         transform::{
             data_cell::splitting::SplitDataCellUsingValueSplit,
             data_cell_row::{
+                transrich_explode::ExplodeItem,
                 transrich_inplace::*,
                 transrich_inplace_stateful::*,
                 transrich_pass::{TransrichPass, TransrichPasses},
             },
-            value::spliting::ValueStringSeparatorCharSplit,
+            data_cell_table::reshape::{Pivot, PivotAggregation},
+            value::spliting::{ValueStringSeparatorCharSplit, ValueStringSeparatorCharTokens},
         },
     };
 
@@ -101,6 +521,9 @@ mod tests {
                 },
             })],
             stateful_transrichers: Vec::new(),
+            exploding_transrichers: Vec::new(),
+            table_transrichers: Vec::new(),
+            strict: false,
             ordering_transrichers: Some(vec![
                 Box::new(MutateItemIdx { from: 1, to: 0 }), // CAUTION!!!
                 Box::new(MutateItemIdx { from: 2, to: 1 }), // You need to order from low to high!
@@ -154,6 +577,9 @@ mod tests {
                 Box::new(DeleteItemAtIdx { 0: 0 }),
             ],
             stateful_transrichers: Vec::new(),
+            exploding_transrichers: Vec::new(),
+            table_transrichers: Vec::new(),
+            strict: false,
             ordering_transrichers: Some(vec![
                 Box::new(MutateItemIdx { from: 1, to: 0 }), // CAUTION!!!
                 Box::new(MutateItemIdx { from: 2, to: 1 }), // You need to order from low to high!
@@ -204,6 +630,9 @@ mod tests {
                 },
             })],
             stateful_transrichers: Vec::new(),
+            exploding_transrichers: Vec::new(),
+            table_transrichers: Vec::new(),
+            strict: false,
             ordering_transrichers: Some(vec![
                 Box::new(MutateItemIdx { from: 0, to: 3 }), // move the old "column" out of the way
                 Box::new(MutateItemIdx { from: 1, to: 0 }),
@@ -217,6 +646,9 @@ mod tests {
                 Some(String::from("_ds_entity_row_num")),
                 2, // Stateful is running AFTER stateless, so we can recycle the index!
             ))],
+            exploding_transrichers: Vec::new(),
+            table_transrichers: Vec::new(),
+            strict: false,
             ordering_transrichers: None,
         };
 
@@ -243,4 +675,119 @@ mod tests {
         );
         assert_eq!(Value::UInt128(1), data.get_by_idx(2).unwrap().data);
     }
+
+    #[test]
+    fn transrich_passes_exploding() {
+        let trp1 = TransrichPass::new(Vec::new(), Vec::new(), None).with_exploding(vec![Box::new(
+            ExplodeItem {
+                idx: 1,
+                splitter: ValueStringSeparatorCharTokens { sep_char: ',' },
+                target_type: ValueType::String,
+                keep_empty: false,
+            },
+        )]);
+        let trp2 = TransrichPass::new(
+            Vec::new(),
+            vec![Box::new(AddItemRuntimeStatefulRowEnum::new(None, 2))],
+            Some(vec![Box::new(SwapItemIdx { from: 0, to: 2 })]),
+        );
+        let mut passes = TransrichPasses(vec![trp1, trp2]);
+        assert!(passes.is_exploding());
+
+        let mut data = DataCellRow::new();
+        data.push(DataCell::new(String::from("id"), 0, Value::Int32(7)).unwrap());
+        data.push(
+            DataCell::new(String::from("tags"), 1, Value::String(String::from("a,b"))).unwrap(),
+        );
+
+        let rows = passes.transrich_rows(data.clone()).unwrap();
+        assert_eq!(2, rows.len());
+        // the second pass sees every exploded row
+        assert_eq!(
+            &Value::UInt128(1),
+            rows[0].get_by_idx(0).unwrap().get_data()
+        );
+        assert_eq!(
+            &Value::UInt128(2),
+            rows[1].get_by_idx(0).unwrap().get_data()
+        );
+        assert_eq!(
+            &Value::String(String::from("b")),
+            rows[1].get_by_idx(1).unwrap().get_data()
+        );
+
+        // in place, only a single resulting row is fine
+        assert!(passes.transrich(&mut data).is_err());
+    }
+
+    #[test]
+    fn transrich_passes_table() {
+        let trp1 = TransrichPass::new(vec![Box::new(DeleteItemAtIdx(3))], Vec::new(), None)
+            .with_table(vec![Box::new(Pivot {
+                id_idxs: vec![0],
+                key_idx: 1,
+                value_idx: 2,
+                aggregation: PivotAggregation::Sum,
+                columns: None,
+                header_prefix: String::new(),
+                start_idx: None,
+            })]);
+        let trp2 = TransrichPass::new(
+            Vec::new(),
+            vec![Box::new(AddItemRuntimeStatefulRowEnum::new(None, 3))],
+            None,
+        );
+        let mut passes = TransrichPasses(vec![trp1, trp2]);
+        assert!(passes.is_table());
+
+        let mut table = DataCellTable::new();
+        for (id, key, val) in [(1, "a", 1), (1, "b", 2), (2, "a", 3), (1, "a", 4)] {
+            let mut data = DataCellRow::new();
+            data.push(DataCell::new(String::from("id"), 0, Value::Int32(id)).unwrap());
+            data.push(
+                DataCell::new(String::from("key"), 1, Value::String(String::from(key))).unwrap(),
+            );
+            data.push(DataCell::new(String::from("val"), 2, Value::Int32(val)).unwrap());
+            data.push(DataCell::new(String::from("junk"), 3, Value::Bool(true)).unwrap());
+            table.push(data);
+        }
+        assert!(passes.transrich(table.get_mut(0).unwrap()).is_err());
+
+        let table = passes.transrich_table(table).unwrap();
+        assert_eq!(2, table.len());
+        let first = table.get(0).unwrap();
+        assert_eq!(vec!["id", "a", "b", "3"], first.headers());
+        assert_eq!(&Value::Int32(5), first.get_by_idx(1).unwrap().get_data());
+        assert_eq!(&Value::Int32(2), first.get_by_idx(2).unwrap().get_data());
+        // the next pass sees the pivoted rows
+        let second = table.get(1).unwrap();
+        assert_eq!(&Value::None, second.get_by_idx(2).unwrap().get_data());
+        assert_eq!(&Value::UInt128(2), second.get_by_idx(3).unwrap().get_data());
+    }
+
+    #[test]
+    fn describe_pass() {
+        let pass = || {
+            TransrichPass::new(
+                vec![Box::new(DeleteItemAtIdx(3))],
+                vec![Box::new(AddItemRuntimeStatefulRowEnum::new(None, 2))],
+                Some(vec![Box::new(SwapItemIdx { from: 0, to: 2 })]),
+            )
+        };
+        let mut used = pass();
+        let mut data = DataCellRow::new();
+        data.push(DataCell::new(String::from("id"), 0, Value::Int32(7)).unwrap());
+        data.push(DataCell::new(String::from("junk"), 3, Value::Bool(true)).unwrap());
+        used.transrich(&mut data).unwrap();
+
+        // the row enumeration moved on, but the pass still does the same
+        assert_ne!(format!("{:?}", pass()), format!("{:?}", used));
+        assert_eq!(pass().describe(), used.describe());
+        assert_eq!(
+            "stateless: DeleteItemAtIdx(idx: 3)\n\
+             stateful: AddItemRuntimeStatefulRowEnum(header: None, idx: 2, on_conflict: None)\n\
+             ordering: SwapItemIdx(from: 0, to: 2)\n",
+            used.describe().to_string()
+        );
+    }
 }
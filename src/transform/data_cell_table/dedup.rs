@@ -0,0 +1,503 @@
+use std::collections::hash_map::{DefaultHasher, Entry};
+use std::collections::{HashMap, HashSet};
+use std::fmt::Debug;
+use std::hash::{Hash, Hasher};
+use std::sync::{mpsc::Sender, Mutex};
+
+#[cfg(feature = "jsonconf")]
+use crate::conf::jsonconf::{
+    DedupConfig, DedupStreamConfig, TableTransformerConfig, TransformerConfig,
+};
+use crate::{
+    data_cell_row::{ColumnRef, DataCellRow},
+    data_cell_table::DataCellTable,
+    errors::{ContainerOpsErrors, Result, VenumTdsError},
+    transform::{
+        data_cell_row::transrich_explode::TransrichExplode,
+        description::Description,
+        value::ordering::{compare_values, Collation},
+    },
+};
+
+use super::{group_by::group_key, transrich_table::TransrichTable};
+
+fn dedup_err(msg: String) -> VenumTdsError {
+    VenumTdsError::ContainerOps(ContainerOpsErrors::Generic { msg })
+}
+
+/// A side output for discarded duplicates.
+pub trait DiscardSink: Send {
+    fn discard(&mut self, row: &DataCellRow) -> Result<()>;
+
+    /// Where the rows go, for the description of the transricher, e.g. the path of a file.
+    fn target(&self) -> String;
+
+    /// The file the rows go to, if they go to one, for the `discardedTo` of a config.
+    fn file(&self) -> Option<String>;
+}
+
+impl DiscardSink for Sender<DataCellRow> {
+    fn discard(&mut self, row: &DataCellRow) -> Result<()> {
+        self.send(row.clone())
+            .map_err(|_| dedup_err(String::from("the receiver of the discarded rows is gone")))
+    }
+
+    fn target(&self) -> String {
+        String::from("channel")
+    }
+
+    fn file(&self) -> Option<String> {
+        None
+    }
+}
+
+#[cfg(feature = "csv")]
+impl<W: std::io::Write + Send> DiscardSink for crate::io::csv::CsvRowWriter<W> {
+    fn discard(&mut self, row: &DataCellRow) -> Result<()> {
+        self.write(row)?;
+        self.flush()
+    }
+
+    fn target(&self) -> String {
+        String::from("csv")
+    }
+
+    fn file(&self) -> Option<String> {
+        None
+    }
+}
+
+/// Writes discarded rows to a csv file. The file is only created (or truncated) with the first
+/// discarded row, not when the sink is built, so e.g. just building a pass leaves it alone.
+#[cfg(feature = "csv")]
+pub struct CsvFileSink {
+    path: std::path::PathBuf,
+    opts: crate::io::csv::CsvOptions,
+    writer: Option<crate::io::csv::CsvRowWriter<std::fs::File>>,
+}
+
+#[cfg(feature = "csv")]
+impl CsvFileSink {
+    pub fn new<P: Into<std::path::PathBuf>>(path: P, opts: crate::io::csv::CsvOptions) -> Self {
+        Self {
+            path: path.into(),
+            opts,
+            writer: None,
+        }
+    }
+
+    pub fn path(&self) -> &std::path::Path {
+        &self.path
+    }
+}
+
+#[cfg(feature = "csv")]
+impl DiscardSink for CsvFileSink {
+    fn discard(&mut self, row: &DataCellRow) -> Result<()> {
+        if self.writer.is_none() {
+            self.writer = Some(crate::io::csv::CsvRowWriter::from_path(
+                &self.path, &self.opts,
+            )?);
+        }
+        self.writer.as_mut().unwrap().discard(row) // we made sure there is one, above
+    }
+
+    fn target(&self) -> String {
+        self.path.display().to_string()
+    }
+
+    fn file(&self) -> Option<String> {
+        Some(self.path.display().to_string())
+    }
+}
+
+/// The target of the sink, even if it's poisoned (the target doesn't change, when writing fails).
+fn sink_target(sink: &Mutex<Box<dyn DiscardSink>>) -> String {
+    sink.lock().unwrap_or_else(|e| e.into_inner()).target()
+}
+
+/// The file of the sink, see `sink_target`.
+#[cfg(feature = "jsonconf")]
+fn sink_file(sink: &Mutex<Box<dyn DiscardSink>>) -> Option<String> {
+    sink.lock().unwrap_or_else(|e| e.into_inner()).file()
+}
+
+fn discard_all<'a, I>(sink: &Mutex<Box<dyn DiscardSink>>, rows: I) -> Result<()>
+where
+    I: IntoIterator<Item = &'a DataCellRow>,
+{
+    let mut sink = sink
+        .lock()
+        .map_err(|_| dedup_err(String::from("the discard sink is poisoned")))?;
+    rows.into_iter().try_for_each(|row| sink.discard(row))
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+#[cfg_attr(
+    feature = "jsonconf",
+    derive(serde::Deserialize, serde::Serialize),
+    serde(rename_all = "camelCase")
+)]
+#[cfg_attr(feature = "jsonschema", derive(schemars::JsonSchema))]
+pub enum DedupKeep {
+    #[default]
+    First,
+    Last,
+    /// The row with the greatest value in the column (see `compare_values`, `None` being the
+    /// smallest). On a tie, the first one.
+    Max(ColumnRef),
+}
+
+/// Removes duplicate rows, i.e. rows with the same (stringified) values in the key columns.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct Dedup {
+    /// Without, the whole row (all values, in column order) is the key.
+    pub keys: Option<Vec<ColumnRef>>,
+    pub keep: DedupKeep,
+}
+
+fn dedup_key(keys: Option<&[ColumnRef]>, row: &DataCellRow) -> Result<Vec<Option<String>>> {
+    match keys {
+        Some(keys) => {
+            let cells = keys
+                .iter()
+                .map(|key| key.get(row))
+                .collect::<Result<Vec<_>>>()?;
+            Ok(group_key(cells.into_iter()))
+        }
+        None => Ok(group_key(row.iter_sorted())),
+    }
+}
+
+impl DataCellTable {
+    /// Splits the rows into the ones to keep and the discarded duplicates, see `Dedup`. Both keep
+    /// the order of the rows.
+    pub fn dedup(&self, dedup: &Dedup) -> Result<(DataCellTable, DataCellTable)> {
+        let mut winners: HashMap<Vec<Option<String>>, usize> = HashMap::new();
+        for (pos, row) in self.iter().enumerate() {
+            let key = dedup_key(dedup.keys.as_deref(), row)?;
+            match &dedup.keep {
+                DedupKeep::First => {
+                    winners.entry(key).or_insert(pos);
+                }
+                DedupKeep::Last => {
+                    winners.insert(key, pos);
+                }
+                DedupKeep::Max(column) => {
+                    let val = column.get(row)?.get_data();
+                    match winners.entry(key) {
+                        Entry::Vacant(entry) => {
+                            entry.insert(pos);
+                        }
+                        Entry::Occupied(mut entry) => {
+                            // we only ever put positions of this table in
+                            let max = column.get(&self.rows()[*entry.get()])?.get_data();
+                            if compare_values(val, max, &Collation::default()).is_gt() {
+                                entry.insert(pos);
+                            }
+                        }
+                    }
+                }
+            }
+        }
+        let kept: HashSet<usize> = winners.into_values().collect();
+        let (mut res, mut discarded) = (
+            DataCellTable::with_capacity(kept.len()),
+            DataCellTable::new(),
+        );
+        for (pos, row) in self.iter().enumerate() {
+            if kept.contains(&pos) {
+                res.push(row.clone());
+            } else {
+                discarded.push(row.clone());
+            }
+        }
+        Ok((res, discarded))
+    }
+}
+
+impl TransrichTable for Dedup {
+    fn transrich(&self, table: DataCellTable) -> Result<DataCellTable> {
+        Ok(table.dedup(self)?.0)
+    }
+
+    fn describe(&self) -> Description {
+        Description::new("Dedup")
+            .param("keys", &self.keys)
+            .param("keep", &self.keep)
+    }
+
+    #[cfg(feature = "jsonconf")]
+    fn to_config(&self) -> Option<TableTransformerConfig> {
+        Some(TableTransformerConfig::Dedup {
+            cfg: DedupConfig {
+                keys: self.keys.clone(),
+                keep: Some(self.keep.clone()),
+                discarded_to: None,
+            },
+        })
+    }
+}
+
+/// A `Dedup`, that hands the discarded duplicates to a sink, e.g. a file.
+pub struct DiscardingDedup {
+    pub dedup: Dedup,
+    pub sink: Mutex<Box<dyn DiscardSink>>,
+}
+
+impl DiscardingDedup {
+    pub fn new(dedup: Dedup, sink: Box<dyn DiscardSink>) -> Self {
+        Self {
+            dedup,
+            sink: Mutex::new(sink),
+        }
+    }
+}
+
+impl Debug for DiscardingDedup {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("DiscardingDedup")
+            .field("dedup", &self.dedup)
+            .finish_non_exhaustive()
+    }
+}
+
+impl TransrichTable for DiscardingDedup {
+    fn transrich(&self, table: DataCellTable) -> Result<DataCellTable> {
+        let (kept, discarded) = table.dedup(&self.dedup)?;
+        discard_all(&self.sink, &discarded)?;
+        Ok(kept)
+    }
+
+    fn describe(&self) -> Description {
+        Description::new("DiscardingDedup")
+            .param("keys", &self.dedup.keys)
+            .param("keep", &self.dedup.keep)
+            .param("discarded_to", sink_target(&self.sink))
+    }
+
+    #[cfg(feature = "jsonconf")]
+    fn to_config(&self) -> Option<TableTransformerConfig> {
+        Some(TableTransformerConfig::Dedup {
+            cfg: DedupConfig {
+                keys: self.dedup.keys.clone(),
+                keep: Some(self.dedup.keep.clone()),
+                discarded_to: Some(sink_file(&self.sink)?),
+            },
+        })
+    }
+}
+
+/// Removes duplicate rows row by row, keeping the first one, see `Dedup`. Instead of the keys, only
+/// their 64 bit hashes are kept, so memory stays small (and can be bounded by `max_keys`), but two
+/// different keys may (very rarely) be taken for the same.
+pub struct DedupStream {
+    /// Without, the whole row is the key.
+    pub keys: Option<Vec<ColumnRef>>,
+    /// More distinct keys are an error.
+    pub max_keys: Option<usize>,
+    seen: Mutex<HashSet<u64>>,
+    sink: Option<Mutex<Box<dyn DiscardSink>>>,
+}
+
+impl DedupStream {
+    pub fn new(keys: Option<Vec<ColumnRef>>, max_keys: Option<usize>) -> Self {
+        Self {
+            keys,
+            max_keys,
+            seen: Mutex::new(HashSet::new()),
+            sink: None,
+        }
+    }
+
+    /// Hands the discarded duplicates to the sink.
+    pub fn with_discarded(mut self, sink: Box<dyn DiscardSink>) -> Self {
+        self.sink = Some(Mutex::new(sink));
+        self
+    }
+
+    /// Whether the row is the first one with its key. (Remembers the key.)
+    pub fn is_first(&self, row: &DataCellRow) -> Result<bool> {
+        let mut hasher = DefaultHasher::new();
+        dedup_key(self.keys.as_deref(), row)?.hash(&mut hasher);
+        let hash = hasher.finish();
+
+        let mut seen = self
+            .seen
+            .lock()
+            .map_err(|_| dedup_err(String::from("the seen keys are poisoned")))?;
+        if seen.contains(&hash) {
+            return Ok(false);
+        }
+        if self.max_keys.is_some_and(|max| seen.len() >= max) {
+            return Err(dedup_err(format!("more than {} distinct keys", seen.len())));
+        }
+        seen.insert(hash);
+        Ok(true)
+    }
+}
+
+impl Debug for DedupStream {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("DedupStream")
+            .field("keys", &self.keys)
+            .field("max_keys", &self.max_keys)
+            .finish_non_exhaustive()
+    }
+}
+
+impl TransrichExplode for DedupStream {
+    fn explode(&self, data_cell_row: DataCellRow) -> Result<Vec<DataCellRow>> {
+        if self.is_first(&data_cell_row)? {
+            return Ok(vec![data_cell_row]);
+        }
+        if let Some(sink) = &self.sink {
+            discard_all(sink, [&data_cell_row])?;
+        }
+        Ok(Vec::new())
+    }
+
+    fn describe(&self) -> Description {
+        Description::new("DedupStream")
+            .param("keys", &self.keys)
+            .param("max_keys", self.max_keys)
+            .param("discarded_to", self.sink.as_ref().map(sink_target))
+    }
+
+    #[cfg(feature = "jsonconf")]
+    fn to_config(&self) -> Option<TransformerConfig> {
+        let discarded_to = match &self.sink {
+            Some(sink) => Some(sink_file(sink)?),
+            None => None,
+        };
+        Some(TransformerConfig::DedupStream {
+            cfg: DedupStreamConfig {
+                keys: self.keys.clone(),
+                max_keys: self.max_keys,
+                discarded_to,
+            },
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::mpsc::channel;
+
+    use venum::{value::Value, value_type::ValueType};
+
+    use crate::data_cell::DataCell;
+
+    use super::*;
+
+    fn booking(id: &str, amount: i32, version: Option<u8>) -> DataCellRow {
+        let mut row = DataCellRow::new();
+        row.push(DataCell::new(String::from("id"), 0, Value::String(String::from(id))).unwrap());
+        row.push(DataCell::new(String::from("amount"), 1, Value::Int32(amount)).unwrap());
+        row.push(
+            DataCell::new_with_type_info(
+                ValueType::UInt8,
+                String::from("version"),
+                2,
+                version.map_or(Value::None, Value::UInt8),
+            )
+            .unwrap(),
+        );
+        row
+    }
+
+    fn bookings() -> DataCellTable {
+        DataCellTable::from(vec![
+            booking("a", 10, Some(1)),
+            booking("b", 20, None),
+            booking("a", 10, Some(3)),
+            booking("b", 20, None),
+            booking("a", 11, Some(2)),
+        ])
+    }
+
+    #[test]
+    fn dedup_keep_policies() {
+        let by_id = |keep: DedupKeep| Dedup {
+            keys: Some(vec![ColumnRef::Name(String::from("id"))]),
+            keep,
+        };
+
+        let (kept, discarded) = bookings().dedup(&by_id(DedupKeep::First)).unwrap();
+        assert_eq!(
+            DataCellTable::from(vec![booking("a", 10, Some(1)), booking("b", 20, None)]),
+            kept
+        );
+        assert_eq!(3, discarded.len());
+
+        let (kept, _) = bookings().dedup(&by_id(DedupKeep::Last)).unwrap();
+        assert_eq!(
+            DataCellTable::from(vec![booking("b", 20, None), booking("a", 11, Some(2))]),
+            kept
+        );
+
+        let (kept, discarded) = bookings()
+            .dedup(&by_id(DedupKeep::Max(ColumnRef::Idx(2))))
+            .unwrap();
+        assert_eq!(
+            DataCellTable::from(vec![booking("b", 20, None), booking("a", 10, Some(3))]),
+            kept
+        );
+        assert_eq!(bookings().get(1), discarded.get(1));
+
+        // the whole row
+        let (kept, discarded) = bookings().dedup(&Dedup::default()).unwrap();
+        assert_eq!(4, kept.len());
+        assert_eq!(DataCellTable::from(vec![booking("b", 20, None)]), discarded);
+
+        assert!(bookings()
+            .dedup(&by_id(DedupKeep::Max(ColumnRef::Idx(7))))
+            .is_err());
+    }
+
+    #[test]
+    fn dedup_discarded() {
+        let (tx, rx) = channel();
+        let dedup = DiscardingDedup::new(
+            Dedup {
+                keys: Some(vec![ColumnRef::Idx(0), ColumnRef::Idx(1)]),
+                keep: DedupKeep::Last,
+            },
+            Box::new(tx),
+        );
+        let kept = dedup.transrich(bookings()).unwrap();
+        assert_eq!(3, kept.len());
+        assert_eq!(
+            vec![booking("a", 10, Some(1)), booking("b", 20, None)],
+            rx.try_iter().collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn dedup_stream() {
+        let (tx, rx) = channel();
+        let dedup =
+            DedupStream::new(Some(vec![ColumnRef::Idx(0)]), Some(2)).with_discarded(Box::new(tx));
+        let kept: Vec<DataCellRow> = bookings()
+            .into_iter()
+            .flat_map(|row| dedup.explode(row).unwrap())
+            .collect();
+        assert_eq!(
+            vec![booking("a", 10, Some(1)), booking("b", 20, None)],
+            kept
+        );
+        assert_eq!(3, rx.try_iter().count());
+        assert_eq!(
+            "DedupStream(keys: Some([Idx(0)]), max_keys: Some(2), discarded_to: Some(\"channel\"))",
+            dedup.describe().to_string()
+        );
+        // rows going to a channel can't be written as config
+        #[cfg(feature = "jsonconf")]
+        assert_eq!(None, dedup.to_config());
+
+        // a third key is one too many
+        assert!(dedup.explode(booking("c", 1, None)).is_err());
+        // known keys are still fine
+        assert!(dedup.explode(booking("a", 1, None)).unwrap().is_empty());
+    }
+}
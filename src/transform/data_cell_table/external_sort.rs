@@ -0,0 +1,552 @@
+use std::cmp::Ordering;
+use std::collections::BinaryHeap;
+use std::fs::File;
+use std::io::{BufReader, BufWriter, ErrorKind, Read, Write};
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use chrono::{DateTime, Datelike, FixedOffset, NaiveDate};
+use rust_decimal::Decimal;
+use tempfile::{NamedTempFile, TempPath};
+use venum::{value::Value, value_type::ValueType};
+
+use crate::{
+    data_cell::DataCell,
+    data_cell_row::DataCellRow,
+    errors::{IoErrors, Result, VenumTdsError},
+};
+
+use super::sort::Sort;
+
+fn io_err<E: std::fmt::Display>(e: E) -> VenumTdsError {
+    VenumTdsError::Io(IoErrors::Generic {
+        msg: format!("sort run: {}", e),
+    })
+}
+
+/// How many runs are merged at once, unless configured otherwise.
+pub const DEFAULT_MAX_RUNS: usize = 64;
+
+/// Sorts rows that don't fit into memory: chunks of `max_rows_in_memory` rows are sorted and
+/// written to temp files (runs), which are then merged. Like `Sort`, the result is stable. If all
+/// rows fit into a single chunk, nothing is written.
+///
+/// At most `max_runs` runs are open (and merged) at a time. If there are more, neighbouring runs
+/// are merged into longer runs first, in as many passes as it takes.
+///
+/// This is not a `TransrichTable`, since a table transricher gets all rows at once. Stream the rows
+/// into `sort` instead, e.g. from a reader, through `TransrichIterExt::transrich`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExternalSort {
+    pub sort: Sort,
+    pub max_rows_in_memory: usize,
+    /// At least 2, see `DEFAULT_MAX_RUNS`.
+    pub max_runs: usize,
+    /// Where to put the runs. Without, the default temp dir.
+    pub temp_dir: Option<PathBuf>,
+}
+
+impl ExternalSort {
+    pub fn new(sort: Sort, max_rows_in_memory: usize) -> Self {
+        Self {
+            sort,
+            max_rows_in_memory,
+            max_runs: DEFAULT_MAX_RUNS,
+            temp_dir: None,
+        }
+    }
+
+    /// Consumes all rows, the first error aborts the sort. The sorted rows are read back lazily.
+    pub fn sort<I>(&self, rows: I) -> Result<SortedRows>
+    where
+        I: IntoIterator<Item = Result<DataCellRow>>,
+    {
+        let chunk_size = self.max_rows_in_memory.max(1);
+        let mut runs: Vec<TempPath> = Vec::new();
+        let mut chunk: Vec<(Vec<Value>, DataCellRow)> = Vec::with_capacity(chunk_size);
+        for row in rows {
+            let row = row?;
+            chunk.push((self.sort.key_values(&row)?, row));
+            if chunk.len() == chunk_size {
+                runs.push(self.write_run(&mut chunk)?);
+            }
+        }
+        let sort = Arc::new(self.sort.clone());
+        if runs.is_empty() {
+            chunk.sort_by(|a, b| self.sort.compare_keys(&a.0, &b.0));
+            return Ok(SortedRows::memory(sort, chunk));
+        }
+        if !chunk.is_empty() {
+            runs.push(self.write_run(&mut chunk)?);
+        }
+        let runs = self.reduce_runs(&sort, runs)?;
+        SortedRows::merge(sort, runs)
+    }
+
+    /// Sorts and drains the chunk into a new run.
+    fn write_run(&self, chunk: &mut Vec<(Vec<Value>, DataCellRow)>) -> Result<TempPath> {
+        chunk.sort_by(|a, b| self.sort.compare_keys(&a.0, &b.0));
+        self.write_rows(chunk.drain(..).map(|(_, row)| Ok(row)))
+    }
+
+    /// Writes (sorted) rows into a new run. The file is closed again, a run only holds a file
+    /// descriptor while it's merged.
+    fn write_rows<I>(&self, rows: I) -> Result<TempPath>
+    where
+        I: Iterator<Item = Result<DataCellRow>>,
+    {
+        let file = match &self.temp_dir {
+            Some(dir) => NamedTempFile::new_in(dir),
+            None => NamedTempFile::new(),
+        }
+        .map_err(io_err)?;
+        let (file, path) = file.into_parts();
+        let mut writer = RunWriter(BufWriter::new(file));
+        for row in rows {
+            writer.put_row(&row?)?;
+        }
+        writer.0.into_inner().map_err(io_err)?;
+        Ok(path)
+    }
+
+    /// Merges groups of `max_runs` neighbouring runs into one, until no more than `max_runs` are
+    /// left. Since the groups (and the runs within) stay in order, the result is still stable.
+    fn reduce_runs(&self, sort: &Arc<Sort>, mut runs: Vec<TempPath>) -> Result<Vec<TempPath>> {
+        let fan_in = self.max_runs.max(2);
+        while runs.len() > fan_in {
+            let mut merged = Vec::new();
+            let mut rest = runs.into_iter().peekable();
+            while rest.peek().is_some() {
+                let mut group: Vec<TempPath> = rest.by_ref().take(fan_in).collect();
+                if group.len() == 1 {
+                    merged.append(&mut group);
+                } else {
+                    merged.push(self.write_rows(SortedRows::merge(sort.clone(), group)?)?);
+                }
+            }
+            runs = merged;
+        }
+        Ok(runs)
+    }
+}
+
+/// The sorted rows, see `ExternalSort`. The runs are deleted when this is dropped.
+#[derive(Debug)]
+pub struct SortedRows {
+    sort: Arc<Sort>,
+    inner: Inner,
+}
+
+#[derive(Debug)]
+enum Inner {
+    Memory(std::vec::IntoIter<(Vec<Value>, DataCellRow)>),
+    /// The runs, and the current (smallest) row of every run that isn't exhausted yet.
+    Merge {
+        runs: Vec<RunReader>,
+        heads: BinaryHeap<Head>,
+    },
+    Failed,
+}
+
+/// The current row of a run in a merge. `BinaryHeap` is a max heap, so the smallest keys must be
+/// the greatest `Head`. On equal keys, the earlier run wins, since it holds the earlier rows, which
+/// keeps the merge stable.
+#[derive(Debug)]
+struct Head {
+    sort: Arc<Sort>,
+    keys: Vec<Value>,
+    row: DataCellRow,
+    run: usize,
+}
+
+impl Ord for Head {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.sort
+            .compare_keys(&other.keys, &self.keys)
+            .then_with(|| other.run.cmp(&self.run))
+    }
+}
+
+impl PartialOrd for Head {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl PartialEq for Head {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Head {}
+
+impl SortedRows {
+    fn memory(sort: Arc<Sort>, chunk: Vec<(Vec<Value>, DataCellRow)>) -> Self {
+        Self {
+            sort,
+            inner: Inner::Memory(chunk.into_iter()),
+        }
+    }
+
+    fn merge(sort: Arc<Sort>, runs: Vec<TempPath>) -> Result<Self> {
+        let mut readers = Vec::with_capacity(runs.len());
+        let mut heads = BinaryHeap::with_capacity(runs.len());
+        for (run, path) in runs.into_iter().enumerate() {
+            let mut reader = RunReader::open(path)?;
+            if let Some((keys, row)) = reader.next_keyed(&sort)? {
+                heads.push(Head {
+                    sort: sort.clone(),
+                    keys,
+                    row,
+                    run,
+                });
+            }
+            readers.push(reader);
+        }
+        Ok(Self {
+            sort,
+            inner: Inner::Merge {
+                runs: readers,
+                heads,
+            },
+        })
+    }
+
+    pub fn is_external(&self) -> bool {
+        matches!(self.inner, Inner::Merge { .. })
+    }
+}
+
+impl Iterator for SortedRows {
+    type Item = Result<DataCellRow>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (runs, heads) = match &mut self.inner {
+            Inner::Memory(rows) => return rows.next().map(|(_, row)| Ok(row)),
+            Inner::Merge { runs, heads } => (runs, heads),
+            Inner::Failed => return None,
+        };
+        let head = heads.pop()?;
+        match runs[head.run].next_keyed(&self.sort) {
+            Ok(Some((keys, row))) => heads.push(Head {
+                sort: self.sort.clone(),
+                keys,
+                row,
+                run: head.run,
+            }),
+            Ok(None) => {}
+            Err(e) => {
+                self.inner = Inner::Failed;
+                return Some(Err(e));
+            }
+        }
+        Some(Ok(head.row))
+    }
+}
+
+/// The (lossless) binary encoding of rows in a run. Lengths are `u64`, numbers little endian.
+struct RunWriter(BufWriter<File>);
+
+macro_rules! value_type_tags {
+    ($($tag:literal => $variant:ident),* $(,)?) => {
+        fn type_tag(value_type: &ValueType) -> u8 {
+            match value_type {
+                $(ValueType::$variant => $tag,)*
+            }
+        }
+
+        fn tag_type(tag: u8) -> Result<ValueType> {
+            match tag {
+                $($tag => Ok(ValueType::$variant),)*
+                _ => Err(io_err(format!("unknown type tag {}", tag))),
+            }
+        }
+    };
+}
+
+value_type_tags! {
+    1 => Bool, 2 => Char, 3 => String,
+    4 => Int8, 5 => Int16, 6 => Int32, 7 => Int64, 8 => Int128,
+    9 => UInt8, 10 => UInt16, 11 => UInt32, 12 => UInt64, 13 => UInt128,
+    14 => Float32, 15 => Float64, 16 => Decimal,
+    17 => NaiveDate, 18 => NaiveDateTime, 19 => DateTime,
+}
+
+impl RunWriter {
+    fn put(&mut self, bytes: &[u8]) -> Result<()> {
+        self.0.write_all(bytes).map_err(io_err)
+    }
+
+    fn put_len(&mut self, len: usize) -> Result<()> {
+        self.put(&(len as u64).to_le_bytes())
+    }
+
+    fn put_str(&mut self, s: &str) -> Result<()> {
+        self.put_len(s.len())?;
+        self.put(s.as_bytes())
+    }
+
+    fn put_row(&mut self, row: &DataCellRow) -> Result<()> {
+        self.put_len(row.len())?;
+        for dc in row {
+            self.put_len(dc.get_idx())?;
+            self.put_str(dc.get_name())?;
+            self.put(&[type_tag(dc.get_type_info())])?;
+            self.put_value(dc.get_data())?;
+        }
+        Ok(())
+    }
+
+    /// A tag (`0` for `None`, otherwise the one of its type) followed by the value.
+    fn put_value(&mut self, val: &Value) -> Result<()> {
+        let Ok(value_type) = ValueType::try_from(val) else {
+            return self.put(&[0]);
+        };
+        self.put(&[type_tag(&value_type)])?;
+        match val {
+            Value::None => Ok(()),
+            Value::Bool(b) => self.put(&[u8::from(*b)]),
+            Value::Char(c) => self.put(&u32::from(*c).to_le_bytes()),
+            Value::String(s) => self.put_str(s),
+            Value::Int8(i) => self.put(&i.to_le_bytes()),
+            Value::Int16(i) => self.put(&i.to_le_bytes()),
+            Value::Int32(i) => self.put(&i.to_le_bytes()),
+            Value::Int64(i) => self.put(&i.to_le_bytes()),
+            Value::Int128(i) => self.put(&i.to_le_bytes()),
+            Value::UInt8(u) => self.put(&u.to_le_bytes()),
+            Value::UInt16(u) => self.put(&u.to_le_bytes()),
+            Value::UInt32(u) => self.put(&u.to_le_bytes()),
+            Value::UInt64(u) => self.put(&u.to_le_bytes()),
+            Value::UInt128(u) => self.put(&u.to_le_bytes()),
+            Value::Float32(f) => self.put(&f.to_le_bytes()),
+            Value::Float64(f) => self.put(&f.to_le_bytes()),
+            Value::Decimal(d) => self.put(&d.serialize()),
+            Value::NaiveDate(d) => self.put(&d.num_days_from_ce().to_le_bytes()),
+            Value::NaiveDateTime(dt) => {
+                let dt = dt.and_utc();
+                self.put(&dt.timestamp().to_le_bytes())?;
+                self.put(&dt.timestamp_subsec_nanos().to_le_bytes())
+            }
+            Value::DateTime(dt) => {
+                self.put(&dt.timestamp().to_le_bytes())?;
+                self.put(&dt.timestamp_subsec_nanos().to_le_bytes())?;
+                self.put(&dt.offset().local_minus_utc().to_le_bytes())
+            }
+        }
+    }
+}
+
+/// Reads a run back. The run is deleted when this is dropped.
+#[derive(Debug)]
+struct RunReader {
+    file: BufReader<File>,
+    _path: TempPath,
+}
+
+impl RunReader {
+    fn open(path: TempPath) -> Result<Self> {
+        Ok(Self {
+            file: BufReader::new(File::open(&path).map_err(io_err)?),
+            _path: path,
+        })
+    }
+
+    fn take<const N: usize>(&mut self) -> Result<[u8; N]> {
+        let mut buf = [0u8; N];
+        self.file.read_exact(&mut buf).map_err(io_err)?;
+        Ok(buf)
+    }
+
+    fn take_len(&mut self) -> Result<usize> {
+        usize::try_from(u64::from_le_bytes(self.take()?)).map_err(io_err)
+    }
+
+    fn take_str(&mut self) -> Result<String> {
+        let mut buf = vec![0u8; self.take_len()?];
+        self.file.read_exact(&mut buf).map_err(io_err)?;
+        String::from_utf8(buf).map_err(io_err)
+    }
+
+    /// The next row, or `None` at the end of the run.
+    fn next_row(&mut self) -> Result<Option<DataCellRow>> {
+        let mut len = [0u8; 8];
+        match self.file.read_exact(&mut len) {
+            Ok(()) => {}
+            Err(e) if e.kind() == ErrorKind::UnexpectedEof => return Ok(None),
+            Err(e) => return Err(io_err(e)),
+        }
+        let len = usize::try_from(u64::from_le_bytes(len)).map_err(io_err)?;
+        let mut row = DataCellRow::with_capacity(len);
+        for _ in 0..len {
+            let idx = self.take_len()?;
+            let name = self.take_str()?;
+            let dtype = tag_type(self.take::<1>()?[0])?;
+            let data = self.take_value()?;
+            row.push(DataCell {
+                dtype,
+                name,
+                idx,
+                data,
+            });
+        }
+        Ok(Some(row))
+    }
+
+    fn next_keyed(&mut self, sort: &Sort) -> Result<Option<(Vec<Value>, DataCellRow)>> {
+        match self.next_row()? {
+            Some(row) => Ok(Some((sort.key_values(&row)?, row))),
+            None => Ok(None),
+        }
+    }
+
+    fn take_value(&mut self) -> Result<Value> {
+        let tag = self.take::<1>()?[0];
+        if tag == 0 {
+            return Ok(Value::None);
+        }
+        Ok(match tag_type(tag)? {
+            ValueType::Bool => Value::Bool(self.take::<1>()?[0] != 0),
+            ValueType::Char => {
+                let c = u32::from_le_bytes(self.take()?);
+                Value::Char(char::from_u32(c).ok_or_else(|| io_err("invalid char"))?)
+            }
+            ValueType::String => Value::String(self.take_str()?),
+            ValueType::Int8 => Value::Int8(i8::from_le_bytes(self.take()?)),
+            ValueType::Int16 => Value::Int16(i16::from_le_bytes(self.take()?)),
+            ValueType::Int32 => Value::Int32(i32::from_le_bytes(self.take()?)),
+            ValueType::Int64 => Value::Int64(i64::from_le_bytes(self.take()?)),
+            ValueType::Int128 => Value::Int128(i128::from_le_bytes(self.take()?)),
+            ValueType::UInt8 => Value::UInt8(u8::from_le_bytes(self.take()?)),
+            ValueType::UInt16 => Value::UInt16(u16::from_le_bytes(self.take()?)),
+            ValueType::UInt32 => Value::UInt32(u32::from_le_bytes(self.take()?)),
+            ValueType::UInt64 => Value::UInt64(u64::from_le_bytes(self.take()?)),
+            ValueType::UInt128 => Value::UInt128(u128::from_le_bytes(self.take()?)),
+            ValueType::Float32 => Value::Float32(f32::from_le_bytes(self.take()?)),
+            ValueType::Float64 => Value::Float64(f64::from_le_bytes(self.take()?)),
+            ValueType::Decimal => Value::Decimal(Decimal::deserialize(self.take()?)),
+            ValueType::NaiveDate => {
+                let days = i32::from_le_bytes(self.take()?);
+                Value::NaiveDate(
+                    NaiveDate::from_num_days_from_ce_opt(days)
+                        .ok_or_else(|| io_err("invalid date"))?,
+                )
+            }
+            ValueType::NaiveDateTime => Value::NaiveDateTime(self.take_instant()?.naive_utc()),
+            ValueType::DateTime => {
+                let instant = self.take_instant()?;
+                let offset = FixedOffset::east_opt(i32::from_le_bytes(self.take()?))
+                    .ok_or_else(|| io_err("invalid offset"))?;
+                Value::DateTime(instant.with_timezone(&offset))
+            }
+        })
+    }
+
+    fn take_instant(&mut self) -> Result<DateTime<chrono::Utc>> {
+        let secs = i64::from_le_bytes(self.take()?);
+        let nanos = u32::from_le_bytes(self.take()?);
+        DateTime::from_timestamp(secs, nanos).ok_or_else(|| io_err("invalid timestamp"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeZone;
+
+    use crate::data_cell_row::ColumnRef;
+    use crate::data_cell_table::DataCellTable;
+    use crate::transform::data_cell_table::sort::SortKey;
+
+    use super::*;
+
+    fn row(key: i64, seq: usize) -> DataCellRow {
+        DataCellRow::from(vec![
+            DataCell::new(String::from("key"), 0, Value::Int64(key)).unwrap(),
+            DataCell::new(String::from("seq"), 1, Value::UInt64(seq as u64)).unwrap(),
+        ])
+    }
+
+    #[test]
+    fn external_sort_is_stable() {
+        let rows: Vec<DataCellRow> = (0..100)
+            .map(|seq| row((seq as i64 * 7) % 10, seq))
+            .collect();
+        let sort = Sort {
+            keys: vec![SortKey::new(ColumnRef::Idx(0))],
+        };
+        let mut expected = DataCellTable::from(rows.clone());
+        expected.sort(&sort).unwrap();
+
+        let sorted = ExternalSort::new(sort.clone(), 16)
+            .sort(rows.clone().into_iter().map(Ok))
+            .unwrap();
+        assert!(sorted.is_external());
+        assert_eq!(expected, sorted.collect::<Result<DataCellTable>>().unwrap());
+
+        let sorted = ExternalSort::new(sort, 1000)
+            .sort(rows.into_iter().map(Ok))
+            .unwrap();
+        assert!(!sorted.is_external());
+        assert_eq!(expected, sorted.map(|r| r.unwrap()).collect());
+    }
+
+    #[test]
+    fn multi_pass_merge_is_stable() {
+        let rows: Vec<DataCellRow> = (0..100)
+            .map(|seq| row((seq as i64 * 7) % 10, seq))
+            .collect();
+        let sort = Sort {
+            keys: vec![SortKey::new(ColumnRef::Idx(0))],
+        };
+        let mut expected = DataCellTable::from(rows.clone());
+        expected.sort(&sort).unwrap();
+
+        // 34 runs, merged 3 at a time: 12, then 4, then 2 runs are left
+        let ext_sort = ExternalSort {
+            max_runs: 3,
+            ..ExternalSort::new(sort, 3)
+        };
+        let sorted = ext_sort.sort(rows.into_iter().map(Ok)).unwrap();
+        assert!(sorted.is_external());
+        assert_eq!(expected, sorted.collect::<Result<DataCellTable>>().unwrap());
+    }
+
+    #[test]
+    fn runs_are_lossless() {
+        let offset = FixedOffset::east_opt(-5 * 3600).unwrap();
+        let date_time = offset.with_ymd_and_hms(2022, 3, 1, 23, 30, 15).unwrap();
+        let values = vec![
+            Value::None,
+            Value::Bool(true),
+            Value::Char('ü'),
+            Value::String(String::from("späti")),
+            Value::Int8(-8),
+            Value::Int128(i128::MIN),
+            Value::UInt128(u128::MAX),
+            Value::Float32(-1.5),
+            Value::Float64(f64::MAX),
+            Value::Decimal(Decimal::new(-12345, 3)),
+            Value::NaiveDate(NaiveDate::from_ymd_opt(1, 1, 1).unwrap()),
+            Value::NaiveDateTime(date_time.naive_local()),
+            Value::DateTime(date_time),
+        ];
+        let mut row = DataCellRow::new();
+        for (idx, val) in values.into_iter().enumerate() {
+            row.push(
+                DataCell::new_with_type_info(ValueType::String, idx.to_string(), idx, Value::None)
+                    .unwrap(),
+            );
+            if val.is_some() {
+                row.push(DataCell::new(format!("v{}", idx), idx + 100, val).unwrap());
+            }
+        }
+
+        let mut chunk = vec![(Vec::new(), row.clone())];
+        let path = ExternalSort::new(Sort::default(), 1)
+            .write_run(&mut chunk)
+            .unwrap();
+        let mut run = RunReader::open(path).unwrap();
+        assert_eq!(Some(row), run.next_row().unwrap());
+        assert_eq!(None, run.next_row().unwrap());
+    }
+}
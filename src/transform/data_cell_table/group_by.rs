@@ -0,0 +1,463 @@
+use std::cmp::Ordering;
+use std::collections::{HashMap, HashSet};
+
+use rust_decimal::Decimal;
+use venum::value::Value;
+use venum::value_type::ValueType;
+
+#[cfg(feature = "jsonconf")]
+use crate::conf::jsonconf::{AggregationConfig, GroupByConfig, TableTransformerConfig};
+use crate::{
+    data_cell::DataCell,
+    data_cell_row::{ColumnRef, DataCellRow},
+    data_cell_table::DataCellTable,
+    errors::{ContainerOpsErrors, Result, VenumTdsError},
+    transform::{
+        description::Description,
+        value::aggregating::{checked_add, compare},
+    },
+    value_fmt::value_to_string,
+};
+
+use super::transrich_table::TransrichTable;
+
+fn group_by_err(msg: String) -> VenumTdsError {
+    VenumTdsError::ContainerOps(ContainerOpsErrors::GroupByError { msg })
+}
+
+/// Identifies a group by the (stringified) values of the given cells. `None` is distinct from the
+/// empty string.
+pub(crate) fn group_key<'a>(cells: impl Iterator<Item = &'a DataCell>) -> Vec<Option<String>> {
+    cells
+        .map(|dc| dc.get_data())
+        .map(|val| val.is_some().then(|| value_to_string(val)))
+        .collect()
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "jsonconf",
+    derive(serde::Deserialize, serde::Serialize),
+    serde(rename_all = "camelCase")
+)]
+#[cfg_attr(feature = "jsonschema", derive(schemars::JsonSchema))]
+pub enum AggregateFunction {
+    /// The number of values, as `UInt64`. Without a column, the number of rows.
+    Count,
+    /// Integer overflows are errors.
+    Sum,
+    Min,
+    Max,
+    /// `Decimal` values give a `Decimal`, all other numbers a `Float64`.
+    Mean,
+    First,
+    Last,
+    /// The number of distinct (stringified) values, as `UInt64`.
+    CountDistinct,
+    /// The stringified values, joined by the separator, as `String`.
+    StringAgg,
+}
+
+/// One aggregated cell of the resulting rows. `None` values are ignored by all functions, i.e. a
+/// group without any values gets `None` (or `0`, when counting).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Aggregation {
+    /// Only `count` works without a column.
+    pub column: Option<ColumnRef>,
+    pub function: AggregateFunction,
+    /// Only for `string_agg`, defaults to `,`.
+    pub separator: Option<String>,
+    pub target_idx: usize,
+    /// Without, the idx is used as header.
+    pub target_header: Option<String>,
+}
+
+/// Groups the rows by the values of the key columns, resulting in one row per group, holding the
+/// key columns (of the first row of the group) and the aggregations. The groups are kept in order
+/// of their first appearance.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GroupBy {
+    pub keys: Vec<ColumnRef>,
+    pub aggregations: Vec<Aggregation>,
+}
+
+#[derive(Debug)]
+enum Accumulator {
+    Count(u64),
+    Value(Value),
+    Mean { sum: Value, count: u64 },
+    Distinct(HashSet<String>),
+    Strings(Vec<String>),
+}
+
+/// Widens numbers for summing them up for the mean, i.e. integers to `Int128` and floats to
+/// `Float64`.
+fn widen(val: &Value) -> Result<Value> {
+    Ok(match val {
+        Value::Int8(i) => Value::Int128(i128::from(*i)),
+        Value::Int16(i) => Value::Int128(i128::from(*i)),
+        Value::Int32(i) => Value::Int128(i128::from(*i)),
+        Value::Int64(i) => Value::Int128(i128::from(*i)),
+        Value::Int128(i) => Value::Int128(*i),
+        Value::UInt8(u) => Value::Int128(i128::from(*u)),
+        Value::UInt16(u) => Value::Int128(i128::from(*u)),
+        Value::UInt32(u) => Value::Int128(i128::from(*u)),
+        Value::UInt64(u) => Value::Int128(i128::from(*u)),
+        Value::UInt128(u) => Value::Int128(
+            i128::try_from(*u).map_err(|_| group_by_err(format!("{} is too large", u)))?,
+        ),
+        Value::Float32(f) => Value::Float64(f64::from(*f)),
+        Value::Float64(f) => Value::Float64(*f),
+        Value::Decimal(d) => Value::Decimal(*d),
+        _ => {
+            return Err(group_by_err(format!(
+                "{:?} is not a number, can't compute the mean",
+                val
+            )))
+        }
+    })
+}
+
+impl Aggregation {
+    fn accumulator(&self) -> Accumulator {
+        match self.function {
+            AggregateFunction::Count => Accumulator::Count(0),
+            AggregateFunction::Mean => Accumulator::Mean {
+                sum: Value::None,
+                count: 0,
+            },
+            AggregateFunction::CountDistinct => Accumulator::Distinct(HashSet::new()),
+            AggregateFunction::StringAgg => Accumulator::Strings(Vec::new()),
+            _ => Accumulator::Value(Value::None),
+        }
+    }
+
+    fn target_type(&self, column_type: Option<&ValueType>) -> ValueType {
+        match (self.function, column_type) {
+            (AggregateFunction::Count | AggregateFunction::CountDistinct, _) => ValueType::UInt64,
+            (AggregateFunction::StringAgg, _) => ValueType::String,
+            (AggregateFunction::Mean, Some(ValueType::Decimal)) => ValueType::Decimal,
+            (AggregateFunction::Mean, _) => ValueType::Float64,
+            (_, Some(vt)) => vt.clone(),
+            (_, None) => ValueType::UInt64, // only count works without a column
+        }
+    }
+
+    fn accumulate(&self, acc: &mut Accumulator, row: &DataCellRow) -> Result<()> {
+        let Some(column) = &self.column else {
+            if let Accumulator::Count(count) = acc {
+                *count += 1;
+            }
+            return Ok(());
+        };
+        let val = column.get(row)?.get_data();
+        if val.is_none() {
+            return Ok(());
+        }
+
+        match acc {
+            Accumulator::Count(count) => *count += 1,
+            Accumulator::Distinct(seen) => {
+                seen.insert(value_to_string(val));
+            }
+            Accumulator::Strings(strings) => strings.push(value_to_string(val)),
+            Accumulator::Mean { sum, count } => {
+                let val = widen(val)?;
+                *sum = if sum.is_none() {
+                    val
+                } else {
+                    checked_add(sum, &val)?
+                };
+                *count += 1;
+            }
+            Accumulator::Value(acc_val) if acc_val.is_none() => *acc_val = val.clone(),
+            Accumulator::Value(acc_val) => match self.function {
+                AggregateFunction::Sum => *acc_val = checked_add(acc_val, val)?,
+                AggregateFunction::Min => {
+                    if compare(val, acc_val)? == Ordering::Less {
+                        *acc_val = val.clone();
+                    }
+                }
+                AggregateFunction::Max => {
+                    if compare(val, acc_val)? == Ordering::Greater {
+                        *acc_val = val.clone();
+                    }
+                }
+                AggregateFunction::Last => *acc_val = val.clone(),
+                _ => {} // First
+            },
+        }
+        Ok(())
+    }
+
+    fn finish(&self, acc: Accumulator) -> Result<Value> {
+        Ok(match acc {
+            Accumulator::Count(count) => Value::UInt64(count),
+            Accumulator::Distinct(seen) => Value::UInt64(seen.len() as u64),
+            Accumulator::Strings(strings) => {
+                Value::String(strings.join(self.separator.as_deref().unwrap_or(",")))
+            }
+            Accumulator::Mean { sum, count } => match sum {
+                Value::None => Value::None,
+                Value::Int128(i) => Value::Float64(i as f64 / count as f64),
+                Value::Float64(f) => Value::Float64(f / count as f64),
+                Value::Decimal(d) => Value::Decimal(
+                    d.checked_div(Decimal::from(count))
+                        .ok_or_else(|| group_by_err(format!("can't divide {} by {}", d, count)))?,
+                ),
+                _ => unreachable!("we only sum up widened values"),
+            },
+            Accumulator::Value(val) => val,
+        })
+    }
+}
+
+impl DataCellTable {
+    /// See `GroupBy`.
+    pub fn group_by(&self, group_by: &GroupBy) -> Result<DataCellTable> {
+        if let Some(aggregation) = group_by
+            .aggregations
+            .iter()
+            .find(|a| a.column.is_none() && a.function != AggregateFunction::Count)
+        {
+            return Err(group_by_err(format!(
+                "{:?} needs a column",
+                aggregation.function
+            )));
+        }
+
+        // the types of the aggregated columns, taken from the first row
+        let mut column_types: Vec<Option<ValueType>> = vec![None; group_by.aggregations.len()];
+        let mut groups: Vec<(DataCellRow, Vec<Accumulator>)> = Vec::new();
+        let mut group_pos: HashMap<Vec<Option<String>>, usize> = HashMap::new();
+
+        for (row_num, row) in self.iter().enumerate() {
+            let mut keys =
+                DataCellRow::with_capacity(group_by.keys.len() + group_by.aggregations.len());
+            for key in &group_by.keys {
+                keys.push(key.get(row)?.clone());
+            }
+
+            let key = group_key(keys.iter());
+            let pos = match group_pos.get(&key) {
+                Some(pos) => *pos,
+                None => {
+                    let accs = group_by.aggregations.iter().map(|a| a.accumulator());
+                    groups.push((keys, accs.collect()));
+                    group_pos.insert(key, groups.len() - 1);
+                    groups.len() - 1
+                }
+            };
+
+            for (i, aggregation) in group_by.aggregations.iter().enumerate() {
+                if let Some(column) = &aggregation.column {
+                    let column_type = column.get(row)?.get_type_info();
+                    match &column_types[i] {
+                        Some(vt) if vt != column_type => {
+                            return Err(group_by_err(format!(
+                                "row {}: column {} is of type {}, expected {}",
+                                row_num, column, column_type, vt
+                            )))
+                        }
+                        Some(_) => {}
+                        None => column_types[i] = Some(column_type.clone()),
+                    }
+                }
+                aggregation.accumulate(&mut groups[pos].1[i], row)?;
+            }
+        }
+
+        let mut res = DataCellTable::with_capacity(groups.len());
+        for (mut row, accs) in groups {
+            for ((aggregation, acc), column_type) in group_by
+                .aggregations
+                .iter()
+                .zip(accs)
+                .zip(column_types.iter())
+            {
+                row.push_checked(
+                    DataCell::new_with_type_info(
+                        aggregation.target_type(column_type.as_ref()),
+                        aggregation
+                            .target_header
+                            .clone()
+                            .unwrap_or_else(|| aggregation.target_idx.to_string()),
+                        aggregation.target_idx,
+                        aggregation.finish(acc)?,
+                    )?,
+                    None,
+                )?;
+            }
+            res.push(row);
+        }
+        Ok(res)
+    }
+}
+
+impl TransrichTable for GroupBy {
+    fn transrich(&self, table: DataCellTable) -> Result<DataCellTable> {
+        table.group_by(self)
+    }
+
+    fn describe(&self) -> Description {
+        Description::new("GroupBy")
+            .param("keys", &self.keys)
+            .param("aggregations", &self.aggregations)
+    }
+
+    #[cfg(feature = "jsonconf")]
+    fn to_config(&self) -> Option<TableTransformerConfig> {
+        Some(TableTransformerConfig::GroupBy {
+            cfg: GroupByConfig {
+                keys: self.keys.clone(),
+                aggregations: self
+                    .aggregations
+                    .iter()
+                    .map(|agg| AggregationConfig {
+                        column: agg.column.clone(),
+                        function: agg.function,
+                        separator: agg.separator.clone(),
+                        idx: agg.target_idx,
+                        header: agg.target_header.clone(),
+                    })
+                    .collect(),
+            },
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::NaiveDate;
+
+    use super::*;
+
+    fn booking(account: &str, day: u32, amount: Option<Decimal>) -> DataCellRow {
+        let mut row = DataCellRow::new();
+        row.push(
+            DataCell::new(
+                String::from("account"),
+                0,
+                Value::String(String::from(account)),
+            )
+            .unwrap(),
+        );
+        row.push(
+            DataCell::new(
+                String::from("date"),
+                1,
+                Value::NaiveDate(NaiveDate::from_ymd_opt(2022, 7, day).unwrap()),
+            )
+            .unwrap(),
+        );
+        row.push(
+            DataCell::new_with_type_info(
+                ValueType::Decimal,
+                String::from("amount"),
+                2,
+                amount.map_or(Value::None, Value::Decimal),
+            )
+            .unwrap(),
+        );
+        row
+    }
+
+    fn aggregation(column: Option<usize>, function: AggregateFunction, idx: usize) -> Aggregation {
+        Aggregation {
+            column: column.map(ColumnRef::Idx),
+            function,
+            separator: None,
+            target_idx: idx,
+            target_header: None,
+        }
+    }
+
+    #[test]
+    fn group_by() {
+        let table = DataCellTable::from(vec![
+            booking("A", 3, Some(Decimal::new(1050, 2))),
+            booking("B", 1, Some(Decimal::new(-200, 2))),
+            booking("A", 1, None),
+            booking("A", 2, Some(Decimal::new(450, 2))),
+        ]);
+        let group_by = GroupBy {
+            keys: vec![ColumnRef::Name(String::from("account"))],
+            aggregations: vec![
+                aggregation(None, AggregateFunction::Count, 1),
+                aggregation(Some(2), AggregateFunction::Count, 2),
+                aggregation(Some(2), AggregateFunction::Sum, 3),
+                aggregation(Some(2), AggregateFunction::Mean, 4),
+                aggregation(Some(1), AggregateFunction::Min, 5),
+                aggregation(Some(1), AggregateFunction::Last, 6),
+                aggregation(Some(2), AggregateFunction::CountDistinct, 7),
+                Aggregation {
+                    separator: Some(String::from("; ")),
+                    ..aggregation(Some(2), AggregateFunction::StringAgg, 8)
+                },
+            ],
+        };
+        let grouped = table.group_by(&group_by).unwrap();
+        assert_eq!(2, grouped.len());
+
+        let a = grouped.get(0).unwrap();
+        assert_eq!(
+            vec!["account", "1", "2", "3", "4", "5", "6", "7", "8"],
+            a.headers()
+        );
+        assert_eq!(
+            vec![
+                Value::String(String::from("A")),
+                Value::UInt64(3),
+                Value::UInt64(2),
+                Value::Decimal(Decimal::new(1500, 2)),
+                Value::Decimal(Decimal::new(750, 2)),
+                Value::NaiveDate(NaiveDate::from_ymd_opt(2022, 7, 1).unwrap()),
+                Value::NaiveDate(NaiveDate::from_ymd_opt(2022, 7, 2).unwrap()),
+                Value::UInt64(2),
+                Value::String(String::from("10.50; 4.50")),
+            ],
+            a.clone().into_values()
+        );
+        assert_eq!(
+            &ValueType::Decimal,
+            a.get_by_idx(4).unwrap().get_type_info()
+        );
+        assert_eq!(
+            &ValueType::NaiveDate,
+            a.get_by_idx(5).unwrap().get_type_info()
+        );
+
+        // dates can't be summed up
+        let group_by = GroupBy {
+            keys: vec![ColumnRef::Idx(0)],
+            aggregations: vec![aggregation(Some(1), AggregateFunction::Sum, 3)],
+        };
+        assert!(table.group_by(&group_by).is_err());
+    }
+
+    #[test]
+    fn group_by_overflow_and_mean() {
+        let mut table = DataCellTable::new();
+        for val in [100_u8, 100, 100] {
+            let mut row = DataCellRow::new();
+            row.push(DataCell::new(String::from("val"), 0, Value::UInt8(val)).unwrap());
+            table.push(row);
+        }
+        let group_by = GroupBy {
+            keys: Vec::new(),
+            aggregations: vec![aggregation(Some(0), AggregateFunction::Mean, 1)],
+        };
+        assert_eq!(
+            vec![Value::Float64(100.0)],
+            table.group_by(&group_by).unwrap().into_rows()[0]
+                .clone()
+                .into_values()
+        );
+
+        let group_by = GroupBy {
+            keys: Vec::new(),
+            aggregations: vec![aggregation(Some(0), AggregateFunction::Sum, 1)],
+        };
+        assert!(table.group_by(&group_by).is_err());
+    }
+}
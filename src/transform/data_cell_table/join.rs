@@ -0,0 +1,503 @@
+use std::collections::{BTreeMap, HashMap};
+use std::fmt::Debug;
+
+use venum::{value::Value, value_type::ValueType};
+
+#[cfg(feature = "jsonconf")]
+use crate::conf::jsonconf::{LookupEnrichConfig, TransformerConfig};
+use crate::{
+    data_cell::DataCell,
+    data_cell_row::{ColumnRef, DataCellRow},
+    data_cell_table::DataCellTable,
+    errors::{ContainerOpsErrors, Result, VenumTdsError},
+    transform::{data_cell_row::transrich_explode::TransrichExplode, description::Description},
+    value_fmt::value_to_string,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(
+    feature = "jsonconf",
+    derive(serde::Deserialize, serde::Serialize),
+    serde(rename_all = "camelCase")
+)]
+#[cfg_attr(feature = "jsonschema", derive(schemars::JsonSchema))]
+pub enum JoinKind {
+    /// One row per matching pair of left and right rows.
+    Inner,
+    /// Like `inner`, but left rows without a match are kept, with `None` values for the right
+    /// columns. This is the default, since joins mostly enrich rows, without dropping any.
+    #[default]
+    Left,
+    /// The left rows that have a match, once, without any right columns.
+    Semi,
+    /// The left rows that don't have a match.
+    Anti,
+}
+
+/// Joins rows (left) with other rows (right), by the values of their key columns. The keys are
+/// compared by their stringified values, so they don't need to be of the same type. A key with a
+/// `None` value never matches.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Join {
+    pub kind: JoinKind,
+    pub left_keys: Vec<ColumnRef>,
+    pub right_keys: Vec<ColumnRef>,
+    /// The right columns to add to the left rows. Without, all columns but the keys, in column
+    /// order. If there are no right rows at all, a left join takes the right columns given by
+    /// header from here, as `String` columns at their position in this list.
+    pub right_columns: Option<Vec<ColumnRef>>,
+    /// If a right column has the same header as a left column, the left header gets this suffix.
+    pub left_suffix: String,
+    /// ... and the right header this one.
+    pub right_suffix: String,
+    /// The idx of a right column is its own idx plus this. Without, the right columns follow the
+    /// highest idx of the left row.
+    pub right_idx_offset: Option<usize>,
+}
+
+impl Default for Join {
+    fn default() -> Self {
+        Self {
+            kind: JoinKind::default(),
+            left_keys: Vec::new(),
+            right_keys: Vec::new(),
+            right_columns: None,
+            left_suffix: String::new(),
+            right_suffix: String::from("_right"),
+            right_idx_offset: None,
+        }
+    }
+}
+
+/// The right side of a join, reduced to the right columns and indexed by its keys.
+#[derive(Clone)]
+pub struct JoinIndex {
+    rows: Vec<DataCellRow>,
+    by_key: HashMap<Vec<String>, Vec<usize>>,
+    /// The right columns, without values, for left joins without a match. Taken from the first
+    /// right row (whatever its keys), or from the right columns given by header.
+    template: Option<DataCellRow>,
+}
+
+// The index can be huge, so we don't print it.
+impl Debug for JoinIndex {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("JoinIndex")
+            .field("rows", &self.rows.len())
+            .field("keys", &self.by_key.len())
+            .finish()
+    }
+}
+
+impl JoinIndex {
+    /// The matching right rows.
+    pub fn get(&self, key: &[String]) -> Vec<&DataCellRow> {
+        self.by_key
+            .get(key)
+            .map(|positions| positions.iter().map(|pos| &self.rows[*pos]).collect())
+            .unwrap_or_default()
+    }
+
+    pub fn len(&self) -> usize {
+        self.rows.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rows.is_empty()
+    }
+}
+
+/// The stringified values of the keys, or `None` if any of them is `None`.
+fn join_key(row: &DataCellRow, keys: &[ColumnRef]) -> Result<Option<Vec<String>>> {
+    let mut res: Vec<String> = Vec::with_capacity(keys.len());
+    for key in keys {
+        let val = key.get(row)?.get_data();
+        if val.is_none() {
+            return Ok(None);
+        }
+        res.push(value_to_string(val));
+    }
+    Ok(Some(res))
+}
+
+impl Join {
+    /// Indexes the right rows.
+    pub fn index<I: IntoIterator<Item = DataCellRow>>(&self, right: I) -> Result<JoinIndex> {
+        if self.left_keys.len() != self.right_keys.len() {
+            return Err(VenumTdsError::ContainerOps(ContainerOpsErrors::Generic {
+                msg: format!(
+                    "can't join {} left keys with {} right keys",
+                    self.left_keys.len(),
+                    self.right_keys.len()
+                ),
+            }));
+        }
+        let mut index = JoinIndex {
+            rows: Vec::new(),
+            by_key: HashMap::new(),
+            template: None,
+        };
+        for row in right {
+            let key = join_key(&row, &self.right_keys)?;
+            if key.is_none() && index.template.is_some() {
+                continue;
+            }
+            let cells = self.right_cells(&row)?;
+            if index.template.is_none() {
+                let mut template = cells.clone();
+                template.iter_mut().for_each(|dc| dc.set_data(Value::None));
+                index.template = Some(template);
+            }
+            let Some(key) = key else {
+                continue;
+            };
+            index.rows.push(cells);
+            index
+                .by_key
+                .entry(key)
+                .or_default()
+                .push(index.rows.len() - 1);
+        }
+        if index.template.is_none() {
+            index.template = self.template_from_headers();
+        }
+        Ok(index)
+    }
+
+    /// The right row, reduced to the right columns.
+    fn right_cells(&self, row: &DataCellRow) -> Result<DataCellRow> {
+        match &self.right_columns {
+            Some(columns) => {
+                let mut cells = DataCellRow::with_capacity(columns.len());
+                for column in columns {
+                    cells.push(column.get(row)?.clone());
+                }
+                Ok(cells)
+            }
+            None => {
+                let mut cells = row.clone();
+                for key in &self.right_keys {
+                    let idx = key.get(row)?.get_idx();
+                    cells.del_by_idx(idx)?;
+                }
+                cells.sort_by_idx();
+                Ok(cells)
+            }
+        }
+    }
+
+    /// The right columns without any right rows, if they are all given by header.
+    fn template_from_headers(&self) -> Option<DataCellRow> {
+        let columns = self.right_columns.as_ref()?;
+        let mut template = DataCellRow::with_capacity(columns.len());
+        for (idx, column) in columns.iter().enumerate() {
+            let ColumnRef::Name(name) = column else {
+                return None;
+            };
+            template.push(DataCell::new_without_data(
+                ValueType::String,
+                name.clone(),
+                idx,
+            ));
+        }
+        Some(template)
+    }
+
+    /// Adds the right cells to the left row, see `left_suffix`, `right_suffix` and
+    /// `right_idx_offset`.
+    pub fn merge_into(&self, left: &mut DataCellRow, right: &DataCellRow) -> Result<()> {
+        let next_idx = left.max_idx().map_or(0, |idx| idx + 1);
+        for (i, dc) in right.iter().enumerate() {
+            let mut dc: DataCell = dc.clone();
+            dc.set_idx(match self.right_idx_offset {
+                Some(offset) => dc.get_idx() + offset,
+                None => next_idx + i,
+            });
+            if let Some(left_dc) = left.get_by_name_mut(dc.get_name()) {
+                if !self.left_suffix.is_empty() {
+                    let name = format!("{}{}", left_dc.get_name(), self.left_suffix);
+                    left_dc.set_name(&name);
+                }
+                let name = format!("{}{}", dc.get_name(), self.right_suffix);
+                dc.set_name(&name);
+            }
+            left.push_checked(dc, None)?;
+        }
+        Ok(())
+    }
+
+    /// Joins a single left row, see `JoinKind`.
+    pub fn join_row(&self, left: DataCellRow, index: &JoinIndex) -> Result<Vec<DataCellRow>> {
+        let matches = match join_key(&left, &self.left_keys)? {
+            Some(key) => index.get(&key),
+            None => Vec::new(),
+        };
+        match self.kind {
+            JoinKind::Semi if matches.is_empty() => Ok(Vec::new()),
+            JoinKind::Anti if !matches.is_empty() => Ok(Vec::new()),
+            JoinKind::Semi | JoinKind::Anti => Ok(vec![left]),
+            JoinKind::Left if matches.is_empty() => {
+                let template = index.template.as_ref().ok_or_else(|| {
+                    VenumTdsError::ContainerOps(ContainerOpsErrors::Generic {
+                        msg: String::from(
+                            "can't left join without any right rows, unless the right columns are given by header",
+                        ),
+                    })
+                })?;
+                let mut row = left;
+                self.merge_into(&mut row, template)?;
+                Ok(vec![row])
+            }
+            JoinKind::Inner | JoinKind::Left => {
+                let mut rows: Vec<DataCellRow> = Vec::with_capacity(matches.len());
+                for right in matches {
+                    let mut row = left.clone();
+                    self.merge_into(&mut row, right)?;
+                    rows.push(row);
+                }
+                Ok(rows)
+            }
+        }
+    }
+}
+
+impl DataCellTable {
+    /// Hash joins the rows of this table (left) with the rows of the other one (right), see
+    /// `Join`. The left order is kept, and for every left row, the right order.
+    pub fn join(&self, right: &DataCellTable, join: &Join) -> Result<DataCellTable> {
+        let index = join.index(right.iter().cloned())?;
+        let mut res = DataCellTable::with_capacity(self.len());
+        for row in self {
+            res.extend(join.join_row(row.clone(), &index)?);
+        }
+        Ok(res)
+    }
+}
+
+/// Joins every row with a fixed set of (reference) rows, e.g. master data from a file. Since the
+/// right side is indexed up front, this works row by row.
+#[derive(Debug)]
+pub struct LookupEnrich {
+    pub join: Join,
+    pub index: JoinIndex,
+    /// The file the reference rows were read from, if any. Only needed to write the config.
+    pub file: Option<String>,
+}
+
+impl LookupEnrich {
+    pub fn new<I: IntoIterator<Item = DataCellRow>>(join: Join, reference: I) -> Result<Self> {
+        let index = join.index(reference)?;
+        Ok(Self {
+            join,
+            index,
+            file: None,
+        })
+    }
+
+    pub fn with_file<S: Into<String>>(mut self, file: S) -> Self {
+        self.file = Some(file.into());
+        self
+    }
+}
+
+impl TransrichExplode for LookupEnrich {
+    fn explode(&self, data_cell_row: DataCellRow) -> Result<Vec<DataCellRow>> {
+        self.join.join_row(data_cell_row, &self.index)
+    }
+
+    fn describe(&self) -> Description {
+        // the reference rows by key, in key order, since the order of the rows doesn't matter
+        let by_key: BTreeMap<&Vec<String>, Vec<&[DataCell]>> = self
+            .index
+            .by_key
+            .iter()
+            .map(|(key, positions)| {
+                let rows = positions
+                    .iter()
+                    .map(|pos| self.index.rows[*pos].as_slice())
+                    .collect();
+                (key, rows)
+            })
+            .collect();
+        Description::new("LookupEnrich")
+            .param("join", &self.join)
+            .data("reference", by_key)
+    }
+
+    #[cfg(feature = "jsonconf")]
+    fn to_config(&self) -> Option<TransformerConfig> {
+        // without the file, there is nothing to read the reference rows from
+        Some(TransformerConfig::LookupEnrich {
+            cfg: LookupEnrichConfig {
+                file: self.file.clone()?,
+                keys: self.join.left_keys.clone(),
+                reference_keys: self.join.right_keys.clone(),
+                kind: Some(self.join.kind),
+                columns: self.join.right_columns.clone(),
+                left_suffix: Some(self.join.left_suffix.clone()),
+                right_suffix: Some(self.join.right_suffix.clone()),
+                idx_offset: self.join.right_idx_offset,
+            },
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use venum::value_type::ValueType;
+
+    use super::*;
+
+    fn row(cells: &[(&str, usize, Option<&str>)]) -> DataCellRow {
+        let mut row = DataCellRow::new();
+        for (name, idx, val) in cells {
+            row.push(
+                DataCell::new_with_type_info(
+                    ValueType::String,
+                    String::from(*name),
+                    *idx,
+                    val.map_or(Value::None, |v| Value::String(String::from(v))),
+                )
+                .unwrap(),
+            );
+        }
+        row
+    }
+
+    fn tables() -> (DataCellTable, DataCellTable) {
+        let bookings = DataCellTable::from(vec![
+            row(&[("account", 0, Some("A")), ("name", 1, Some("rent"))]),
+            row(&[("account", 0, Some("B")), ("name", 1, Some("food"))]),
+            row(&[("account", 0, None), ("name", 1, Some("misc"))]),
+        ]);
+        let accounts = DataCellTable::from(vec![
+            row(&[("id", 0, Some("A")), ("name", 1, Some("Alice"))]),
+            row(&[("id", 0, Some("C")), ("name", 1, Some("Carol"))]),
+            row(&[("id", 0, Some("A")), ("name", 1, Some("Alice 2"))]),
+        ]);
+        (bookings, accounts)
+    }
+
+    fn join(kind: JoinKind) -> Join {
+        Join {
+            kind,
+            left_keys: vec![ColumnRef::Name(String::from("account"))],
+            right_keys: vec![ColumnRef::Idx(0)],
+            ..Join::default()
+        }
+    }
+
+    #[test]
+    fn join_kinds() {
+        let (bookings, accounts) = tables();
+
+        let inner = bookings.join(&accounts, &join(JoinKind::Inner)).unwrap();
+        assert_eq!(2, inner.len());
+        assert_eq!(
+            &row(&[
+                ("account", 0, Some("A")),
+                ("name", 1, Some("rent")),
+                ("name_right", 2, Some("Alice 2"))
+            ]),
+            inner.get(1).unwrap()
+        );
+
+        let left = bookings.join(&accounts, &join(JoinKind::Left)).unwrap();
+        assert_eq!(4, left.len());
+        assert_eq!(
+            &row(&[
+                ("account", 0, Some("B")),
+                ("name", 1, Some("food")),
+                ("name_right", 2, None)
+            ]),
+            left.get(2).unwrap()
+        );
+
+        let semi = bookings.join(&accounts, &join(JoinKind::Semi)).unwrap();
+        assert_eq!(
+            vec![bookings.get(0).unwrap()],
+            semi.iter().collect::<Vec<_>>()
+        );
+
+        let anti = bookings.join(&accounts, &join(JoinKind::Anti)).unwrap();
+        assert_eq!(2, anti.len());
+        assert_eq!(bookings.get(2), anti.get(1));
+
+        // the right columns of a left join don't depend on the right rows having keys
+        let no_right_keys: DataCellTable = accounts
+            .iter()
+            .map(|row| {
+                let mut row = row.clone();
+                row.get_by_idx_mut(0).unwrap().set_data(Value::None);
+                row
+            })
+            .collect();
+        let left = bookings
+            .join(&no_right_keys, &join(JoinKind::Left))
+            .unwrap();
+        assert_eq!(
+            &row(&[
+                ("account", 0, Some("A")),
+                ("name", 1, Some("rent")),
+                ("name_right", 2, None)
+            ]),
+            left.get(0).unwrap()
+        );
+
+        // without any right rows, they come from the right columns given by header
+        let empty = DataCellTable::new();
+        assert!(bookings.join(&empty, &join(JoinKind::Left)).is_err());
+        let by_header = Join {
+            right_columns: Some(vec![ColumnRef::Name(String::from("name"))]),
+            ..join(JoinKind::Left)
+        };
+        assert_eq!(left, bookings.join(&empty, &by_header).unwrap());
+
+        let no_keys = Join {
+            right_keys: Vec::new(),
+            ..join(JoinKind::Inner)
+        };
+        assert!(bookings.join(&accounts, &no_keys).is_err());
+    }
+
+    #[test]
+    fn lookup_enrich() {
+        let (bookings, accounts) = tables();
+        let lookup = LookupEnrich::new(
+            Join {
+                right_columns: Some(vec![ColumnRef::Name(String::from("name"))]),
+                left_suffix: String::from("_booking"),
+                right_suffix: String::from("_account"),
+                right_idx_offset: Some(10),
+                ..join(JoinKind::Left)
+            },
+            accounts,
+        )
+        .unwrap();
+        assert_eq!(3, lookup.index.len());
+
+        let rows = lookup.explode(bookings.get(0).unwrap().clone()).unwrap();
+        assert_eq!(2, rows.len());
+        assert_eq!(
+            vec!["account", "name_booking", "name_account"],
+            rows[0].headers()
+        );
+        assert_eq!(
+            &Value::String(String::from("Alice")),
+            rows[0].get_by_idx(11).unwrap().get_data()
+        );
+
+        // the description covers the reference rows
+        let describe = |accounts: DataCellTable| {
+            LookupEnrich::new(join(JoinKind::Left), accounts)
+                .unwrap()
+                .describe()
+        };
+        let (_, accounts) = tables();
+        assert_eq!(describe(accounts.clone()), describe(accounts.clone()));
+        assert_ne!(
+            describe(accounts.clone()),
+            describe(accounts.into_iter().take(2).collect())
+        );
+    }
+}
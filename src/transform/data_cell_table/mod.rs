@@ -0,0 +1,8 @@
+pub mod dedup;
+#[cfg(feature = "extsort")]
+pub mod external_sort;
+pub mod group_by;
+pub mod join;
+pub mod reshape;
+pub mod sort;
+pub mod transrich_table;
@@ -0,0 +1,434 @@
+use std::cmp::Ordering;
+use std::collections::HashMap;
+
+use venum::value::Value;
+use venum::value_type::ValueType;
+
+#[cfg(feature = "jsonconf")]
+use crate::conf::jsonconf::{MeltConfig, PivotConfig, TableTransformerConfig};
+use crate::{
+    data_cell::DataCell,
+    data_cell_row::DataCellRow,
+    data_cell_table::DataCellTable,
+    errors::{ContainerOpsErrors, DataAccessErrors, Result, VenumTdsError},
+    transform::{
+        data_cell_row::transrich_explode::convert_to,
+        description::Description,
+        value::aggregating::{checked_add, compare},
+    },
+    value_fmt::value_to_string,
+};
+
+use super::{group_by::group_key, transrich_table::TransrichTable};
+
+fn reshape_err(msg: String) -> VenumTdsError {
+    VenumTdsError::ContainerOps(ContainerOpsErrors::ReshapeError { msg })
+}
+
+fn get_cell(row: &DataCellRow, idx: usize) -> Result<&DataCell> {
+    row.get_by_idx(idx)
+        .ok_or_else(|| VenumTdsError::DataAccess(DataAccessErrors::IllegalIdxAccess { idx }))
+}
+
+/// Melts (unpivots) value columns into rows: every value column of a row becomes a row of its own,
+/// holding the id columns, the header of the value column as key and its value as value. E.g.
+/// `id, jan, feb` becomes the rows `id, month, amount` for `jan` and `feb`. Everything that is
+/// neither an id nor a value column is dropped.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Melt {
+    pub id_idxs: Vec<usize>,
+    /// Without, all columns that are not id columns, in column order.
+    pub value_idxs: Option<Vec<usize>>,
+    /// Type, header and idx of the key column. The headers of the value columns are parsed as this
+    /// type.
+    pub key_target: DataCell,
+    /// Type, header and idx of the value column. The values are converted to this type.
+    pub value_target: DataCell,
+}
+
+/// What to do, if there is more than one value for the same id columns and key.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(
+    feature = "jsonconf",
+    derive(serde::Deserialize, serde::Serialize),
+    serde(rename_all = "camelCase")
+)]
+#[cfg_attr(feature = "jsonschema", derive(schemars::JsonSchema))]
+pub enum PivotAggregation {
+    /// More than one value is an error.
+    #[default]
+    Error,
+    First,
+    Last,
+    /// `None` values are ignored by `sum`, `min`, `max` and `count`.
+    Sum,
+    Min,
+    Max,
+    /// The number of values, as `UInt64`. Pivoted columns without any value are `0`, not `None`.
+    Count,
+}
+
+/// Pivots the distinct values of a key column into columns of their own: there is one row per
+/// distinct combination of id column values, holding the id columns and one column per key, with
+/// the (aggregated) value of the value column. Columns without a value for a row are `None`.
+/// Everything else is dropped. E.g. the rows `id, month, amount` become `id, jan, feb`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Pivot {
+    pub id_idxs: Vec<usize>,
+    pub key_idx: usize,
+    pub value_idx: usize,
+    pub aggregation: PivotAggregation,
+    /// The (stringified) keys that become columns, in this order. Rows with other keys are
+    /// ignored. Without, all distinct keys become columns, in order of appearance.
+    pub columns: Option<Vec<String>>,
+    /// The header of a pivoted column is the key, prefixed with this.
+    pub header_prefix: String,
+    /// The idx of the first pivoted column. Without, the one after the highest id idx.
+    pub start_idx: Option<usize>,
+}
+
+#[derive(Debug)]
+struct Aggregate {
+    value: Value,
+    count: u64,
+}
+
+impl Pivot {
+    fn aggregate(&self, acc: Option<Aggregate>, val: &Value, column: &str) -> Result<Aggregate> {
+        let count = u64::from(val.is_some());
+        let Some(mut acc) = acc else {
+            return Ok(Aggregate {
+                value: val.clone(),
+                count,
+            });
+        };
+        match self.aggregation {
+            PivotAggregation::Error => {
+                return Err(reshape_err(format!(
+                    "more than one value for column {:?}",
+                    column
+                )))
+            }
+            PivotAggregation::First | PivotAggregation::Count => {}
+            PivotAggregation::Last => acc.value = val.clone(),
+            _ if val.is_none() => {}
+            _ if acc.value.is_none() => acc.value = val.clone(),
+            PivotAggregation::Sum => acc.value = checked_add(&acc.value, val)?,
+            PivotAggregation::Min => {
+                if compare(val, &acc.value)? == Ordering::Less {
+                    acc.value = val.clone();
+                }
+            }
+            PivotAggregation::Max => {
+                if compare(val, &acc.value)? == Ordering::Greater {
+                    acc.value = val.clone();
+                }
+            }
+        }
+        acc.count += count;
+        Ok(acc)
+    }
+}
+
+impl DataCellTable {
+    /// See `Melt`.
+    pub fn melt(&self, melt: &Melt) -> Result<DataCellTable> {
+        let mut res = DataCellTable::with_capacity(self.len());
+        for row in self {
+            let mut rest = row.clone();
+            let ids = rest.take_by_idx(&melt.id_idxs)?;
+            let values = match &melt.value_idxs {
+                Some(idxs) => rest.take_by_idx(idxs)?,
+                None => {
+                    rest.sort_by_idx();
+                    rest
+                }
+            };
+
+            for dc in values {
+                let mut melted = ids.clone();
+                let mut key = melt.key_target.clone();
+                key.set_data(Value::from_str_and_type(
+                    dc.get_name(),
+                    key.get_type_info(),
+                )?);
+                melted.push_checked(key, None)?;
+                let mut value = melt.value_target.clone();
+                value.set_data(convert_to(dc.data, value.get_type_info())?);
+                melted.push_checked(value, None)?;
+                res.push(melted);
+            }
+        }
+        Ok(res)
+    }
+
+    /// See `Pivot`. The rows come out in order of the first appearance of their id column values.
+    pub fn pivot(&self, pivot: &Pivot) -> Result<DataCellTable> {
+        let mut columns: Vec<String> = pivot.columns.clone().unwrap_or_default();
+        let mut column_pos: HashMap<String, usize> = columns.iter().cloned().zip(0..).collect();
+        let mut value_type: Option<ValueType> = None;
+        let mut groups: Vec<(DataCellRow, Vec<Option<Aggregate>>)> = Vec::new();
+        let mut group_pos: HashMap<Vec<Option<String>>, usize> = HashMap::new();
+
+        for row in self {
+            let value_dc = get_cell(row, pivot.value_idx)?;
+            match &value_type {
+                Some(vt) if vt != value_dc.get_type_info() => {
+                    return Err(reshape_err(format!(
+                        "values of different types: {} and {}",
+                        vt,
+                        value_dc.get_type_info()
+                    )))
+                }
+                Some(_) => {}
+                None => value_type = Some(value_dc.get_type_info().clone()),
+            }
+
+            let key = get_cell(row, pivot.key_idx)?.get_data();
+            if key.is_none() {
+                return Err(reshape_err(format!(
+                    "key (idx {}) is None, can't pivot it into a column",
+                    pivot.key_idx
+                )));
+            }
+            let key = value_to_string(key);
+            let col = match column_pos.get(&key) {
+                Some(pos) => *pos,
+                None if pivot.columns.is_some() => continue,
+                None => {
+                    columns.push(key.clone());
+                    column_pos.insert(key.clone(), columns.len() - 1);
+                    columns.len() - 1
+                }
+            };
+
+            let mut ids = DataCellRow::with_capacity(pivot.id_idxs.len() + columns.len());
+            for idx in &pivot.id_idxs {
+                ids.push(get_cell(row, *idx)?.clone());
+            }
+            let group_key = group_key(ids.iter());
+            let pos = match group_pos.get(&group_key) {
+                Some(pos) => *pos,
+                None => {
+                    groups.push((ids, Vec::new()));
+                    group_pos.insert(group_key, groups.len() - 1);
+                    groups.len() - 1
+                }
+            };
+
+            let aggregates = &mut groups[pos].1;
+            if aggregates.len() <= col {
+                aggregates.resize_with(col + 1, || None);
+            }
+            aggregates[col] =
+                Some(pivot.aggregate(aggregates[col].take(), value_dc.get_data(), &key)?);
+        }
+
+        let Some(value_type) = value_type else {
+            return Ok(DataCellTable::new());
+        };
+        let column_type = match pivot.aggregation {
+            PivotAggregation::Count => ValueType::UInt64,
+            _ => value_type,
+        };
+        let start_idx = pivot
+            .start_idx
+            .unwrap_or_else(|| pivot.id_idxs.iter().max().map_or(0, |idx| idx + 1));
+
+        let mut res = DataCellTable::with_capacity(groups.len());
+        for (mut row, aggregates) in groups {
+            let mut aggregates = aggregates.into_iter();
+            for (i, column) in columns.iter().enumerate() {
+                let data = match (aggregates.next().flatten(), pivot.aggregation) {
+                    (Some(acc), PivotAggregation::Count) => Value::UInt64(acc.count),
+                    (None, PivotAggregation::Count) => Value::UInt64(0),
+                    (Some(acc), _) => acc.value,
+                    (None, _) => Value::None,
+                };
+                row.push_checked(
+                    DataCell::new_with_type_info(
+                        column_type.clone(),
+                        format!("{}{}", pivot.header_prefix, column),
+                        start_idx + i,
+                        data,
+                    )?,
+                    None,
+                )?;
+            }
+            res.push(row);
+        }
+        Ok(res)
+    }
+}
+
+impl TransrichTable for Melt {
+    fn transrich(&self, table: DataCellTable) -> Result<DataCellTable> {
+        table.melt(self)
+    }
+
+    fn describe(&self) -> Description {
+        Description::new("Melt")
+            .param("id_idxs", &self.id_idxs)
+            .param("value_idxs", &self.value_idxs)
+            .param("key_target", &self.key_target)
+            .param("value_target", &self.value_target)
+    }
+
+    #[cfg(feature = "jsonconf")]
+    fn to_config(&self) -> Option<TableTransformerConfig> {
+        Some(TableTransformerConfig::Melt {
+            cfg: MeltConfig {
+                id_idxs: self.id_idxs.clone(),
+                value_idxs: self.value_idxs.clone(),
+                key: (&self.key_target).into(),
+                value: (&self.value_target).into(),
+            },
+        })
+    }
+}
+
+impl TransrichTable for Pivot {
+    fn transrich(&self, table: DataCellTable) -> Result<DataCellTable> {
+        table.pivot(self)
+    }
+
+    fn describe(&self) -> Description {
+        Description::new("Pivot")
+            .param("id_idxs", &self.id_idxs)
+            .param("key_idx", self.key_idx)
+            .param("value_idx", self.value_idx)
+            .param("aggregation", self.aggregation)
+            .param("columns", &self.columns)
+            .param("header_prefix", &self.header_prefix)
+            .param("start_idx", self.start_idx)
+    }
+
+    #[cfg(feature = "jsonconf")]
+    fn to_config(&self) -> Option<TableTransformerConfig> {
+        Some(TableTransformerConfig::Pivot {
+            cfg: PivotConfig {
+                id_idxs: self.id_idxs.clone(),
+                key_idx: self.key_idx,
+                value_idx: self.value_idx,
+                aggregation: Some(self.aggregation),
+                columns: self.columns.clone(),
+                header_prefix: Some(self.header_prefix.clone()),
+                start_idx: self.start_idx,
+            },
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sales_row(region: &str, month: &str, amount: Option<i64>) -> DataCellRow {
+        let mut row = DataCellRow::new();
+        row.push(
+            DataCell::new(
+                String::from("region"),
+                0,
+                Value::String(String::from(region)),
+            )
+            .unwrap(),
+        );
+        row.push(
+            DataCell::new(String::from("month"), 1, Value::String(String::from(month))).unwrap(),
+        );
+        row.push(
+            DataCell::new_with_type_info(
+                ValueType::Int64,
+                String::from("amount"),
+                2,
+                amount.map_or(Value::None, Value::Int64),
+            )
+            .unwrap(),
+        );
+        row
+    }
+
+    fn pivot(aggregation: PivotAggregation) -> Pivot {
+        Pivot {
+            id_idxs: vec![0],
+            key_idx: 1,
+            value_idx: 2,
+            aggregation,
+            columns: None,
+            header_prefix: String::new(),
+            start_idx: None,
+        }
+    }
+
+    #[test]
+    fn pivot_and_melt() {
+        let table = DataCellTable::from(vec![
+            sales_row("north", "jan", Some(10)),
+            sales_row("south", "jan", Some(5)),
+            sales_row("north", "feb", Some(7)),
+            sales_row("north", "feb", Some(4)),
+        ]);
+
+        let pivoted = table.pivot(&pivot(PivotAggregation::Sum)).unwrap();
+        assert_eq!(2, pivoted.len());
+        let north = pivoted.get(0).unwrap();
+        assert_eq!(vec!["region", "jan", "feb"], north.headers());
+        assert_eq!(
+            vec![&ValueType::String, &ValueType::Int64, &ValueType::Int64],
+            north.types()
+        );
+        assert_eq!(&Value::Int64(10), north.get_by_idx(1).unwrap().get_data());
+        assert_eq!(&Value::Int64(11), north.get_by_idx(2).unwrap().get_data());
+        let south = pivoted.get(1).unwrap();
+        assert_eq!(&Value::Int64(5), south.get_by_idx(1).unwrap().get_data());
+        assert_eq!(&Value::None, south.get_by_idx(2).unwrap().get_data());
+
+        // collisions fail by default
+        assert!(table.pivot(&pivot(PivotAggregation::Error)).is_err());
+
+        let melted = pivoted
+            .melt(&Melt {
+                id_idxs: vec![0],
+                value_idxs: None,
+                key_target: DataCell::new_without_data(ValueType::String, String::from("month"), 1),
+                value_target: DataCell::new_without_data(
+                    ValueType::Int64,
+                    String::from("amount"),
+                    2,
+                ),
+            })
+            .unwrap();
+        assert_eq!(4, melted.len());
+        assert_eq!(&sales_row("north", "jan", Some(10)), melted.get(0).unwrap());
+        assert_eq!(&sales_row("north", "feb", Some(11)), melted.get(1).unwrap());
+        assert_eq!(&sales_row("south", "jan", Some(5)), melted.get(2).unwrap());
+        assert_eq!(&sales_row("south", "feb", None), melted.get(3).unwrap());
+    }
+
+    #[test]
+    fn pivot_columns_and_count() {
+        let table = DataCellTable::from(vec![
+            sales_row("north", "jan", Some(10)),
+            sales_row("north", "jan", None),
+            sales_row("north", "mar", Some(1)),
+        ]);
+        let pivoted = table
+            .pivot(&Pivot {
+                columns: Some(vec![String::from("jan"), String::from("feb")]),
+                header_prefix: String::from("n_"),
+                start_idx: Some(10),
+                ..pivot(PivotAggregation::Count)
+            })
+            .unwrap();
+        assert_eq!(1, pivoted.len());
+        let north = pivoted.get(0).unwrap();
+        assert_eq!(vec!["region", "n_jan", "n_feb"], north.headers());
+        assert_eq!(&Value::UInt64(1), north.get_by_idx(10).unwrap().get_data());
+        assert_eq!(&Value::UInt64(0), north.get_by_idx(11).unwrap().get_data());
+
+        assert!(DataCellTable::new()
+            .pivot(&pivot(PivotAggregation::Sum))
+            .unwrap()
+            .is_empty());
+    }
+}
@@ -0,0 +1,237 @@
+use std::cmp::Ordering;
+
+use venum::value::Value;
+
+#[cfg(feature = "jsonconf")]
+use crate::conf::jsonconf::{SortConfig, TableTransformerConfig};
+use crate::{
+    data_cell_row::{ColumnRef, DataCellRow},
+    data_cell_table::DataCellTable,
+    errors::Result,
+    transform::{
+        description::Description,
+        value::ordering::{compare_values, Collation},
+    },
+};
+
+use super::transrich_table::TransrichTable;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(
+    feature = "jsonconf",
+    derive(serde::Deserialize, serde::Serialize),
+    serde(rename_all = "camelCase")
+)]
+#[cfg_attr(feature = "jsonschema", derive(schemars::JsonSchema))]
+pub enum NullOrder {
+    First,
+    #[default]
+    Last,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "jsonconf",
+    derive(serde::Deserialize, serde::Serialize),
+    serde(rename_all = "camelCase")
+)]
+#[cfg_attr(feature = "jsonschema", derive(schemars::JsonSchema))]
+pub struct SortKey {
+    pub column: ColumnRef,
+    #[cfg_attr(feature = "jsonconf", serde(default))]
+    pub descending: bool,
+    /// Where the `None` values go, no matter the direction.
+    #[cfg_attr(feature = "jsonconf", serde(default))]
+    pub nulls: NullOrder,
+    #[cfg_attr(feature = "jsonconf", serde(default))]
+    pub collation: Collation,
+}
+
+impl SortKey {
+    /// Ascending, nulls last, binary collation.
+    pub fn new(column: ColumnRef) -> Self {
+        Self {
+            column,
+            descending: false,
+            nulls: NullOrder::Last,
+            collation: Collation::default(),
+        }
+    }
+
+    fn compare(&self, a: &Value, b: &Value) -> Ordering {
+        match (a.is_none(), b.is_none()) {
+            (true, true) => Ordering::Equal,
+            (true, false) if self.nulls == NullOrder::First => Ordering::Less,
+            (true, false) => Ordering::Greater,
+            (false, true) => self.compare(b, a).reverse(),
+            (false, false) if self.descending => compare_values(b, a, &self.collation),
+            (false, false) => compare_values(a, b, &self.collation),
+        }
+    }
+}
+
+/// Sorts rows by multiple keys, the first one taking precedence. The values are compared with
+/// `compare_values`, i.e. by their data only. The sort is stable.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct Sort {
+    pub keys: Vec<SortKey>,
+}
+
+impl Sort {
+    /// The values of the sort keys of a row, to compare with `compare_keys`. (Fails if a key column
+    /// is missing.)
+    pub fn key_values(&self, row: &DataCellRow) -> Result<Vec<Value>> {
+        self.keys
+            .iter()
+            .map(|key| Ok(key.column.get(row)?.get_data().clone()))
+            .collect()
+    }
+
+    pub fn compare_keys(&self, a: &[Value], b: &[Value]) -> Ordering {
+        self.keys
+            .iter()
+            .zip(a.iter().zip(b))
+            .map(|(key, (a, b))| key.compare(a, b))
+            .find(|ord| *ord != Ordering::Equal)
+            .unwrap_or(Ordering::Equal)
+    }
+
+    pub fn compare(&self, a: &DataCellRow, b: &DataCellRow) -> Result<Ordering> {
+        Ok(self.compare_keys(&self.key_values(a)?, &self.key_values(b)?))
+    }
+}
+
+impl DataCellTable {
+    /// Sorts the rows in place, see `Sort`. On error (a missing key column), the table is left
+    /// untouched.
+    pub fn sort(&mut self, sort: &Sort) -> Result<()> {
+        let keys = self
+            .iter()
+            .map(|row| sort.key_values(row))
+            .collect::<Result<Vec<_>>>()?;
+        let mut keyed: Vec<(Vec<Value>, DataCellRow)> =
+            keys.into_iter().zip(std::mem::take(self)).collect();
+        keyed.sort_by(|a, b| sort.compare_keys(&a.0, &b.0));
+        *self = keyed.into_iter().map(|(_, row)| row).collect();
+        Ok(())
+    }
+}
+
+impl TransrichTable for Sort {
+    fn transrich(&self, mut table: DataCellTable) -> Result<DataCellTable> {
+        table.sort(self)?;
+        Ok(table)
+    }
+
+    fn describe(&self) -> Description {
+        Description::new("Sort").param("keys", &self.keys)
+    }
+
+    #[cfg(feature = "jsonconf")]
+    fn to_config(&self) -> Option<TableTransformerConfig> {
+        Some(TableTransformerConfig::Sort {
+            cfg: SortConfig {
+                keys: self.keys.clone(),
+            },
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use venum::value_type::ValueType;
+
+    use crate::data_cell::DataCell;
+
+    use super::*;
+
+    fn row(id: u8, name: Option<&str>, amount: Option<i32>) -> DataCellRow {
+        DataCellRow::from(vec![
+            DataCell::new(String::from("id"), 0, Value::UInt8(id)).unwrap(),
+            DataCell::new_with_type_info(
+                ValueType::String,
+                String::from("name"),
+                1,
+                name.map_or(Value::None, |n| Value::String(String::from(n))),
+            )
+            .unwrap(),
+            DataCell::new_with_type_info(
+                ValueType::Int32,
+                String::from("amount"),
+                2,
+                amount.map_or(Value::None, Value::Int32),
+            )
+            .unwrap(),
+        ])
+    }
+
+    fn ids(table: &DataCellTable) -> Vec<Value> {
+        table
+            .iter()
+            .map(|row| row.get_by_idx(0).unwrap().get_data().clone())
+            .collect()
+    }
+
+    fn table() -> DataCellTable {
+        DataCellTable::from(vec![
+            row(1, Some("b"), Some(10)),
+            row(2, None, Some(5)),
+            row(3, Some("A"), None),
+            row(4, Some("a"), Some(5)),
+            row(5, Some("B"), Some(10)),
+        ])
+    }
+
+    #[test]
+    fn sort_multiple_keys() {
+        let mut t = table();
+        t.sort(&Sort {
+            keys: vec![
+                SortKey {
+                    descending: true,
+                    ..SortKey::new(ColumnRef::Name(String::from("amount")))
+                },
+                SortKey::new(ColumnRef::Idx(1)),
+            ],
+        })
+        .unwrap();
+        // 10s (name "B" before "b"), 5s ("a", then None), None last
+        assert_eq!(
+            vec![5, 1, 4, 2, 3]
+                .into_iter()
+                .map(Value::UInt8)
+                .collect::<Vec<_>>(),
+            ids(&t)
+        );
+
+        let mut t = table();
+        t.sort(&Sort {
+            keys: vec![SortKey {
+                nulls: NullOrder::First,
+                descending: true,
+                collation: Collation {
+                    case_insensitive: true,
+                    natural: false,
+                },
+                ..SortKey::new(ColumnRef::Idx(1))
+            }],
+        })
+        .unwrap();
+        // stable: "b" (1) before "B" (5) and "A" (3) before "a" (4)
+        assert_eq!(
+            vec![2, 1, 5, 3, 4]
+                .into_iter()
+                .map(Value::UInt8)
+                .collect::<Vec<_>>(),
+            ids(&t)
+        );
+
+        let mut t = table();
+        assert!(t
+            .sort(&Sort {
+                keys: vec![SortKey::new(ColumnRef::Idx(7))],
+            })
+            .is_err());
+        assert_eq!(table(), t);
+    }
+}
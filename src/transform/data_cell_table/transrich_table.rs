@@ -0,0 +1,18 @@
+use std::fmt::Debug;
+
+#[cfg(feature = "jsonconf")]
+use crate::conf::jsonconf::TableTransformerConfig;
+use crate::{data_cell_table::DataCellTable, errors::Result, transform::description::Description};
+
+/// Transriches a whole table at once, i.e. anything that needs to see all rows, like reshaping.
+pub trait TransrichTable: Debug {
+    fn transrich(&self, table: DataCellTable) -> Result<DataCellTable>;
+
+    /// What this transricher does, leaving out any state, see `Description`.
+    fn describe(&self) -> Description;
+
+    /// The config this transricher is built from, if it can be written as one, see
+    /// `TransrichPass::to_config`.
+    #[cfg(feature = "jsonconf")]
+    fn to_config(&self) -> Option<TableTransformerConfig>;
+}
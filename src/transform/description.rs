@@ -0,0 +1,175 @@
+use std::fmt::{Debug, Display, Formatter};
+
+/// What a transricher does: its name and its parameters, e.g. `DeleteItemAtIdx(idx: 3)`. Unlike
+/// the `Debug` output, it leaves out any state (counters, seen keys, reports), so two transrichers
+/// that do the same have the same description. That way, a built pipeline can be printed, compared
+/// and hashed (e.g. for caching), see `TransrichPass::describe`.
+///
+/// Data a transricher holds, like a lookup table or reference rows, is described by its size and a
+/// hash of its content (see `data`), no matter whether it was given inline or loaded from a file.
+/// Where rows go (e.g. discarded ones) is described by the target, like the path of the file.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Description {
+    pub name: &'static str,
+    pub params: Vec<(&'static str, String)>,
+}
+
+impl Description {
+    pub fn new(name: &'static str) -> Self {
+        Self {
+            name,
+            params: Vec::new(),
+        }
+    }
+
+    /// Adds a parameter. The value is formatted with `Debug`, so it must not print any state, nor
+    /// anything with an arbitrary order, like a `HashMap`.
+    pub fn param<V: Debug>(mut self, name: &'static str, value: V) -> Self {
+        self.params.push((name, format!("{:?}", value)));
+        self
+    }
+
+    /// Adds a parameter for data: the number of items and a hash of them, instead of the items
+    /// themselves, which may be many. The items are hashed by their `Debug` output, so, like for
+    /// `param`, they must come in a fixed order.
+    pub fn data<I, V>(mut self, name: &'static str, items: I) -> Self
+    where
+        I: IntoIterator<Item = V>,
+        V: Debug,
+    {
+        // FNV-1a, which, unlike `DefaultHasher`, is the same on every platform and Rust version
+        let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+        let mut len: usize = 0;
+        for item in items {
+            // every item ends with a 0 byte, which `Debug` escapes within strings
+            for byte in format!("{:?}", item).bytes().chain([0]) {
+                hash = (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3);
+            }
+            len += 1;
+        }
+        self.params
+            .push((name, format!("{} items, hash {:016x}", len, hash)));
+        self
+    }
+
+    /// Adds a parameter, that holds other transrichers.
+    pub fn nested<I: IntoIterator<Item = Description>>(
+        mut self,
+        name: &'static str,
+        nested: I,
+    ) -> Self {
+        let nested: Vec<String> = nested.into_iter().map(|d| d.to_string()).collect();
+        self.params.push((name, format!("[{}]", nested.join(", "))));
+        self
+    }
+}
+
+/// What a `TransrichPass` does, stage by stage, see `TransrichPass::describe`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Default)]
+pub struct PassDescription {
+    pub stateless: Vec<Description>,
+    pub stateful: Vec<Description>,
+    pub exploding: Vec<Description>,
+    pub ordering: Option<Vec<Description>>,
+    pub table: Vec<Description>,
+    /// See `TransrichPass::with_strict`.
+    pub strict: bool,
+}
+
+impl Display for Description {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.name)?;
+        if self.params.is_empty() {
+            return Ok(());
+        }
+        let params: Vec<String> = self
+            .params
+            .iter()
+            .map(|(name, value)| format!("{}: {}", name, value))
+            .collect();
+        write!(f, "({})", params.join(", "))
+    }
+}
+
+/// One line per transricher, prefixed by its stage, e.g. `stateless: DeleteItemAtIdx(idx: 3)`. A
+/// strict pass starts with a line `strict`.
+impl Display for PassDescription {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        if self.strict {
+            writeln!(f, "strict")?;
+        }
+        let stages = [
+            ("stateless", &self.stateless),
+            ("stateful", &self.stateful),
+            ("exploding", &self.exploding),
+        ];
+        for (stage, descriptions) in stages {
+            for d in descriptions {
+                writeln!(f, "{}: {}", stage, d)?;
+            }
+        }
+        for d in self.ordering.iter().flatten() {
+            writeln!(f, "ordering: {}", d)?;
+        }
+        for d in &self.table {
+            writeln!(f, "table: {}", d)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn display() {
+        assert_eq!(
+            "CompactItemIdx",
+            Description::new("CompactItemIdx").to_string()
+        );
+        assert_eq!(
+            "When(strict: true, then: [DeleteItemAtIdx(idx: 3), SortItemsByIdx])",
+            Description::new("When")
+                .param("strict", true)
+                .nested(
+                    "then",
+                    [
+                        Description::new("DeleteItemAtIdx").param("idx", 3),
+                        Description::new("SortItemsByIdx")
+                    ]
+                )
+                .to_string()
+        );
+        assert_eq!(
+            "MapItemValue(table: 2 items, hash d54382a4d70daca1)",
+            Description::new("MapItemValue")
+                .data("table", [("CHF", 1), ("EUR", 2)])
+                .to_string()
+        );
+        assert_ne!(
+            Description::new("MapItemValue").data("table", [("CHF", 1), ("EUR", 2)]),
+            Description::new("MapItemValue").data("table", [("CHF", 1), ("EUR", 3)])
+        );
+        assert_eq!(
+            "AddItemStatic(header: Some(\"region\"))",
+            Description::new("AddItemStatic")
+                .param("header", Some("region"))
+                .to_string()
+        );
+    }
+
+    #[test]
+    fn display_pass() {
+        let pass = PassDescription {
+            stateless: vec![Description::new("DeleteItemAtIdx").param("idx", 3)],
+            ordering: Some(vec![Description::new("CompactItemIdx")]),
+            ..Default::default()
+        };
+        assert_eq!(
+            "stateless: DeleteItemAtIdx(idx: 3)\nordering: CompactItemIdx\n",
+            pass.to_string()
+        );
+        assert_eq!("", PassDescription::default().to_string());
+    }
+}
@@ -1,4 +1,6 @@
 pub mod data_cell;
 pub mod data_cell_row;
+pub mod data_cell_table;
+pub mod description;
 pub mod util;
 pub mod value;
@@ -0,0 +1,84 @@
+use std::cmp::Ordering;
+
+use venum::value::Value;
+
+use crate::errors::{Result, TransformErrors, VenumTdsError};
+
+fn aggregate_err(a: &Value, b: &Value, details: &str) -> VenumTdsError {
+    VenumTdsError::Transform(TransformErrors::Generic {
+        msg: format!("can't aggregate {:?} and {:?}: {}", a, b, details),
+    })
+}
+
+/// Adds two values of the same (numeric) type. Integer overflows are errors, not wrapped.
+pub fn checked_add(a: &Value, b: &Value) -> Result<Value> {
+    macro_rules! checked {
+        ($variant:ident, $x:expr, $y:expr) => {
+            $x.checked_add(*$y)
+                .map(Value::$variant)
+                .ok_or_else(|| aggregate_err(a, b, "overflow"))
+        };
+    }
+    match (a, b) {
+        (Value::Int8(x), Value::Int8(y)) => checked!(Int8, x, y),
+        (Value::Int16(x), Value::Int16(y)) => checked!(Int16, x, y),
+        (Value::Int32(x), Value::Int32(y)) => checked!(Int32, x, y),
+        (Value::Int64(x), Value::Int64(y)) => checked!(Int64, x, y),
+        (Value::Int128(x), Value::Int128(y)) => checked!(Int128, x, y),
+        (Value::UInt8(x), Value::UInt8(y)) => checked!(UInt8, x, y),
+        (Value::UInt16(x), Value::UInt16(y)) => checked!(UInt16, x, y),
+        (Value::UInt32(x), Value::UInt32(y)) => checked!(UInt32, x, y),
+        (Value::UInt64(x), Value::UInt64(y)) => checked!(UInt64, x, y),
+        (Value::UInt128(x), Value::UInt128(y)) => checked!(UInt128, x, y),
+        (Value::Decimal(x), Value::Decimal(y)) => checked!(Decimal, x, y),
+        (Value::Float32(x), Value::Float32(y)) => Ok(Value::Float32(x + y)),
+        (Value::Float64(x), Value::Float64(y)) => Ok(Value::Float64(x + y)),
+        _ => Err(aggregate_err(a, b, "not numeric, or of different types")),
+    }
+}
+
+/// Compares two values of the same type. (Values of different types, or a NaN, are an error.)
+pub fn compare(a: &Value, b: &Value) -> Result<Ordering> {
+    if std::mem::discriminant(a) != std::mem::discriminant(b) {
+        return Err(aggregate_err(a, b, "different types"));
+    }
+    a.partial_cmp(b)
+        .ok_or_else(|| aggregate_err(a, b, "not comparable"))
+}
+
+#[cfg(test)]
+mod tests {
+    use rust_decimal::Decimal;
+
+    use super::*;
+
+    #[test]
+    fn add_and_compare() {
+        assert_eq!(
+            Value::Int32(5),
+            checked_add(&Value::Int32(2), &Value::Int32(3)).unwrap()
+        );
+        assert_eq!(
+            Value::Decimal(Decimal::new(350, 2)),
+            checked_add(
+                &Value::Decimal(Decimal::new(125, 2)),
+                &Value::Decimal(Decimal::new(225, 2))
+            )
+            .unwrap()
+        );
+        assert!(checked_add(&Value::UInt8(200), &Value::UInt8(100)).is_err());
+        assert!(checked_add(&Value::Int32(1), &Value::Int64(1)).is_err());
+        assert!(checked_add(
+            &Value::String(String::from("1")),
+            &Value::String(String::from("1"))
+        )
+        .is_err());
+
+        assert_eq!(
+            Ordering::Less,
+            compare(&Value::Float64(1.5), &Value::Float64(2.0)).unwrap()
+        );
+        assert!(compare(&Value::Float64(f64::NAN), &Value::Float64(1.0)).is_err());
+        assert!(compare(&Value::Int32(1), &Value::Int64(1)).is_err());
+    }
+}
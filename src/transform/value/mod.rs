@@ -1 +1,5 @@
+pub mod aggregating;
+pub mod modifying;
+pub mod numbers;
+pub mod ordering;
 pub mod spliting;
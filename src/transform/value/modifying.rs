@@ -0,0 +1,244 @@
+use regex::Regex;
+use unicode_normalization::UnicodeNormalization;
+
+use crate::errors::{Result, TransformErrors, VenumTdsError};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(
+    feature = "jsonconf",
+    derive(serde::Deserialize, serde::Serialize),
+    serde(rename_all = "camelCase")
+)]
+#[cfg_attr(feature = "jsonschema", derive(schemars::JsonSchema))]
+pub enum TrimSide {
+    Left,
+    Right,
+    #[default]
+    Both,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "jsonconf",
+    derive(serde::Deserialize, serde::Serialize),
+    serde(rename_all = "camelCase")
+)]
+#[cfg_attr(feature = "jsonschema", derive(schemars::JsonSchema))]
+pub enum CaseMode {
+    Upper,
+    Lower,
+    /// The first letter of every (whitespace separated) word upper case, the rest lower case.
+    Title,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(
+    feature = "jsonconf",
+    derive(serde::Deserialize, serde::Serialize),
+    serde(rename_all = "camelCase")
+)]
+#[cfg_attr(feature = "jsonschema", derive(schemars::JsonSchema))]
+pub enum PadSide {
+    #[default]
+    Left,
+    Right,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "jsonconf",
+    derive(serde::Deserialize, serde::Serialize),
+    serde(rename_all = "camelCase")
+)]
+#[cfg_attr(feature = "jsonschema", derive(schemars::JsonSchema))]
+pub enum UnicodeForm {
+    Nfc,
+    Nfkc,
+}
+
+/// A single modification of a string. Lengths and positions are in chars, not bytes.
+#[derive(Debug, Clone)]
+pub enum StringModifier {
+    /// Trims whitespace, or, if given, the chars in `chars`.
+    Trim {
+        side: TrimSide,
+        chars: Option<Vec<char>>,
+    },
+    Case(CaseMode),
+    /// Replaces all occurrences of `from` with `to`, literally.
+    Replace {
+        from: String,
+        to: String,
+    },
+    /// Replaces all matches of the pattern. The replacement may reference capture groups, i.e.
+    /// `$1` or `${name}`.
+    ReplacePattern {
+        pattern: Regex,
+        replacement: String,
+    },
+    /// Pads to (at least) `width` chars.
+    Pad {
+        side: PadSide,
+        width: usize,
+        fill: char,
+    },
+    /// Keeps (at most) `len` chars, starting at `start`. Without `len`, keeps the rest.
+    Substring {
+        start: usize,
+        len: Option<usize>,
+    },
+    Normalize(UnicodeForm),
+    /// Replaces every run of whitespace with a single space, and trims both ends.
+    CollapseWhitespace,
+}
+
+impl StringModifier {
+    pub fn replace_pattern(pattern: &str, replacement: &str) -> Result<Self> {
+        let re = Regex::new(pattern).map_err(|e| {
+            VenumTdsError::Transform(TransformErrors::Generic {
+                msg: format!("invalid pattern {:?}: {}", pattern, e),
+            })
+        })?;
+        Ok(StringModifier::ReplacePattern {
+            pattern: re,
+            replacement: String::from(replacement),
+        })
+    }
+
+    pub fn apply(&self, s: &str) -> String {
+        match self {
+            StringModifier::Trim { side, chars } => {
+                let is_trimmed = |c: char| match chars {
+                    Some(cs) => cs.contains(&c),
+                    None => c.is_whitespace(),
+                };
+                String::from(match side {
+                    TrimSide::Left => s.trim_start_matches(is_trimmed),
+                    TrimSide::Right => s.trim_end_matches(is_trimmed),
+                    TrimSide::Both => s.trim_matches(is_trimmed),
+                })
+            }
+            StringModifier::Case(CaseMode::Upper) => s.to_uppercase(),
+            StringModifier::Case(CaseMode::Lower) => s.to_lowercase(),
+            StringModifier::Case(CaseMode::Title) => {
+                let mut res = String::with_capacity(s.len());
+                let mut word_start = true;
+                for c in s.chars() {
+                    if word_start {
+                        res.extend(c.to_uppercase());
+                    } else {
+                        res.extend(c.to_lowercase());
+                    }
+                    word_start = c.is_whitespace();
+                }
+                res
+            }
+            StringModifier::Replace { from, to } => s.replace(from.as_str(), to),
+            StringModifier::ReplacePattern {
+                pattern,
+                replacement,
+            } => pattern.replace_all(s, replacement.as_str()).into_owned(),
+            StringModifier::Pad { side, width, fill } => {
+                let len = s.chars().count();
+                if len >= *width {
+                    return String::from(s);
+                }
+                let padding = fill.to_string().repeat(width - len);
+                match side {
+                    PadSide::Left => padding + s,
+                    PadSide::Right => String::from(s) + &padding,
+                }
+            }
+            StringModifier::Substring { start, len } => {
+                let rest = s.chars().skip(*start);
+                match len {
+                    Some(l) => rest.take(*l).collect(),
+                    None => rest.collect(),
+                }
+            }
+            StringModifier::Normalize(UnicodeForm::Nfc) => s.nfc().collect(),
+            StringModifier::Normalize(UnicodeForm::Nfkc) => s.nfkc().collect(),
+            StringModifier::CollapseWhitespace => {
+                s.split_whitespace().collect::<Vec<&str>>().join(" ")
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn trim() {
+        let m = StringModifier::Trim {
+            side: TrimSide::Both,
+            chars: None,
+        };
+        assert_eq!("a b", m.apply(" \ta b\n"));
+
+        let m = StringModifier::Trim {
+            side: TrimSide::Left,
+            chars: Some(vec!['0', '_']),
+        };
+        assert_eq!("120_", m.apply("_00120_"));
+    }
+
+    #[test]
+    fn case() {
+        assert_eq!("ÄBC", StringModifier::Case(CaseMode::Upper).apply("äbc"));
+        assert_eq!(
+            "Hello  New World",
+            StringModifier::Case(CaseMode::Title).apply("hELLO  new world")
+        );
+    }
+
+    #[test]
+    fn replace() {
+        let m = StringModifier::Replace {
+            from: String::from(","),
+            to: String::from("."),
+        };
+        assert_eq!("1.5.", m.apply("1,5,"));
+
+        let m = StringModifier::replace_pattern(r"(\d{2})\.(\d{2})\.(\d{4})", "$3-$2-$1").unwrap();
+        assert_eq!("on 2022-12-31", m.apply("on 31.12.2022"));
+        assert!(StringModifier::replace_pattern("(", "").is_err());
+    }
+
+    #[test]
+    fn pad_and_substring() {
+        let m = StringModifier::Pad {
+            side: PadSide::Left,
+            width: 5,
+            fill: '0',
+        };
+        assert_eq!("00042", m.apply("42"));
+        assert_eq!("123456", m.apply("123456"));
+
+        let m = StringModifier::Substring {
+            start: 1,
+            len: Some(2),
+        };
+        assert_eq!("üc", m.apply("äüch"));
+        assert_eq!("", m.apply("a"));
+    }
+
+    #[test]
+    fn normalize_and_collapse() {
+        // "e" + combining acute accent
+        assert_eq!(
+            "\u{e9}",
+            StringModifier::Normalize(UnicodeForm::Nfc).apply("e\u{301}")
+        );
+        // the "fi" ligature is only decomposed by the compatibility form
+        assert_eq!(
+            "fi",
+            StringModifier::Normalize(UnicodeForm::Nfkc).apply("\u{fb01}")
+        );
+        assert_eq!(
+            "a b c",
+            StringModifier::CollapseWhitespace.apply(" a \t b\n\nc ")
+        );
+    }
+}
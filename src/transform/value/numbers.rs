@@ -0,0 +1,305 @@
+use rust_decimal::RoundingStrategy;
+use venum::value::Value;
+use venum::value_type::ValueType;
+
+use crate::errors::{Result, TransformErrors, VenumTdsError};
+
+fn number_err(s: &str, details: &str) -> VenumTdsError {
+    VenumTdsError::Transform(TransformErrors::Generic {
+        msg: format!("can't parse {:?} as number: {}", s, details),
+    })
+}
+
+/// How numbers are written in the source data, e.g. `"1.234,56"`, `"(12.00)"`, `"12-"`, `"$1,200"`
+/// or `"5%"`. Used to turn such strings into something `Value::from_str_and_type` understands.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "jsonconf",
+    derive(serde::Deserialize, serde::Serialize),
+    serde(rename_all = "camelCase", default)
+)]
+#[cfg_attr(feature = "jsonschema", derive(schemars::JsonSchema))]
+pub struct NumberFormat {
+    pub decimal_separator: char,
+    /// Thousands separator, e.g. `,`, `.`, `'` or a (non-breaking) space.
+    #[cfg_attr(feature = "jsonconf", serde(skip_serializing_if = "Option::is_none"))]
+    pub grouping_separator: Option<char>,
+    /// Stripped, wherever they occur, e.g. `$`, `€` or `CHF`.
+    pub currency_symbols: Vec<String>,
+    /// `(12.00)` is `-12.00`.
+    pub parenthesized_negative: bool,
+    /// `12-` is `-12`.
+    pub trailing_negative: bool,
+    /// `5%` is `0.05`. Without it, a `%` is an error.
+    pub percent: bool,
+}
+
+impl Default for NumberFormat {
+    fn default() -> Self {
+        Self {
+            decimal_separator: '.',
+            grouping_separator: None,
+            currency_symbols: Vec::new(),
+            parenthesized_negative: false,
+            trailing_negative: false,
+            percent: false,
+        }
+    }
+}
+
+impl NumberFormat {
+    /// Turns the string into a plain number, i.e. an optional `-`, digits and an optional `.`
+    /// followed by digits.
+    pub fn normalize(&self, src: &str) -> Result<String> {
+        let mut s = String::from(src.trim());
+        for sym in &self.currency_symbols {
+            s = s.replace(sym.as_str(), "");
+        }
+        let mut s = s.trim();
+
+        let mut negative = false;
+        if self.parenthesized_negative && s.starts_with('(') && s.ends_with(')') && s.len() > 1 {
+            negative = true;
+            s = s[1..s.len() - 1].trim();
+        }
+        if self.trailing_negative && s.ends_with('-') {
+            negative = !negative;
+            s = s[..s.len() - 1].trim();
+        }
+        let mut percent = false;
+        if s.ends_with('%') {
+            if !self.percent {
+                return Err(number_err(src, "unexpected '%'"));
+            }
+            percent = true;
+            s = s[..s.len() - 1].trim();
+        }
+        if let Some(rest) = s.strip_prefix('-') {
+            negative = !negative;
+            s = rest.trim();
+        } else if let Some(rest) = s.strip_prefix('+') {
+            s = rest.trim();
+        }
+
+        let mut int_part = String::with_capacity(s.len());
+        let mut frac_part: Option<String> = None;
+        for c in s.chars() {
+            if Some(c) == self.grouping_separator && frac_part.is_none() {
+                continue;
+            }
+            if c == self.decimal_separator && frac_part.is_none() {
+                frac_part = Some(String::new());
+                continue;
+            }
+            if !c.is_ascii_digit() {
+                return Err(number_err(src, &format!("unexpected {:?}", c)));
+            }
+            match frac_part.as_mut() {
+                Some(f) => f.push(c),
+                None => int_part.push(c),
+            }
+        }
+        let mut frac_part = frac_part.unwrap_or_default();
+        if int_part.is_empty() && frac_part.is_empty() {
+            return Err(number_err(src, "no digits"));
+        }
+
+        if percent {
+            // move the decimal point two places to the left, on the string, to stay exact
+            let padded = format!("{:0>3}", int_part);
+            let (int, moved) = padded.split_at(padded.len() - 2);
+            frac_part = format!("{}{}", moved, frac_part);
+            int_part = String::from(int);
+        }
+
+        let mut res = String::with_capacity(int_part.len() + frac_part.len() + 3);
+        if negative {
+            res.push('-');
+        }
+        if int_part.is_empty() {
+            res.push('0');
+        } else {
+            res.push_str(&int_part);
+        }
+        if !frac_part.is_empty() {
+            res.push('.');
+            res.push_str(&frac_part);
+        }
+        Ok(res)
+    }
+
+    /// Parses the string as a value of the given (numeric) type. A blank string is `Value::None`.
+    pub fn parse(&self, src: &str, value_type: &ValueType) -> Result<Value> {
+        if src.trim().is_empty() {
+            return Ok(Value::None);
+        }
+        Ok(Value::from_str_and_type(&self.normalize(src)?, value_type)?)
+    }
+}
+
+/// How to round to a given scale. The `Half*` modes only differ at the midpoint.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(
+    feature = "jsonconf",
+    derive(serde::Deserialize, serde::Serialize),
+    serde(rename_all = "camelCase")
+)]
+#[cfg_attr(feature = "jsonschema", derive(schemars::JsonSchema))]
+pub enum RoundingMode {
+    /// Midpoint away from zero, i.e. "commercial" rounding.
+    #[default]
+    HalfUp,
+    /// Midpoint to the nearest even number, i.e. "banker's" rounding.
+    HalfEven,
+    /// Towards zero, i.e. truncating.
+    Down,
+    /// Away from zero.
+    Up,
+    /// Towards negative infinity.
+    Floor,
+    /// Towards positive infinity.
+    Ceiling,
+}
+
+impl From<RoundingMode> for RoundingStrategy {
+    fn from(mode: RoundingMode) -> Self {
+        match mode {
+            RoundingMode::HalfUp => RoundingStrategy::MidpointAwayFromZero,
+            RoundingMode::HalfEven => RoundingStrategy::MidpointNearestEven,
+            RoundingMode::Down => RoundingStrategy::ToZero,
+            RoundingMode::Up => RoundingStrategy::AwayFromZero,
+            RoundingMode::Floor => RoundingStrategy::ToNegativeInfinity,
+            RoundingMode::Ceiling => RoundingStrategy::ToPositiveInfinity,
+        }
+    }
+}
+
+fn round_f64(f: f64, scale: u32, mode: RoundingMode) -> f64 {
+    let factor = 10_f64.powi(scale as i32);
+    let scaled = f * factor;
+    let rounded = match mode {
+        RoundingMode::HalfUp => scaled.round(),
+        RoundingMode::HalfEven => scaled.round_ties_even(),
+        RoundingMode::Down => scaled.trunc(),
+        RoundingMode::Up => scaled.abs().ceil().copysign(scaled),
+        RoundingMode::Floor => scaled.floor(),
+        RoundingMode::Ceiling => scaled.ceil(),
+    };
+    rounded / factor
+}
+
+/// Rounds a `Decimal` or float value to `scale` decimal places. `None` stays `None`. (Since
+/// floats are binary, the result is only the closest float to the rounded number.)
+pub fn round_value(val: &Value, scale: u32, mode: RoundingMode) -> Result<Value> {
+    match val {
+        Value::None => Ok(Value::None),
+        Value::Decimal(d) => Ok(Value::Decimal(
+            d.round_dp_with_strategy(scale, RoundingStrategy::from(mode)),
+        )),
+        Value::Float64(f) => Ok(Value::Float64(round_f64(*f, scale, mode))),
+        Value::Float32(f) => Ok(Value::Float32(round_f64(*f as f64, scale, mode) as f32)),
+        _ => Err(VenumTdsError::Transform(TransformErrors::Generic {
+            msg: format!("{:?} is not a Decimal or float value. Can't round.", val),
+        })),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rust_decimal::Decimal;
+
+    use super::*;
+
+    #[test]
+    fn normalize() {
+        let nf = NumberFormat {
+            decimal_separator: ',',
+            grouping_separator: Some('.'),
+            currency_symbols: vec![String::from("€"), String::from("EUR")],
+            parenthesized_negative: true,
+            trailing_negative: true,
+            percent: true,
+        };
+        assert_eq!("1234.56", nf.normalize("1.234,56").unwrap());
+        assert_eq!("-12.00", nf.normalize("(12,00)").unwrap());
+        assert_eq!("-12", nf.normalize("12-").unwrap());
+        assert_eq!("-1200", nf.normalize("- € 1.200").unwrap());
+        assert_eq!("1200", nf.normalize("1.200 EUR").unwrap());
+        assert_eq!("0.05", nf.normalize("5%").unwrap());
+        assert_eq!("0.125", nf.normalize("12,5 %").unwrap());
+        assert_eq!("1.50", nf.normalize("150%").unwrap());
+        assert!(nf.normalize("12x").is_err());
+        assert!(nf.normalize("-").is_err());
+
+        let nf = NumberFormat {
+            grouping_separator: Some(','),
+            currency_symbols: vec![String::from("$")],
+            ..NumberFormat::default()
+        };
+        assert_eq!("1200", nf.normalize("$1,200").unwrap());
+        assert!(nf.normalize("5%").is_err());
+        assert!(nf.normalize("(5)").is_err());
+    }
+
+    #[test]
+    fn parse() {
+        let nf = NumberFormat {
+            decimal_separator: ',',
+            grouping_separator: Some('\''),
+            ..NumberFormat::default()
+        };
+        assert_eq!(
+            Value::Decimal(Decimal::new(-123456, 2)),
+            nf.parse("-1'234,56", &ValueType::Decimal).unwrap()
+        );
+        assert_eq!(
+            Value::Float64(1234.5),
+            nf.parse("1'234,5", &ValueType::Float64).unwrap()
+        );
+        assert_eq!(Value::None, nf.parse(" ", &ValueType::Float64).unwrap());
+    }
+
+    #[test]
+    fn round() {
+        let d = Value::Decimal(Decimal::new(-12345, 3)); // -12.345
+        let rounded = |mode| round_value(&d, 2, mode).unwrap();
+        assert_eq!(
+            Value::Decimal(Decimal::new(-1235, 2)),
+            rounded(RoundingMode::HalfUp)
+        );
+        assert_eq!(
+            Value::Decimal(Decimal::new(-1234, 2)),
+            rounded(RoundingMode::HalfEven)
+        );
+        assert_eq!(
+            Value::Decimal(Decimal::new(-1234, 2)),
+            rounded(RoundingMode::Down)
+        );
+        assert_eq!(
+            Value::Decimal(Decimal::new(-1235, 2)),
+            rounded(RoundingMode::Up)
+        );
+        assert_eq!(
+            Value::Decimal(Decimal::new(-1235, 2)),
+            rounded(RoundingMode::Floor)
+        );
+        assert_eq!(
+            Value::Decimal(Decimal::new(-1234, 2)),
+            rounded(RoundingMode::Ceiling)
+        );
+
+        assert_eq!(
+            Value::Float64(2.5),
+            round_value(&Value::Float64(2.45), 1, RoundingMode::Up).unwrap()
+        );
+        assert_eq!(
+            Value::Float64(2.0),
+            round_value(&Value::Float64(2.5), 0, RoundingMode::HalfEven).unwrap()
+        );
+        assert_eq!(
+            Value::None,
+            round_value(&Value::None, 0, RoundingMode::HalfUp).unwrap()
+        );
+        assert!(round_value(&Value::Int32(1), 0, RoundingMode::HalfUp).is_err());
+    }
+}
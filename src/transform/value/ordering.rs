@@ -0,0 +1,408 @@
+use std::cmp::Ordering;
+use std::iter::Peekable;
+use std::str::Chars;
+
+use chrono::NaiveDateTime;
+use rust_decimal::{prelude::ToPrimitive, Decimal};
+use venum::value::Value;
+
+/// How strings (and chars) are compared. The default is a plain (byte wise) comparison.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(
+    feature = "jsonconf",
+    derive(serde::Deserialize, serde::Serialize),
+    serde(rename_all = "camelCase", default)
+)]
+#[cfg_attr(feature = "jsonschema", derive(schemars::JsonSchema))]
+pub struct Collation {
+    /// Ignore the case, i.e. `"apple"` and `"Apple"` are equal.
+    pub case_insensitive: bool,
+    /// Compare runs of digits by their numeric value, i.e. `"file2"` comes before `"file10"`.
+    pub natural: bool,
+}
+
+impl Collation {
+    pub fn compare(&self, a: &str, b: &str) -> Ordering {
+        match (self.natural, self.case_insensitive) {
+            (false, false) => a.cmp(b),
+            (false, true) => a
+                .chars()
+                .flat_map(char::to_lowercase)
+                .cmp(b.chars().flat_map(char::to_lowercase)),
+            (true, _) => self.natural_compare(a, b),
+        }
+    }
+
+    fn natural_compare(&self, a: &str, b: &str) -> Ordering {
+        let mut a = a.chars().peekable();
+        let mut b = b.chars().peekable();
+        loop {
+            let ord = match (a.peek(), b.peek()) {
+                (None, None) => return Ordering::Equal,
+                (None, Some(_)) => return Ordering::Less,
+                (Some(_), None) => return Ordering::Greater,
+                (Some(x), Some(y)) if x.is_ascii_digit() && y.is_ascii_digit() => {
+                    compare_digits(&take_digits(&mut a), &take_digits(&mut b))
+                }
+                (Some(_), Some(_)) => {
+                    // we just peeked, so there is a next char
+                    let (x, y) = (a.next().unwrap(), b.next().unwrap());
+                    if self.case_insensitive {
+                        x.to_lowercase().cmp(y.to_lowercase())
+                    } else {
+                        x.cmp(&y)
+                    }
+                }
+            };
+            if ord != Ordering::Equal {
+                return ord;
+            }
+        }
+    }
+}
+
+fn take_digits(chars: &mut Peekable<Chars>) -> String {
+    let mut digits = String::new();
+    while let Some(c) = chars.next_if(char::is_ascii_digit) {
+        digits.push(c);
+    }
+    digits
+}
+
+/// Compares two runs of digits by their value, without parsing them (they can be arbitrarily
+/// long). On the same value, the one with fewer leading zeros comes first.
+fn compare_digits(a: &str, b: &str) -> Ordering {
+    let (a_trimmed, b_trimmed) = (a.trim_start_matches('0'), b.trim_start_matches('0'));
+    a_trimmed
+        .len()
+        .cmp(&b_trimmed.len())
+        .then_with(|| a_trimmed.cmp(b_trimmed))
+        .then_with(|| a.len().cmp(&b.len()))
+}
+
+/// A numeric value, widened so that we can compare numbers of different types.
+enum Number {
+    Int(i128),
+    UInt(u128),
+    Float(f64),
+    Decimal(Decimal),
+}
+
+impl Number {
+    fn from_value(val: &Value) -> Option<Self> {
+        match val {
+            Value::Int8(i) => Some(Number::Int(i128::from(*i))),
+            Value::Int16(i) => Some(Number::Int(i128::from(*i))),
+            Value::Int32(i) => Some(Number::Int(i128::from(*i))),
+            Value::Int64(i) => Some(Number::Int(i128::from(*i))),
+            Value::Int128(i) => Some(Number::Int(*i)),
+            Value::UInt8(u) => Some(Number::UInt(u128::from(*u))),
+            Value::UInt16(u) => Some(Number::UInt(u128::from(*u))),
+            Value::UInt32(u) => Some(Number::UInt(u128::from(*u))),
+            Value::UInt64(u) => Some(Number::UInt(u128::from(*u))),
+            Value::UInt128(u) => Some(Number::UInt(*u)),
+            Value::Float32(f) => Some(Number::Float(f64::from(*f))),
+            Value::Float64(f) => Some(Number::Float(*f)),
+            Value::Decimal(d) => Some(Number::Decimal(*d)),
+            _ => None,
+        }
+    }
+
+    fn as_f64(&self) -> f64 {
+        match self {
+            Number::Int(i) => *i as f64,
+            Number::UInt(u) => *u as f64,
+            Number::Float(f) => *f,
+            Number::Decimal(d) => d.to_f64().unwrap_or(f64::NAN),
+        }
+    }
+
+    fn compare(&self, other: &Number) -> Ordering {
+        match (self, other) {
+            (Number::Int(a), Number::Int(b)) => a.cmp(b),
+            (Number::UInt(a), Number::UInt(b)) => a.cmp(b),
+            (Number::Decimal(a), Number::Decimal(b)) => a.cmp(b),
+            (Number::Int(a), Number::UInt(b)) => match u128::try_from(*a) {
+                Ok(a) => a.cmp(b),
+                Err(_) => Ordering::Less,
+            },
+            (Number::UInt(_), Number::Int(_)) => other.compare(self).reverse(),
+            (Number::Int(i), Number::Decimal(d)) => compare_int_decimal(*i, d),
+            (Number::UInt(u), Number::Decimal(d)) => match i128::try_from(*u) {
+                Ok(i) => compare_int_decimal(i, d),
+                // way out of the range of a decimal
+                Err(_) => Ordering::Greater,
+            },
+            (Number::Decimal(_), Number::Int(_) | Number::UInt(_)) => other.compare(self).reverse(),
+            (Number::Int(i), Number::Float(f)) => compare_int_float(*i, *f),
+            (Number::UInt(u), Number::Float(f)) => compare_uint_float(*u, *f),
+            (Number::Float(_), Number::Int(_) | Number::UInt(_)) => other.compare(self).reverse(),
+            (Number::Float(_), _) | (_, Number::Float(_)) => {
+                compare_floats(self.as_f64(), other.as_f64())
+            }
+        }
+    }
+}
+
+fn compare_int_decimal(i: i128, d: &Decimal) -> Ordering {
+    match Decimal::try_from_i128_with_scale(i, 0) {
+        Ok(i) => i.cmp(d),
+        // out of the range of a decimal, so bigger (or smaller) than any
+        Err(_) if i > 0 => Ordering::Greater,
+        Err(_) => Ordering::Less,
+    }
+}
+
+/// Compares an integer with a float exactly, i.e. with the integral part of the float first, then
+/// with its fraction. (Going through `f64` would lose precision above 2^53, so e.g. 2^53 + 1 would
+/// be equal to the float 2^53, but greater than the integer 2^53.) NaN is greater than any number.
+fn compare_int_float(i: i128, f: f64) -> Ordering {
+    if f.is_nan() {
+        return Ordering::Less;
+    }
+    let integral = f.trunc();
+    // (-2^127 is exact as a float, the infinities end up here, too)
+    if integral >= -(i128::MIN as f64) {
+        return Ordering::Less;
+    }
+    if integral < i128::MIN as f64 {
+        return Ordering::Greater;
+    }
+    i.cmp(&(integral as i128))
+        .then_with(|| compare_floats(0.0, f - integral))
+}
+
+/// See `compare_int_float`.
+fn compare_uint_float(u: u128, f: f64) -> Ordering {
+    if f.is_nan() {
+        return Ordering::Less;
+    }
+    let integral = f.trunc();
+    if integral < 0.0 {
+        return Ordering::Greater;
+    }
+    // (u128::MAX rounds up to 2^128)
+    if integral >= u128::MAX as f64 {
+        return Ordering::Less;
+    }
+    u.cmp(&(integral as u128))
+        .then_with(|| compare_floats(0.0, f - integral))
+}
+
+/// Compares floats, with NaN being greater than any other number (and equal to NaN).
+fn compare_floats(a: f64, b: f64) -> Ordering {
+    a.partial_cmp(&b)
+        .unwrap_or_else(|| a.is_nan().cmp(&b.is_nan()))
+}
+
+/// The instant of a date/time value, a date being its midnight and a `DateTime` in UTC.
+fn as_naive_utc(val: &Value) -> Option<NaiveDateTime> {
+    match val {
+        Value::NaiveDate(d) => d.and_hms_opt(0, 0, 0),
+        Value::NaiveDateTime(dt) => Some(*dt),
+        Value::DateTime(dt) => Some(dt.naive_utc()),
+        _ => None,
+    }
+}
+
+/// The rank of the kind of a value, for ordering values that can't be compared with each other.
+fn kind_rank(val: &Value) -> u8 {
+    match val {
+        Value::None => 0,
+        Value::Bool(_) => 1,
+        Value::Char(_) | Value::String(_) => 3,
+        Value::NaiveDate(_) | Value::NaiveDateTime(_) | Value::DateTime(_) => 4,
+        _ => 2, // numbers
+    }
+}
+
+/// A total order over values of any type, unlike the derived `PartialOrd`:
+/// - numbers compare by value, across types. NaN is greater than any other number.
+/// - chars and strings compare as strings, using the collation.
+/// - dates and date times compare as instants, a date being its midnight and a `DateTime` in UTC.
+/// - otherwise: `None < Bool < numbers < strings < dates`.
+pub fn compare_values(a: &Value, b: &Value, collation: &Collation) -> Ordering {
+    match (a, b) {
+        (Value::Bool(x), Value::Bool(y)) => x.cmp(y),
+        (Value::String(x), Value::String(y)) => collation.compare(x, y),
+        (Value::Char(x), Value::Char(y)) => {
+            collation.compare(x.encode_utf8(&mut [0; 4]), y.encode_utf8(&mut [0; 4]))
+        }
+        (Value::Char(x), Value::String(y)) => collation.compare(x.encode_utf8(&mut [0; 4]), y),
+        (Value::String(x), Value::Char(y)) => collation.compare(x, y.encode_utf8(&mut [0; 4])),
+        _ => match (Number::from_value(a), Number::from_value(b)) {
+            (Some(x), Some(y)) => x.compare(&y),
+            _ => match (as_naive_utc(a), as_naive_utc(b)) {
+                (Some(x), Some(y)) => x.cmp(&y),
+                _ => kind_rank(a).cmp(&kind_rank(b)),
+            },
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{FixedOffset, NaiveDate, TimeZone};
+
+    use super::*;
+
+    fn s(s: &str) -> Value {
+        Value::String(String::from(s))
+    }
+
+    #[test]
+    fn compare_across_types() {
+        let binary = Collation::default();
+        assert_eq!(
+            Ordering::Less,
+            compare_values(&Value::Int8(-1), &Value::UInt64(0), &binary)
+        );
+        assert_eq!(
+            Ordering::Equal,
+            compare_values(
+                &Value::Int32(2),
+                &Value::Decimal(Decimal::new(200, 2)),
+                &binary
+            )
+        );
+        assert_eq!(
+            Ordering::Greater,
+            compare_values(&Value::Float64(2.5), &Value::UInt8(2), &binary)
+        );
+        assert_eq!(
+            Ordering::Greater,
+            compare_values(
+                &Value::Float64(f64::NAN),
+                &Value::Int128(i128::MAX),
+                &binary
+            )
+        );
+        assert_eq!(
+            Ordering::Less,
+            compare_values(
+                &Value::Int128(i128::MIN),
+                &Value::Decimal(Decimal::MIN),
+                &binary
+            )
+        );
+        assert_eq!(
+            Ordering::Less,
+            compare_values(&Value::Char('a'), &s("ab"), &binary)
+        );
+
+        let date = NaiveDate::from_ymd_opt(2022, 3, 1).unwrap();
+        let date_time = FixedOffset::east_opt(3600)
+            .unwrap()
+            .with_ymd_and_hms(2022, 3, 1, 0, 30, 0)
+            .unwrap();
+        // 00:30 at +01:00 is the day before in UTC
+        assert_eq!(
+            Ordering::Greater,
+            compare_values(
+                &Value::NaiveDate(date),
+                &Value::DateTime(date_time),
+                &binary
+            )
+        );
+
+        assert_eq!(
+            Ordering::Less,
+            compare_values(&Value::None, &Value::Bool(false), &binary)
+        );
+        assert_eq!(
+            Ordering::Less,
+            compare_values(&Value::Int8(100), &s("1"), &binary)
+        );
+        assert_eq!(
+            Ordering::Less,
+            compare_values(&s("z"), &Value::NaiveDate(date), &binary)
+        );
+    }
+
+    #[test]
+    fn compare_ints_and_floats_exactly() {
+        let binary = Collation::default();
+        let cmp = |a: &Value, b: &Value| compare_values(a, b, &binary);
+        let int = Value::Int64((1 << 53) + 1);
+        let float = Value::Float64((1_u64 << 53) as f64);
+        let smaller_int = Value::UInt64(1 << 53);
+
+        // int > float == smaller int, so int > smaller int, too
+        assert_eq!(Ordering::Greater, cmp(&int, &float));
+        assert_eq!(Ordering::Less, cmp(&float, &int));
+        assert_eq!(Ordering::Equal, cmp(&float, &smaller_int));
+        assert_eq!(Ordering::Equal, cmp(&smaller_int, &float));
+        assert_eq!(Ordering::Greater, cmp(&int, &smaller_int));
+
+        // the fraction decides, if the integral parts are equal
+        assert_eq!(Ordering::Less, cmp(&Value::Int8(-3), &Value::Float32(-2.5)));
+        assert_eq!(
+            Ordering::Greater,
+            cmp(&Value::UInt8(0), &Value::Float64(-0.5))
+        );
+        assert_eq!(Ordering::Equal, cmp(&Value::Int8(0), &Value::Float64(-0.0)));
+
+        // out of the range of the integers
+        assert_eq!(
+            Ordering::Less,
+            cmp(&Value::Int128(i128::MAX), &Value::Float64(1e39))
+        );
+        assert_eq!(
+            Ordering::Greater,
+            cmp(
+                &Value::Int128(i128::MIN),
+                &Value::Float64(f64::NEG_INFINITY)
+            )
+        );
+        assert_eq!(
+            Ordering::Less,
+            cmp(&Value::UInt128(u128::MAX), &Value::Float64(f64::INFINITY))
+        );
+        assert_eq!(
+            Ordering::Greater,
+            cmp(&Value::UInt128(u128::MAX), &Value::Float64(3e38))
+        );
+        assert_eq!(
+            Ordering::Less,
+            cmp(&Value::UInt128(0), &Value::Float64(f64::NAN))
+        );
+    }
+
+    #[test]
+    fn collations() {
+        let binary = Collation::default();
+        let case_insensitive = Collation {
+            case_insensitive: true,
+            natural: false,
+        };
+        let natural = Collation {
+            case_insensitive: false,
+            natural: true,
+        };
+        assert_eq!(Ordering::Less, binary.compare("B", "a"));
+        assert_eq!(Ordering::Greater, case_insensitive.compare("B", "a"));
+        assert_eq!(Ordering::Equal, case_insensitive.compare("Apple", "aPPLE"));
+
+        assert_eq!(Ordering::Greater, binary.compare("file10", "file2"));
+        assert_eq!(Ordering::Less, natural.compare("file2", "file10"));
+        assert_eq!(Ordering::Less, natural.compare("file2", "file02"));
+        assert_eq!(Ordering::Less, natural.compare("file2", "file2a"));
+        assert_eq!(Ordering::Greater, natural.compare("v1.10", "v1.9"));
+        assert_eq!(
+            Ordering::Less,
+            natural.compare(
+                "x99999999999999999999999999999999999999",
+                "x100000000000000000000000000000000000000"
+            )
+        );
+        assert_eq!(Ordering::Less, natural.compare("B2", "a1"));
+        assert_eq!(
+            Ordering::Greater,
+            Collation {
+                case_insensitive: true,
+                natural: true
+            }
+            .compare("B2", "a1")
+        );
+    }
+}
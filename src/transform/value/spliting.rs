@@ -3,14 +3,28 @@ use std::fmt::Debug;
 use regex::Regex;
 use venum::value::Value;
 
+#[cfg(feature = "jsonconf")]
+use crate::conf::jsonconf::SplitterType;
 use crate::errors::{Result, SplitError, TransformErrors, VenumTdsError};
 
 pub trait ValueSplit: Debug {
     fn split(&self, src: &Value) -> Result<(Value, Value)>;
+
+    /// The `spec` of a `splitItem` config, if this is a splitter it builds.
+    #[cfg(feature = "jsonconf")]
+    fn to_config(&self) -> Option<SplitterType> {
+        None
+    }
 }
 
 pub trait ValueSplitN: Debug {
     fn split_n(&self, src: &Value) -> Result<Vec<Value>>;
+
+    /// The `sepChar` of an `explodeItem` config, if this is the splitter it builds.
+    #[cfg(feature = "jsonconf")]
+    fn sep_char(&self) -> Option<char> {
+        None
+    }
 }
 
 #[derive(Debug, PartialEq, Eq)]
@@ -59,6 +73,14 @@ impl ValueSplit for ValueStringSeparatorCharSplit {
             )))
         }
     }
+
+    #[cfg(feature = "jsonconf")]
+    fn to_config(&self) -> Option<SplitterType> {
+        Some(SplitterType::SeparatorChar {
+            char: self.sep_char,
+            split_none: Some(self.split_none),
+        })
+    }
 }
 
 #[derive(Debug, PartialEq, Eq)]
@@ -118,6 +140,39 @@ impl ValueSplitN for ValueStringSeparatorCharSplitN {
     }
 }
 
+/// Splits into however many tokens there are, even just one. Tokens are trimmed and empty ones are
+/// skipped, so `None`, `""` and `" , "` all give no tokens at all. (Meant for exploding rows, e.g.
+/// one row per tag, where a single token is as fine as many, and none at all is fine, too.)
+#[derive(Debug, PartialEq, Eq)]
+pub struct ValueStringSeparatorCharTokens {
+    pub sep_char: char,
+}
+
+impl ValueSplitN for ValueStringSeparatorCharTokens {
+    fn split_n(&self, src: &Value) -> Result<Vec<Value>> {
+        match src {
+            Value::None => Ok(Vec::new()),
+            Value::String(s) => Ok(s
+                .split(self.sep_char)
+                .map(str::trim)
+                .filter(|t| !t.is_empty())
+                .map(|t| Value::from(String::from(t)))
+                .collect()),
+            _ => Err(VenumTdsError::Transform(TransformErrors::Split(
+                SplitError::new(
+                    String::from("Not a Value::String. Can't split."),
+                    src.clone(),
+                ),
+            ))),
+        }
+    }
+
+    #[cfg(feature = "jsonconf")]
+    fn sep_char(&self) -> Option<char> {
+        Some(self.sep_char)
+    }
+}
+
 #[derive(Debug)]
 pub struct ValueStringRegexPairSplit {
     re: Regex,
@@ -187,6 +242,14 @@ impl ValueSplit for ValueStringRegexPairSplit {
             )))
         }
     }
+
+    #[cfg(feature = "jsonconf")]
+    fn to_config(&self) -> Option<SplitterType> {
+        Some(SplitterType::Pattern {
+            pattern: String::from(self.re.as_str()),
+            split_none: Some(self.split_none),
+        })
+    }
 }
 
 #[cfg(test)]
@@ -296,6 +359,21 @@ mod tests {
         sep.split_n(&data).unwrap();
     }
 
+    #[test]
+    fn split_into_tokens() {
+        let sep = ValueStringSeparatorCharTokens { sep_char: ',' };
+        assert_eq!(
+            vec![Value::from("foo".to_string()), Value::from("bar".to_string())],
+            sep.split_n(&Value::from("foo, ,bar ".to_string())).unwrap()
+        );
+        assert_eq!(
+            vec![Value::from("foo".to_string())],
+            sep.split_n(&Value::from("foo".to_string())).unwrap()
+        );
+        assert!(sep.split_n(&Value::None).unwrap().is_empty());
+        assert!(sep.split_n(&Value::Int32(1)).is_err());
+    }
+
     #[test]
     fn split_by_regex_pair() {
         let sep_res =
@@ -0,0 +1,44 @@
+use venum::value::Value;
+
+/// Stringifies the inner value of a `Value`. (The `Display` of `Value` only gives us the name of the variant.)
+/// `Value::None` becomes an empty `String`.
+pub fn value_to_string(val: &Value) -> String {
+    match val {
+        Value::None => String::new(),
+        Value::Bool(b) => b.to_string(),
+        Value::Char(c) => c.to_string(),
+        Value::String(s) => s.clone(),
+        Value::Int8(i) => i.to_string(),
+        Value::Int16(i) => i.to_string(),
+        Value::Int32(i) => i.to_string(),
+        Value::Int64(i) => i.to_string(),
+        Value::Int128(i) => i.to_string(),
+        Value::UInt8(u) => u.to_string(),
+        Value::UInt16(u) => u.to_string(),
+        Value::UInt32(u) => u.to_string(),
+        Value::UInt64(u) => u.to_string(),
+        Value::UInt128(u) => u.to_string(),
+        Value::Float32(f) => f.to_string(),
+        Value::Float64(f) => f.to_string(),
+        Value::Decimal(d) => d.to_string(),
+        Value::NaiveDate(d) => d.to_string(),
+        Value::NaiveDateTime(dt) => dt.to_string(),
+        Value::DateTime(dt) => dt.to_rfc3339(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use venum::value::Value;
+
+    use super::*;
+
+    #[test]
+    fn stringify_values() {
+        assert_eq!("", value_to_string(&Value::None));
+        assert_eq!("true", value_to_string(&Value::Bool(true)));
+        assert_eq!("-12", value_to_string(&Value::Int16(-12)));
+        assert_eq!("10.5", value_to_string(&Value::Float64(10.5)));
+        assert_eq!("CHF", value_to_string(&Value::String(String::from("CHF"))));
+    }
+}